@@ -0,0 +1,41 @@
+//! Replay a consensus trace written by `overlord::record::Recorder`.
+//!
+//! Usage: `cargo run --example replay -- <trace> [--step]`. With `--step`, the replay waits for
+//! Enter before each input.
+
+use std::io::{self, BufRead};
+
+use overlord::record::Replay;
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let step_by_step = args.iter().any(|arg| arg == "--step");
+    let path = match args.iter().find(|arg| !arg.starts_with("--")) {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: replay <trace> [--step]");
+            std::process::exit(1);
+        }
+    };
+
+    let mut replay = match Replay::load(path) {
+        Ok(replay) => replay,
+        Err(e) => {
+            eprintln!("load {} error: {}", path, e);
+            std::process::exit(1);
+        }
+    };
+    println!("replay {} inputs of {}", replay.remaining(), path);
+
+    let stdin = io::stdin();
+    while let Some(step) = replay.step().await {
+        println!("{}", step);
+        if step_by_step {
+            let mut line = String::new();
+            if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 {
+                break;
+            }
+        }
+    }
+}
@@ -18,7 +18,9 @@ use serde::{Deserialize, Serialize};
 
 use overlord::error::ConsensusError;
 use overlord::types::{Commit, Hash, Node, OverlordMsg, Status, ViewChangeReason};
-use overlord::{Codec, Consensus, Crypto, DurationConfig, Overlord, OverlordHandler, Wal};
+use overlord::{
+    Codec, Consensus, Crypto, DurationConfig, Overlord, OverlordConfig, OverlordHandler, Wal,
+};
 
 lazy_static! {
     static ref HASHER_INST: HasherKeccak = HasherKeccak::new();
@@ -272,6 +274,11 @@ impl Speaker {
             Arc::clone(&brain),
             Arc::new(crypto),
             Arc::new(MockWal::new()),
+            OverlordConfig::builder()
+                .interval(SPEECH_INTERVAL)
+                .timer_config(timer_config())
+                .build()
+                .unwrap(),
         );
         let overlord_handler = overlord.get_handler();
 
@@ -294,12 +301,7 @@ impl Speaker {
         }
     }
 
-    async fn run(
-        &self,
-        interval: u64,
-        timer_config: Option<DurationConfig>,
-        speaker_list: Vec<Node>,
-    ) -> Result<(), Box<dyn Error + Send>> {
+    async fn run(&self, speaker_list: Vec<Node>) -> Result<(), Box<dyn Error + Send>> {
         let brain = Arc::<Brain>::clone(&self.brain);
         let handler = self.handler.clone();
 
@@ -331,10 +333,7 @@ impl Speaker {
             }
         });
 
-        self.overlord
-            .run(0, interval, speaker_list, timer_config)
-            .await
-            .unwrap();
+        self.overlord.run(0, speaker_list).await.unwrap();
 
         Ok(())
     }
@@ -375,10 +374,7 @@ async fn main() {
 
         let list = auth_list.clone();
         tokio::spawn(async move {
-            speaker
-                .run(SPEECH_INTERVAL, timer_config(), list)
-                .await
-                .unwrap();
+            speaker.run(list).await.unwrap();
         });
     }
 
@@ -396,6 +392,6 @@ fn hash(bytes: &Bytes) -> Bytes {
     BytesMut::from(&out[..]).freeze()
 }
 
-fn timer_config() -> Option<DurationConfig> {
-    Some(DurationConfig::new(10, 10, 10, 3))
+fn timer_config() -> DurationConfig {
+    DurationConfig::new(10, 10, 10, 3)
 }
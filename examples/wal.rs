@@ -0,0 +1,123 @@
+//! Inspect and repair a wal blob saved by overlord.
+//!
+//! Usage:
+//!
+//! - `cargo run --example wal -- inspect <wal> [--authority <list>]` decodes the wal into JSON and
+//!   checks its quorum certificates against the authority list, a JSON array of `Node`.
+//! - `cargo run --example wal -- reset <wal> [--authority <list>]` resets the wal to the propose
+//!   step of the next round of its height without a lock after confirmation, and keeps the
+//!   original wal in `<wal>.bak`.
+//!
+//! The block in the lock is shown as the bytes encoded by the application. The aggregated
+//! signatures are not checked since this tool does not know the crypto of the chain, build it with
+//! the chain's `Crypto` passed to `WalInfo::verify` to check them.
+
+use std::error::Error;
+use std::fs;
+use std::io::{self, BufRead, Write};
+
+use bytes::Bytes;
+
+use overlord::types::{Address, Hash, Node, RawBlock, Signature};
+use overlord::{Crypto, SignDomain, WalInfo};
+
+/// A crypto which only takes the voters and their weights into account.
+struct SkipSignature;
+
+impl Crypto for SkipSignature {
+    fn hash(&self, msg: Bytes) -> Hash {
+        msg
+    }
+
+    fn sign(&self, _hash: Hash) -> Result<Signature, Box<dyn Error + Send>> {
+        Ok(Signature::new())
+    }
+
+    fn aggregate_signatures(
+        &self,
+        _signatures: Vec<Signature>,
+        _voters: Vec<Address>,
+    ) -> Result<Signature, Box<dyn Error + Send>> {
+        Ok(Signature::new())
+    }
+
+    fn verify_signature(
+        &self,
+        _signature: Signature,
+        _hash: Hash,
+        _voter: Address,
+    ) -> Result<(), Box<dyn Error + Send>> {
+        Ok(())
+    }
+
+    fn verify_aggregated_signature(
+        &self,
+        _aggregate_signature: Signature,
+        _msg_hash: Hash,
+        _voters: Vec<Address>,
+    ) -> Result<(), Box<dyn Error + Send>> {
+        Ok(())
+    }
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("error: {}", e);
+        if let Some(source) = e.source() {
+            eprintln!("caused by: {}", source);
+        }
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let (command, path) = match (args.first(), args.get(1)) {
+        (Some(command), Some(path)) => (command.as_str(), path),
+        _ => return Err("usage: wal <inspect|reset> <wal> [--authority <list>]".into()),
+    };
+    let authority_list = match args.iter().position(|arg| arg == "--authority") {
+        Some(index) => {
+            let list = args.get(index + 1).ok_or("no authority list is given")?;
+            Some(serde_json::from_slice::<Vec<Node>>(&fs::read(list)?)?)
+        }
+        None => None,
+    };
+
+    let data = fs::read(path)?;
+    let wal = WalInfo::<RawBlock>::from_bytes(&data)?;
+    println!("{}", serde_json::to_string_pretty(&wal)?);
+    if let Some(list) = authority_list.as_ref() {
+        match wal.verify(list, &SkipSignature, &SignDomain::Legacy) {
+            Ok(()) => println!("the quorum certificates are valid, signatures unchecked"),
+            Err(e) => println!("the quorum certificates are invalid: {}", e),
+        }
+    }
+
+    match command {
+        "inspect" => Ok(()),
+        "reset" => reset(path, &data, &wal),
+        _ => Err(format!("unknown command {}", command).into()),
+    }
+}
+
+fn reset(path: &str, data: &[u8], wal: &WalInfo<RawBlock>) -> Result<(), Box<dyn Error>> {
+    let reset = wal.reset();
+    println!("reset to:\n{}", serde_json::to_string_pretty(&reset)?);
+    println!("dropping the lock may let this node vote for a conflicting block");
+    print!("type \"yes\" to reset {}: ", path);
+    io::stdout().flush()?;
+
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line)?;
+    if line.trim() != "yes" {
+        println!("aborted");
+        return Ok(());
+    }
+
+    let backup = format!("{}.bak", path);
+    fs::write(&backup, data)?;
+    fs::write(path, reset.to_bytes())?;
+    println!("reset {}, the original wal is kept in {}", path, backup);
+    Ok(())
+}
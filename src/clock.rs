@@ -0,0 +1,162 @@
+use std::fmt::Debug;
+use std::time::{Duration, Instant};
+
+use futures::channel::oneshot::{channel, Sender};
+use futures::future::{self, BoxFuture, FutureExt};
+use parking_lot::Mutex;
+
+/// Trait for the time source of the overlord consensus. The timer, the state and the retries of
+/// the application callbacks get the current instant and sleep by the clock, so a consensus run
+/// can be reproduced and fast-forwarded with a manual clock.
+pub trait Clock: Debug + Send + Sync {
+    /// Get the current instant.
+    fn now(&self) -> Instant;
+
+    /// Return a future that completes after the given duration.
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+}
+
+/// A clock that follows the real time. It is the default clock of overlord.
+#[derive(Clone, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        tokio::time::sleep(duration).boxed()
+    }
+}
+
+/// A clock that only moves when it is advanced. The sleeps complete when the clock is advanced
+/// past their deadlines.
+#[derive(Debug)]
+pub struct ManualClock {
+    start: Instant,
+    inner: Mutex<ManualInner>,
+}
+
+#[derive(Debug, Default)]
+struct ManualInner {
+    elapsed: Duration,
+    sleeps: Vec<(Duration, Sender<()>)>,
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        ManualClock::new()
+    }
+}
+
+impl ManualClock {
+    /// Create a manual clock which starts from now.
+    pub fn new() -> Self {
+        ManualClock {
+            start: Instant::now(),
+            inner: Mutex::new(ManualInner::default()),
+        }
+    }
+
+    /// Get the time that the clock has been advanced.
+    pub fn elapsed(&self) -> Duration {
+        self.inner.lock().elapsed
+    }
+
+    /// Get the number of the pending sleeps.
+    pub fn pending(&self) -> usize {
+        let mut inner = self.inner.lock();
+        inner.sleeps.retain(|(_, tx)| !tx.is_canceled());
+        inner.sleeps.len()
+    }
+
+    /// Get the time since the clock started at which the earliest pending sleep is due.
+    pub fn next_deadline(&self) -> Option<Duration> {
+        let mut inner = self.inner.lock();
+        inner.sleeps.retain(|(_, tx)| !tx.is_canceled());
+        inner.sleeps.iter().map(|(deadline, _)| *deadline).min()
+    }
+
+    /// Advance the clock by the given duration and complete the sleeps that are due.
+    pub fn advance(&self, duration: Duration) {
+        let mut inner = self.inner.lock();
+        inner.elapsed += duration;
+        inner.wake();
+    }
+
+    /// Advance the clock to the earliest deadline of the pending sleeps and complete the sleeps
+    /// that are due. Return the advanced duration, or `None` if there is no pending sleep.
+    pub fn advance_to_next(&self) -> Option<Duration> {
+        let deadline = self.next_deadline()?;
+        let mut inner = self.inner.lock();
+        let advanced = deadline.saturating_sub(inner.elapsed);
+        inner.elapsed = inner.elapsed.max(deadline);
+        inner.wake();
+        Some(advanced)
+    }
+}
+
+impl ManualInner {
+    fn wake(&mut self) {
+        let elapsed = self.elapsed;
+        let (due, pending) = self
+            .sleeps
+            .drain(..)
+            .partition::<Vec<_>, _>(|(deadline, _)| *deadline <= elapsed);
+        self.sleeps = pending;
+        for (_, tx) in due {
+            let _ = tx.send(());
+        }
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        if duration.is_zero() {
+            return future::ready(()).boxed();
+        }
+
+        let (tx, rx) = channel();
+        let mut inner = self.inner.lock();
+        let deadline = inner.elapsed + duration;
+        inner.sleeps.push((deadline, tx));
+        rx.map(|_| ()).boxed()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use futures::FutureExt;
+
+    use crate::clock::{Clock, ManualClock};
+
+    #[test]
+    fn test_manual_clock() {
+        let clock = ManualClock::new();
+        let start = clock.now();
+        let mut short = clock.sleep(Duration::from_millis(100));
+        let mut long = clock.sleep(Duration::from_millis(300));
+        let cancelled = clock.sleep(Duration::from_millis(50));
+        drop(cancelled);
+        assert!(clock.sleep(Duration::ZERO).now_or_never().is_some());
+        assert_eq!(clock.pending(), 2);
+
+        clock.advance(Duration::from_millis(150));
+        assert_eq!(clock.now() - start, Duration::from_millis(150));
+        assert!((&mut short).now_or_never().is_some());
+        assert!((&mut long).now_or_never().is_none());
+
+        assert_eq!(clock.next_deadline(), Some(Duration::from_millis(300)));
+        assert_eq!(clock.advance_to_next(), Some(Duration::from_millis(150)));
+        assert_eq!(clock.elapsed(), Duration::from_millis(300));
+        assert!(long.now_or_never().is_some());
+        assert_eq!(clock.advance_to_next(), None);
+    }
+}
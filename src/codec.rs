@@ -3,11 +3,11 @@ use std::convert::TryFrom;
 use bytes::Bytes;
 use rlp::{Decodable, DecoderError, Encodable, Prototype, Rlp, RlpStream};
 
-use crate::smr::smr_types::Step;
+use crate::smr::smr_types::{FromWhere, SMREvent, Step};
 use crate::types::{
     Address, AggregatedChoke, AggregatedSignature, AggregatedVote, Choke, Commit, Hash, HashChoke,
-    Node, PoLC, Proof, Proposal, Signature, SignedChoke, SignedProposal, SignedVote, Status,
-    UpdateFrom, Vote, VoteType,
+    Node, OverlordMsg, PoLC, Proof, Proposal, Signature, SignedChoke, SignedProposal, SignedVote,
+    Status, UpdateFrom, Vote, VoteType,
 };
 use crate::wal::{WalInfo, WalLock};
 use crate::{Codec, DurationConfig};
@@ -445,7 +445,7 @@ impl Decodable for UpdateFrom {
                         let qc: AggregatedChoke = r.val_at(1)?;
                         UpdateFrom::ChokeQC(qc)
                     }
-                    _ => unreachable!(),
+                    _ => return Err(DecoderError::Custom("Invalid update from tag.")),
                 };
                 Ok(res)
             }
@@ -611,6 +611,210 @@ impl Decodable for AggregatedChoke {
     }
 }
 
+// impl Encodable and Decodable trait for FromWhere
+impl Encodable for FromWhere {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        let (tag, round) = match self {
+            FromWhere::PrevoteQC(round) => (0u8, round),
+            FromWhere::PrecommitQC(round) => (1u8, round),
+            FromWhere::ChokeQC(round) => (2u8, round),
+        };
+        s.begin_list(2).append(&tag).append(round);
+    }
+}
+
+impl Decodable for FromWhere {
+    fn decode(r: &Rlp) -> Result<Self, DecoderError> {
+        match r.prototype()? {
+            Prototype::List(2) => {
+                let tag: u8 = r.val_at(0)?;
+                let round: u64 = r.val_at(1)?;
+                match tag {
+                    0u8 => Ok(FromWhere::PrevoteQC(round)),
+                    1u8 => Ok(FromWhere::PrecommitQC(round)),
+                    2u8 => Ok(FromWhere::ChokeQC(round)),
+                    _ => Err(DecoderError::Custom("Invalid from where tag.")),
+                }
+            }
+            _ => Err(DecoderError::RlpInconsistentLengthAndData),
+        }
+    }
+}
+
+// impl Encodable and Decodable trait for SMREvent
+impl Encodable for SMREvent {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        match self {
+            SMREvent::NewRoundInfo {
+                height,
+                round,
+                lock_round,
+                lock_proposal,
+                from_where,
+                new_interval,
+                new_config,
+            } => {
+                s.begin_list(8)
+                    .append(&0u8)
+                    .append(height)
+                    .append(round)
+                    .append(lock_round)
+                    .append(&lock_proposal.as_ref().map(|hash| hash.to_vec()))
+                    .append(from_where)
+                    .append(new_interval)
+                    .append(new_config);
+            }
+            SMREvent::PrevoteVote {
+                height,
+                round,
+                block_hash,
+                lock_round,
+            } => {
+                s.begin_list(5)
+                    .append(&1u8)
+                    .append(height)
+                    .append(round)
+                    .append(&block_hash.to_vec())
+                    .append(lock_round);
+            }
+            SMREvent::PrecommitVote {
+                height,
+                round,
+                block_hash,
+                lock_round,
+            } => {
+                s.begin_list(5)
+                    .append(&2u8)
+                    .append(height)
+                    .append(round)
+                    .append(&block_hash.to_vec())
+                    .append(lock_round);
+            }
+            SMREvent::Commit(hash) => {
+                s.begin_list(2).append(&3u8).append(&hash.to_vec());
+            }
+            SMREvent::Brake {
+                height,
+                round,
+                lock_round,
+            } => {
+                s.begin_list(4)
+                    .append(&4u8)
+                    .append(height)
+                    .append(round)
+                    .append(lock_round);
+            }
+            SMREvent::Stop => {
+                s.begin_list(1).append(&5u8);
+            }
+        }
+    }
+}
+
+impl Decodable for SMREvent {
+    fn decode(r: &Rlp) -> Result<Self, DecoderError> {
+        if !r.is_list() {
+            return Err(DecoderError::RlpExpectedToBeList);
+        }
+        let tag: u8 = r.val_at(0)?;
+        match (tag, r.item_count()?) {
+            (0u8, 8) => {
+                let tmp: Option<Vec<u8>> = r.val_at(4)?;
+                Ok(SMREvent::NewRoundInfo {
+                    height: r.val_at(1)?,
+                    round: r.val_at(2)?,
+                    lock_round: r.val_at(3)?,
+                    lock_proposal: tmp.map(Hash::from),
+                    from_where: r.val_at(5)?,
+                    new_interval: r.val_at(6)?,
+                    new_config: r.val_at(7)?,
+                })
+            }
+            (1u8, 5) | (2u8, 5) => {
+                let height: u64 = r.val_at(1)?;
+                let round: u64 = r.val_at(2)?;
+                let tmp: Vec<u8> = r.val_at(3)?;
+                let block_hash = Hash::from(tmp);
+                let lock_round: Option<u64> = r.val_at(4)?;
+                if tag == 1u8 {
+                    Ok(SMREvent::PrevoteVote {
+                        height,
+                        round,
+                        block_hash,
+                        lock_round,
+                    })
+                } else {
+                    Ok(SMREvent::PrecommitVote {
+                        height,
+                        round,
+                        block_hash,
+                        lock_round,
+                    })
+                }
+            }
+            (3u8, 2) => {
+                let tmp: Vec<u8> = r.val_at(1)?;
+                Ok(SMREvent::Commit(Hash::from(tmp)))
+            }
+            (4u8, 4) => Ok(SMREvent::Brake {
+                height: r.val_at(1)?,
+                round: r.val_at(2)?,
+                lock_round: r.val_at(3)?,
+            }),
+            (5u8, 1) => Ok(SMREvent::Stop),
+            _ => Err(DecoderError::RlpInconsistentLengthAndData),
+        }
+    }
+}
+
+// impl Encodable and Decodable trait for OverlordMsg
+impl<T: Codec> Encodable for OverlordMsg<T> {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        match self {
+            OverlordMsg::SignedProposal(sp) => {
+                s.begin_list(2).append(&0u8).append(sp);
+            }
+            OverlordMsg::SignedVote(sv) => {
+                s.begin_list(2).append(&1u8).append(sv);
+            }
+            OverlordMsg::AggregatedVote(qc) => {
+                s.begin_list(2).append(&2u8).append(qc);
+            }
+            OverlordMsg::RichStatus(status) => {
+                s.begin_list(2).append(&3u8).append(status);
+            }
+            OverlordMsg::SignedChoke(sc) => {
+                s.begin_list(2).append(&4u8).append(sc);
+            }
+            OverlordMsg::Stop => {
+                s.begin_list(1).append(&5u8);
+            }
+            #[cfg(test)]
+            OverlordMsg::Commit(commit) => {
+                s.begin_list(2).append(&6u8).append(commit);
+            }
+        }
+    }
+}
+
+impl<T: Codec> Decodable for OverlordMsg<T> {
+    fn decode(r: &Rlp) -> Result<Self, DecoderError> {
+        let tag: u8 = r.val_at(0)?;
+        match (tag, r.prototype()?) {
+            (0u8, Prototype::List(2)) => Ok(OverlordMsg::SignedProposal(r.val_at(1)?)),
+            (1u8, Prototype::List(2)) => Ok(OverlordMsg::SignedVote(r.val_at(1)?)),
+            (2u8, Prototype::List(2)) => Ok(OverlordMsg::AggregatedVote(r.val_at(1)?)),
+            (3u8, Prototype::List(2)) => Ok(OverlordMsg::RichStatus(r.val_at(1)?)),
+            (4u8, Prototype::List(2)) => Ok(OverlordMsg::SignedChoke(r.val_at(1)?)),
+            (5u8, Prototype::List(1)) => Ok(OverlordMsg::Stop),
+            #[cfg(test)]
+            (6u8, Prototype::List(2)) => Ok(OverlordMsg::Commit(r.val_at(1)?)),
+            (_, Prototype::List(_)) => Err(DecoderError::Custom("Invalid message tag.")),
+            _ => Err(DecoderError::RlpInconsistentLengthAndData),
+        }
+    }
+}
+
 impl Encodable for HashChoke {
     fn rlp_append(&self, s: &mut RlpStream) {
         s.begin_list(2).append(&self.height).append(&self.round);
@@ -924,5 +1128,41 @@ mod test {
         let wal_info = WalInfo::new(None);
         let res: WalInfo<Pill> = rlp::decode(&wal_info.rlp_bytes()).unwrap();
         assert_eq!(wal_info, res);
+
+        // Test SMR Event
+        let events = vec![
+            SMREvent::NewRoundInfo {
+                height: random::<u64>(),
+                round: random::<u64>(),
+                lock_round: Some(random::<u64>()),
+                lock_proposal: Some(gen_hash()),
+                from_where: FromWhere::ChokeQC(random::<u64>()),
+                new_interval: None,
+                new_config: Some(DurationConfig::new(1, 2, 3, 4)),
+            },
+            SMREvent::PrevoteVote {
+                height: random::<u64>(),
+                round: random::<u64>(),
+                block_hash: gen_hash(),
+                lock_round: None,
+            },
+            SMREvent::PrecommitVote {
+                height: random::<u64>(),
+                round: random::<u64>(),
+                block_hash: Hash::new(),
+                lock_round: Some(random::<u64>()),
+            },
+            SMREvent::Commit(gen_hash()),
+            SMREvent::Brake {
+                height: random::<u64>(),
+                round: random::<u64>(),
+                lock_round: None,
+            },
+            SMREvent::Stop,
+        ];
+        for event in events {
+            let res: SMREvent = rlp::decode(&event.rlp_bytes()).unwrap();
+            assert_eq!(event, res);
+        }
     }
 }
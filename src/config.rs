@@ -0,0 +1,516 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use rlp::{Encodable, RlpStream};
+use serde::{Deserialize, Serialize};
+
+use crate::error::ConsensusError;
+use crate::types::{HashChoke, Proposal, Vote};
+use crate::{Codec, ConsensusResult, DurationConfig};
+
+const DEFAULT_INTERVAL: u64 = 3000;
+const DEFAULT_MAX_TIMEOUT_COEF: u32 = 5;
+const DEFAULT_MAX_IDLE_INTERVAL: u64 = 30000;
+const MAX_TIMEOUT_COEF_LIMIT: u32 = 16;
+
+const PROPOSAL_TAG: &[u8] = b"overlord-proposal";
+const VOTE_TAG: &[u8] = b"overlord-vote";
+const CHOKE_TAG: &[u8] = b"overlord-choke";
+
+/// The configuration of an overlord instance. All the fields have default values, so a config
+/// file in TOML or JSON only needs to contain the fields that differ from the default. Use
+/// `OverlordConfig::builder()` to build a validated config.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct OverlordConfig {
+    /// The height interval as millisecond.
+    pub interval: u64,
+    /// The proportions of each step timeout to the height interval. The ratios are divided by
+    /// 10.
+    pub timer_config: DurationConfig,
+    /// The timeout of each step doubles every round until the round reaches this coefficient.
+    pub max_timeout_coef: u32,
+    /// The maximum gap between a future message height and the current height. The messages of a
+    /// higher height are dropped.
+    pub future_height_gap: u64,
+    /// The maximum round of a future message. The messages of a higher round are dropped.
+    pub future_round_gap: u64,
+    /// The maximum number of the cached signed proposals of the future heights.
+    pub max_future_proposals: usize,
+    /// The maximum number of the cached signed votes and quorum certificates of the future
+    /// heights.
+    pub max_future_votes: usize,
+    /// The maximum number of the cached future messages that wait for their authority lists to
+    /// be verified.
+    pub max_unverified_msgs: usize,
+    /// The maximum number of signature verifications running concurrently.
+    pub verify_concurrency: usize,
+    /// The number of heights that an authority list returned by a commit takes to come into
+    /// effect. The list returned by the commit of height `h` is used from height `h + delay`. The
+    /// lists waiting to take effect are not saved in the wal, so they are got again by
+    /// `get_authority_list` on a restart or after a synchronization.
+    pub authority_delay: u64,
+    /// Whether to follow the consensus when self is not in the authority list. A follower collects
+    /// proposals and precommit quorum certificates and hands the observed commits to
+    /// `Consensus::on_observed_commit`.
+    pub follower_mode: bool,
+    /// The retry policy of `get_block`, `check_block` and `commit`.
+    pub retry: RetryPolicy,
+    /// The maximum time as millisecond from the start of a height to the start of a round in
+    /// which a block is proposed. The proposer consults `Consensus::should_propose` only if
+    /// skipping the round still starts the next round in this time, so it must not be less than
+    /// the interval.
+    pub max_idle_interval: u64,
+    /// Tune the propose, prevote and precommit timeouts by the observed step durations if set.
+    /// Otherwise the timeouts are the fixed proportions of the height interval.
+    pub adaptive_timer: Option<AdaptiveTimerConfig>,
+    /// The domain which the signed proposals, votes and chokes are bound to. All the nodes of a
+    /// network must use the same domain.
+    pub sign_domain: SignDomain,
+}
+
+impl Default for OverlordConfig {
+    fn default() -> Self {
+        OverlordConfig {
+            interval: DEFAULT_INTERVAL,
+            timer_config: DurationConfig::new(24, 10, 5, 3),
+            max_timeout_coef: DEFAULT_MAX_TIMEOUT_COEF,
+            future_height_gap: 5,
+            future_round_gap: 10,
+            max_future_proposals: 256,
+            max_future_votes: 8192,
+            max_unverified_msgs: 8192,
+            verify_concurrency: 1024,
+            authority_delay: 1,
+            follower_mode: false,
+            retry: RetryPolicy::default(),
+            max_idle_interval: DEFAULT_MAX_IDLE_INTERVAL,
+            adaptive_timer: None,
+            sign_domain: SignDomain::default(),
+        }
+    }
+}
+
+impl OverlordConfig {
+    /// Create a config builder with the default values.
+    pub fn builder() -> OverlordConfigBuilder {
+        OverlordConfigBuilder::default()
+    }
+
+    /// Check whether the config is valid.
+    pub fn validate(&self) -> ConsensusResult<()> {
+        let timer = &self.timer_config;
+        if self.interval == 0 {
+            return Err(config_err("interval must be positive"));
+        } else if timer.propose_ratio == 0
+            || timer.prevote_ratio == 0
+            || timer.precommit_ratio == 0
+            || timer.brake_ratio == 0
+        {
+            return Err(config_err("timer ratios must be positive"));
+        } else if self.max_timeout_coef > MAX_TIMEOUT_COEF_LIMIT {
+            return Err(config_err(&format!(
+                "max timeout coef must not be larger than {}",
+                MAX_TIMEOUT_COEF_LIMIT
+            )));
+        } else if self.future_height_gap == 0 || self.future_round_gap == 0 {
+            return Err(config_err("future gaps must be positive"));
+        } else if self.verify_concurrency == 0 {
+            return Err(config_err("verify concurrency must be positive"));
+        } else if self.authority_delay == 0 {
+            return Err(config_err("authority delay must be positive"));
+        } else if self.max_idle_interval < self.interval {
+            return Err(config_err(
+                "max idle interval must not be less than the interval",
+            ));
+        } else if self.retry.max_attempts == 0 {
+            return Err(config_err("retry attempts must be positive"));
+        } else if self.retry.initial_backoff > self.retry.max_backoff {
+            return Err(config_err(
+                "initial retry backoff must not be larger than the max backoff",
+            ));
+        }
+
+        if matches!(&self.sign_domain, SignDomain::Chain(chain_id) if chain_id.is_empty()) {
+            return Err(config_err("chain id must not be empty"));
+        }
+
+        if let Some(adaptive) = &self.adaptive_timer {
+            if adaptive.window == 0 || adaptive.multiplier == 0 {
+                return Err(config_err(
+                    "adaptive timer window and multiplier must be positive",
+                ));
+            } else if adaptive.min_timeout == 0 || adaptive.min_timeout > adaptive.max_timeout {
+                return Err(config_err(
+                    "adaptive timer bounds must be positive and min must not be larger than max",
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The retry policy of the application callbacks. A call failed with a `TransientError` is retried
+/// after a backoff which doubles every retry until it reaches the max backoff.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryPolicy {
+    /// The maximum number of calls including the first one. `1` means no retry.
+    pub max_attempts: u32,
+    /// The backoff before the first retry as millisecond.
+    pub initial_backoff: u64,
+    /// The maximum backoff as millisecond.
+    pub max_backoff: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: 100,
+            max_backoff: 1000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Get the backoff before the given retry which starts from `1`.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let coef = 1u64
+            .checked_shl(retry.saturating_sub(1))
+            .unwrap_or(u64::MAX);
+        Duration::from_millis(
+            self.initial_backoff
+                .saturating_mul(coef)
+                .min(self.max_backoff),
+        )
+    }
+}
+
+/// The adaptive timing mode. The timeout of a step is the longest duration of the step observed in
+/// the latest `window` times multiplied by the `multiplier`, and bounded by the min and the max
+/// timeout. A step that times out is observed with its timeout, so the timeout grows on a slow
+/// network and shrinks on a fast one. The timeouts still double every round.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdaptiveTimerConfig {
+    /// The number of the latest durations of each step to observe.
+    pub window: usize,
+    /// The multiple of the longest observed duration used as the timeout.
+    pub multiplier: u32,
+    /// The minimum timeout of a step as millisecond.
+    pub min_timeout: u64,
+    /// The maximum timeout of a step as millisecond.
+    pub max_timeout: u64,
+}
+
+impl Default for AdaptiveTimerConfig {
+    fn default() -> Self {
+        AdaptiveTimerConfig {
+            window: 20,
+            multiplier: 2,
+            min_timeout: 100,
+            max_timeout: 10000,
+        }
+    }
+}
+
+/// The domain of the signed payloads. A payload of the chain domain is the RLP list of a tag of the
+/// message type, the chain id and the RLP encoded message, so that a signature is neither valid
+/// for another type of message nor on another chain signed by the same keys. The legacy domain
+/// signs the RLP encoded message alone as the networks before the domain separation do, and is the
+/// default to keep them compatible. A network moves to the chain domain by upgrading all the nodes
+/// at once, since the signatures of the two domains do not verify each other.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SignDomain {
+    /// Sign the RLP encoded messages without a domain.
+    Legacy,
+    /// Sign the messages bound to the chain id.
+    Chain(#[serde(with = "crate::serde_hex")] Bytes),
+}
+
+impl Default for SignDomain {
+    fn default() -> Self {
+        SignDomain::Legacy
+    }
+}
+
+impl SignDomain {
+    /// Get the payload of a proposal to hash and sign.
+    pub fn proposal_payload<T: Codec>(&self, proposal: &Proposal<T>) -> Bytes {
+        self.payload(PROPOSAL_TAG, proposal)
+    }
+
+    /// Get the payload of a vote to hash and sign. An aggregated vote and the proof of a commit
+    /// are signed on the payload of the vote they aggregate.
+    pub fn vote_payload(&self, vote: &Vote) -> Bytes {
+        self.payload(VOTE_TAG, vote)
+    }
+
+    /// Get the payload of a choke of the height and round to hash and sign.
+    pub fn choke_payload(&self, height: u64, round: u64) -> Bytes {
+        self.payload(CHOKE_TAG, &HashChoke { height, round })
+    }
+
+    fn payload<E: Encodable>(&self, tag: &[u8], msg: &E) -> Bytes {
+        match self {
+            SignDomain::Legacy => Bytes::from(rlp::encode(msg)),
+            SignDomain::Chain(chain_id) => {
+                let mut s = RlpStream::new_list(3);
+                s.append(&tag.to_vec())
+                    .append(&chain_id.to_vec())
+                    .append(msg);
+                Bytes::from(s.out())
+            }
+        }
+    }
+}
+
+/// A builder of `OverlordConfig` which validates the config when building.
+#[derive(Clone, Debug, Default)]
+pub struct OverlordConfigBuilder {
+    config: OverlordConfig,
+}
+
+impl OverlordConfigBuilder {
+    /// Set the height interval as millisecond.
+    pub fn interval(mut self, interval: u64) -> Self {
+        self.config.interval = interval;
+        self
+    }
+
+    /// Set the proportions of each step timeout to the height interval.
+    pub fn timer_config(mut self, timer_config: DurationConfig) -> Self {
+        self.config.timer_config = timer_config;
+        self
+    }
+
+    /// Set the maximum timeout coefficient.
+    pub fn max_timeout_coef(mut self, coef: u32) -> Self {
+        self.config.max_timeout_coef = coef;
+        self
+    }
+
+    /// Set the future height gap and the future round gap.
+    pub fn future_gaps(mut self, height_gap: u64, round_gap: u64) -> Self {
+        self.config.future_height_gap = height_gap;
+        self.config.future_round_gap = round_gap;
+        self
+    }
+
+    /// Set the maximum number of the cached future proposals, votes and unverified messages.
+    pub fn cache_limits(mut self, proposals: usize, votes: usize, unverified_msgs: usize) -> Self {
+        self.config.max_future_proposals = proposals;
+        self.config.max_future_votes = votes;
+        self.config.max_unverified_msgs = unverified_msgs;
+        self
+    }
+
+    /// Set the maximum number of signature verifications running concurrently.
+    pub fn verify_concurrency(mut self, concurrency: usize) -> Self {
+        self.config.verify_concurrency = concurrency;
+        self
+    }
+
+    /// Set the activation delay of authority lists.
+    pub fn authority_delay(mut self, delay: u64) -> Self {
+        self.config.authority_delay = delay;
+        self
+    }
+
+    /// Set whether to follow the consensus without consensus power.
+    pub fn follower_mode(mut self, enable: bool) -> Self {
+        self.config.follower_mode = enable;
+        self
+    }
+
+    /// Set the retry policy of the application callbacks.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.config.retry = policy;
+        self
+    }
+
+    /// Set the maximum time from the start of a height to the round in which a block is proposed.
+    pub fn max_idle_interval(mut self, interval: u64) -> Self {
+        self.config.max_idle_interval = interval;
+        self
+    }
+
+    /// Enable the adaptive timing mode.
+    pub fn adaptive_timer(mut self, adaptive: AdaptiveTimerConfig) -> Self {
+        self.config.adaptive_timer = Some(adaptive);
+        self
+    }
+
+    /// Bind the signed payloads to the chain id.
+    pub fn chain_id(mut self, chain_id: Bytes) -> Self {
+        self.config.sign_domain = SignDomain::Chain(chain_id);
+        self
+    }
+
+    /// Validate and build the config.
+    pub fn build(self) -> ConsensusResult<OverlordConfig> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+fn config_err(msg: &str) -> ConsensusError {
+    ConsensusError::ConfigErr(msg.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+
+    use crate::config::{AdaptiveTimerConfig, OverlordConfig, RetryPolicy, SignDomain, VOTE_TAG};
+    use crate::types::{HashChoke, Vote, VoteType};
+    use crate::DurationConfig;
+
+    #[test]
+    fn test_config_builder() {
+        let config = OverlordConfig::builder()
+            .interval(1000)
+            .future_gaps(20, 30)
+            .authority_delay(3)
+            .max_idle_interval(60000)
+            .build()
+            .unwrap();
+        assert_eq!(config.interval, 1000);
+        assert_eq!(config.future_height_gap, 20);
+        assert_eq!(config.future_round_gap, 30);
+        assert_eq!(config.authority_delay, 3);
+        assert_eq!(config.max_idle_interval, 60000);
+        assert_eq!(config.timer_config, DurationConfig::new(24, 10, 5, 3));
+
+        assert!(OverlordConfig::builder().interval(0).build().is_err());
+        assert!(OverlordConfig::builder()
+            .max_idle_interval(0)
+            .build()
+            .is_err());
+        assert!(OverlordConfig::builder()
+            .interval(3000)
+            .max_idle_interval(2999)
+            .build()
+            .is_err());
+        assert!(OverlordConfig::builder()
+            .timer_config(DurationConfig::default())
+            .build()
+            .is_err());
+        assert!(OverlordConfig::builder()
+            .max_timeout_coef(17)
+            .build()
+            .is_err());
+        assert!(OverlordConfig::builder()
+            .future_gaps(0, 10)
+            .build()
+            .is_err());
+        assert!(OverlordConfig::builder()
+            .verify_concurrency(0)
+            .build()
+            .is_err());
+        assert!(OverlordConfig::builder()
+            .authority_delay(0)
+            .build()
+            .is_err());
+        assert!(OverlordConfig::builder()
+            .retry_policy(RetryPolicy {
+                max_attempts: 0,
+                ..RetryPolicy::default()
+            })
+            .build()
+            .is_err());
+        assert!(OverlordConfig::builder()
+            .retry_policy(RetryPolicy {
+                initial_backoff: 2000,
+                ..RetryPolicy::default()
+            })
+            .build()
+            .is_err());
+        assert!(OverlordConfig::builder()
+            .adaptive_timer(AdaptiveTimerConfig {
+                min_timeout: 20000,
+                ..AdaptiveTimerConfig::default()
+            })
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_retry_backoff() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_backoff: 100,
+            max_backoff: 500,
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(500));
+        assert_eq!(policy.backoff(100), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_config_serde() {
+        let config: OverlordConfig =
+            serde_json::from_str(r#"{"interval": 500, "follower_mode": true}"#).unwrap();
+        assert_eq!(config.interval, 500);
+        assert!(config.follower_mode);
+        assert_eq!(
+            config.max_timeout_coef,
+            OverlordConfig::default().max_timeout_coef
+        );
+        assert!(config.validate().is_ok());
+
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(
+            serde_json::from_str::<OverlordConfig>(&json).unwrap(),
+            config
+        );
+
+        assert!(serde_json::from_str::<OverlordConfig>(r#"{"intervals": 500}"#).is_err());
+
+        let config: OverlordConfig =
+            serde_json::from_str(r#"{"sign_domain": {"chain": "0102"}}"#).unwrap();
+        assert_eq!(
+            config.sign_domain,
+            SignDomain::Chain(Bytes::from(vec![1u8, 2]))
+        );
+    }
+
+    #[test]
+    fn test_sign_domain() {
+        let vote = Vote {
+            height: 1,
+            round: 2,
+            vote_type: VoteType::Prevote,
+            block_hash: Bytes::from("hash"),
+        };
+        let choke = HashChoke {
+            height: 1,
+            round: 2,
+        };
+        let legacy = SignDomain::Legacy;
+        assert_eq!(legacy.vote_payload(&vote), Bytes::from(rlp::encode(&vote)));
+        assert_eq!(legacy.choke_payload(1, 2), Bytes::from(rlp::encode(&choke)));
+
+        let chain = SignDomain::Chain(Bytes::from("chain a"));
+        let other = SignDomain::Chain(Bytes::from("chain b"));
+        assert_ne!(chain.vote_payload(&vote), legacy.vote_payload(&vote));
+        assert_ne!(chain.vote_payload(&vote), other.vote_payload(&vote));
+        // The same fields of a choke and a vote are told apart by the tags.
+        assert_ne!(chain.choke_payload(1, 2), chain.payload(VOTE_TAG, &choke));
+
+        assert!(OverlordConfig::builder()
+            .chain_id(Bytes::from("chain a"))
+            .build()
+            .is_ok());
+        assert!(OverlordConfig::builder()
+            .chain_id(Bytes::new())
+            .build()
+            .is_err());
+    }
+}
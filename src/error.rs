@@ -4,8 +4,39 @@ use std::error::Error;
 
 use derive_more::Display;
 
-/// Overlord consensus error.
-#[derive(Clone, Debug, Display)]
+/// The error returned by the application traits.
+pub type ErrorSource = Box<dyn Error + Send>;
+
+/// An error of the application which may not occur again on another call, such as a timeout of a
+/// storage or a network request. Only the calls of `get_block`, `check_block` and `commit` that
+/// fail with a transient error are retried by the `RetryPolicy` of the config, and a failed
+/// `check_block` with any other error is taken as a rejection of the block.
+#[derive(Debug, Display)]
+#[display(fmt = "{}", _0)]
+pub struct TransientError(pub ErrorSource);
+
+impl Error for TransientError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.0.as_ref())
+    }
+}
+
+impl TransientError {
+    /// Wrap an error as a transient one to return from the application traits.
+    pub fn boxed<E: Error + Send + 'static>(error: E) -> ErrorSource {
+        Box::new(TransientError(Box::new(error)))
+    }
+
+    /// Return whether an error returned by the application is transient.
+    pub fn is_transient(error: &ErrorSource) -> bool {
+        error.downcast_ref::<TransientError>().is_some()
+    }
+}
+
+/// Overlord consensus error. The errors returned by the application traits are kept as the
+/// `source()` of the typed variants which carry the height and round. Each variant has a stable
+/// code given by `code()`.
+#[derive(Debug, Display)]
 pub enum ConsensusError {
     ///
     #[display(fmt = "Invalid address")]
@@ -63,28 +94,182 @@ pub enum ConsensusError {
     ///
     #[display(fmt = "Save Wal error {}, {}, {} step", height, round, step)]
     SaveWalErr {
-        ///
+        /// The height.
         height: u64,
-        ///
+        /// The round.
         round: u64,
         ///
         step: String,
+        /// The original error.
+        source: ErrorSource,
     },
     ///
-    #[display(fmt = "Load Wal error {}", _0)]
-    LoadWalErr(String),
-    ///
-    #[display(fmt = "Crypto error {}", _0)]
-    CryptoErr(String),
+    #[display(fmt = "Load Wal error")]
+    LoadWalErr {
+        /// The original error.
+        source: ErrorSource,
+    },
+    /// Signing or aggregating signatures by `Crypto` failed.
+    #[display(fmt = "Crypto error height {}, round {}", height, round)]
+    CryptoErr {
+        /// The height.
+        height: u64,
+        /// The round.
+        round: u64,
+        /// The original error.
+        source: ErrorSource,
+    },
     ///
     #[display(fmt = "Aggregated signature error {}", _0)]
     AggregatedSignatureErr(String),
+    /// `Consensus::get_block` failed.
+    #[display(fmt = "Get block error height {}, round {}", height, round)]
+    GetBlockErr {
+        /// The height.
+        height: u64,
+        /// The round.
+        round: u64,
+        /// The original error.
+        source: ErrorSource,
+    },
+    /// `Consensus::check_block` failed with a `TransientError` after the retries. The checks
+    /// which reject the proposals are reported by `Consensus::report_invalid_proposal` instead.
+    #[display(fmt = "Check block error height {}, round {}", height, round)]
+    CheckBlockErr {
+        /// The height.
+        height: u64,
+        /// The round.
+        round: u64,
+        /// The original error.
+        source: ErrorSource,
+    },
+    /// `Consensus::commit` failed.
+    #[display(fmt = "Commit error height {}, round {}", height, round)]
+    CommitErr {
+        /// The height.
+        height: u64,
+        /// The round.
+        round: u64,
+        /// The original error.
+        source: ErrorSource,
+    },
+    /// `Consensus::on_observed_commit` failed.
+    #[display(fmt = "Observed commit error height {}, round {}", height, round)]
+    ObservedCommitErr {
+        /// The height.
+        height: u64,
+        /// The round.
+        round: u64,
+        /// The original error.
+        source: ErrorSource,
+    },
+    /// `Consensus::get_authority_list` failed. The round is the current round of self.
+    #[display(fmt = "Get authority list error height {}, round {}", height, round)]
+    GetAuthorityListErr {
+        /// The height.
+        height: u64,
+        /// The round.
+        round: u64,
+        /// The original error.
+        source: ErrorSource,
+    },
+    /// `Consensus::request_sync` failed. The height is the target height.
+    #[display(fmt = "Request sync error height {}, round {}", height, round)]
+    RequestSyncErr {
+        /// The height.
+        height: u64,
+        /// The round.
+        round: u64,
+        /// The original error.
+        source: ErrorSource,
+    },
+    /// `Consensus::broadcast_to_other` failed.
+    #[display(fmt = "Broadcast error height {}, round {}", height, round)]
+    BroadcastErr {
+        /// The height.
+        height: u64,
+        /// The round.
+        round: u64,
+        /// The original error.
+        source: ErrorSource,
+    },
+    /// `Consensus::transmit_to_relayer` failed.
+    #[display(fmt = "Transmit error height {}, round {}", height, round)]
+    TransmitErr {
+        /// The height.
+        height: u64,
+        /// The round.
+        round: u64,
+        /// The original error.
+        source: ErrorSource,
+    },
+    /// Invalid configuration.
+    #[display(fmt = "Config error {}", _0)]
+    ConfigErr(String),
     /// Other error.
     #[display(fmt = "Other error {}", _0)]
     Other(String),
 }
 
-impl Error for ConsensusError {}
+impl Error for ConsensusError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ConsensusError::SaveWalErr { source, .. }
+            | ConsensusError::LoadWalErr { source }
+            | ConsensusError::CryptoErr { source, .. }
+            | ConsensusError::GetBlockErr { source, .. }
+            | ConsensusError::CheckBlockErr { source, .. }
+            | ConsensusError::CommitErr { source, .. }
+            | ConsensusError::ObservedCommitErr { source, .. }
+            | ConsensusError::GetAuthorityListErr { source, .. }
+            | ConsensusError::RequestSyncErr { source, .. }
+            | ConsensusError::BroadcastErr { source, .. }
+            | ConsensusError::TransmitErr { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl ConsensusError {
+    /// Get the stable code of the error. The codes are grouped by the origin of the error: `1xx`
+    /// for the consensus process, `2xx` for the application traits, `3xx` for the wal, `4xx` for
+    /// the crypto, `5xx` for the config and `999` for the others. The code of a variant never
+    /// changes, and a new variant always gets a new code.
+    pub fn code(&self) -> u16 {
+        match self {
+            ConsensusError::InvalidAddress => 100,
+            ConsensusError::ChannelErr(_) => 101,
+            ConsensusError::TriggerSMRErr(_) => 102,
+            ConsensusError::MonitorEventErr(_) => 103,
+            ConsensusError::ThrowEventErr(_) => 104,
+            ConsensusError::ProposalErr(_) => 105,
+            ConsensusError::PrevoteErr(_) => 106,
+            ConsensusError::PrecommitErr(_) => 107,
+            ConsensusError::BrakeErr(_) => 108,
+            ConsensusError::RoundDiff { .. } => 109,
+            ConsensusError::SelfCheckErr(_) => 110,
+            ConsensusError::CorrectnessErr(_) => 111,
+            ConsensusError::TimerErr(_) => 112,
+            ConsensusError::StateErr(_) => 113,
+            ConsensusError::MultiProposal(..) => 114,
+            ConsensusError::StorageErr(_) => 115,
+            ConsensusError::GetBlockErr { .. } => 200,
+            ConsensusError::CheckBlockErr { .. } => 201,
+            ConsensusError::CommitErr { .. } => 202,
+            ConsensusError::ObservedCommitErr { .. } => 203,
+            ConsensusError::GetAuthorityListErr { .. } => 204,
+            ConsensusError::RequestSyncErr { .. } => 205,
+            ConsensusError::BroadcastErr { .. } => 206,
+            ConsensusError::TransmitErr { .. } => 207,
+            ConsensusError::SaveWalErr { .. } => 300,
+            ConsensusError::LoadWalErr { .. } => 301,
+            ConsensusError::CryptoErr { .. } => 400,
+            ConsensusError::AggregatedSignatureErr(_) => 401,
+            ConsensusError::ConfigErr(_) => 500,
+            ConsensusError::Other(_) => 999,
+        }
+    }
+}
 
 #[cfg(test)]
 impl PartialEq for ConsensusError {
@@ -115,3 +300,52 @@ impl PartialEq for ConsensusError {
 
 #[cfg(test)]
 impl Eq for ConsensusError {}
+
+#[cfg(test)]
+mod test {
+    use std::error::Error;
+    use std::io;
+
+    use crate::error::{ConsensusError, TransientError};
+
+    #[test]
+    fn test_transient_error() {
+        let err = TransientError::boxed(io::Error::new(io::ErrorKind::TimedOut, "timeout"));
+        assert!(TransientError::is_transient(&err));
+        assert_eq!(err.to_string(), "timeout");
+        let err: Box<dyn Error + Send> =
+            Box::new(io::Error::new(io::ErrorKind::InvalidData, "invalid"));
+        assert!(!TransientError::is_transient(&err));
+    }
+
+    #[test]
+    fn test_transient_error_chain() {
+        let err = ConsensusError::CommitErr {
+            height: 10,
+            round: 1,
+            source: TransientError::boxed(io::Error::new(io::ErrorKind::TimedOut, "timeout")),
+        };
+        let transient = err.source().unwrap();
+        assert!(transient.is::<TransientError>());
+        let inner = transient.source().unwrap();
+        let inner_io = inner.downcast_ref::<io::Error>().unwrap();
+        assert_eq!(inner_io.kind(), io::ErrorKind::TimedOut);
+        assert!(inner.source().is_none());
+    }
+
+    #[test]
+    fn test_error_source() {
+        let err = ConsensusError::CommitErr {
+            height: 10,
+            round: 1,
+            source: Box::new(io::Error::new(io::ErrorKind::WriteZero, "disk full")),
+        };
+        assert_eq!(err.code(), 202);
+        assert_eq!(err.to_string(), "Commit error height 10, round 1");
+        assert_eq!(err.source().unwrap().to_string(), "disk full");
+
+        let err = ConsensusError::Other("other".to_string());
+        assert_eq!(err.code(), 999);
+        assert!(err.source().is_none());
+    }
+}
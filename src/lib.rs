@@ -7,18 +7,30 @@
 #![recursion_limit = "512"]
 #![allow(clippy::mutable_key_type)]
 
+/// The time source of the overlord consensus.
+pub mod clock;
 /// A module that impl rlp encodable and decodable trait for types that need to save wal.
 mod codec;
+/// The configuration of the overlord consensus.
+pub mod config;
 /// Overlord error module.
 pub mod error;
+/// The metrics of the overlord consensus.
+pub mod metrics;
 /// Create and run the overlord consensus process.
 pub mod overlord;
+/// Record the inputs of the consensus state and replay them offline.
+pub mod record;
 /// serialize Bytes in hex format
 pub mod serde_hex;
 /// serialize Vec<Bytes> in hex format
 mod serde_multi_hex;
+/// A deterministic simulation of the overlord consensus in one process.
+pub mod sim;
 /// State machine replicas module to do state changes.
 mod smr;
+/// The task spawner of the overlord consensus.
+pub mod spawner;
 /// The state module to storage proposals and votes.
 mod state;
 /// The timer module to ensure the protocol liveness.
@@ -29,22 +41,35 @@ pub mod types;
 mod utils;
 /// Write ahead log module.
 mod wal;
-
+/// The wire format of the overlord messages.
+pub mod wire;
+
+pub use self::clock::{Clock, ManualClock, SystemClock};
+pub use self::config::{
+    AdaptiveTimerConfig, OverlordConfig, OverlordConfigBuilder, RetryPolicy, SignDomain,
+};
+pub use self::metrics::{Metrics, NoopMetrics};
 pub use self::overlord::Overlord;
 pub use self::overlord::OverlordHandler;
+pub use self::spawner::{Spawner, TokioSpawner};
 pub use self::utils::auth_manage::{extract_voters, get_leader};
+pub use self::utils::cancel::CancelToken;
 pub use creep::Context;
 pub use wal::WalInfo;
 
 use std::error::Error;
 use std::fmt::Debug;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use bytes::Bytes;
 use serde::{Deserialize, Serialize};
 
 use crate::error::ConsensusError;
-use crate::types::{Address, Commit, Hash, Node, OverlordMsg, Signature, Status, ViewChangeReason};
+use crate::types::{
+    Address, Commit, Hash, InvalidProposal, Node, OverlordMsg, Proof, Signature, Status,
+    ViewChangeReason,
+};
 
 /// Overlord consensus result.
 pub type ConsensusResult<T> = std::result::Result<T, ConsensusError>;
@@ -55,15 +80,35 @@ const INIT_ROUND: u64 = 0;
 /// Trait for some functions that consensus needs.
 #[async_trait]
 pub trait Consensus<T: Codec>: Send + Sync {
-    /// Get a block of the given height and return the block with its hash.
+    /// Get a block of the given height and return the block with its hash. An error wrapped in
+    /// `TransientError` is retried according to the retry policy of the config.
     async fn get_block(
         &self,
         ctx: Context,
         height: u64,
     ) -> Result<(T, Hash), Box<dyn Error + Send>>;
 
+    /// Decide whether to propose a new block in the given round as the proposer, where `elapsed`
+    /// is the time since the height started. If returns `false`, self skips proposing and the
+    /// others go to the next round after the propose timeout, which slows down producing blocks
+    /// on an idle chain. It is not consulted when skipping the round would start the next round
+    /// later than `max_idle_interval` of the config since the height started, so a block is
+    /// proposed within the bound. Neither is it when self proposes a locked block.
+    async fn should_propose(
+        &self,
+        _ctx: Context,
+        _height: u64,
+        _round: u64,
+        _elapsed: Duration,
+    ) -> bool {
+        true
+    }
+
     /// Check the correctness of a block. If is passed, return the integrated transcations to do
-    /// data persistence.
+    /// data persistence. The check is given a deadline of the prevote and the precommit timeouts
+    /// of the round, and the `CancelToken` in the context is cancelled when the check times out or
+    /// becomes stale. An error wrapped in `TransientError` is retried according to the retry
+    /// policy of the config.
     async fn check_block(
         &self,
         ctx: Context,
@@ -72,7 +117,10 @@ pub trait Consensus<T: Codec>: Send + Sync {
         block: T,
     ) -> Result<(), Box<dyn Error + Send>>;
 
-    /// Commit a given height to execute and return the rich status.
+    /// Commit a given height to execute and return the rich status. It must be idempotent, since
+    /// the same height is committed again after an error wrapped in `TransientError`, and on a
+    /// restart from a wal saved in the commit step. If the last attempt fails, overlord requests
+    /// to synchronize the height by `request_sync` and waits for a `RichStatus`.
     async fn commit(
         &self,
         ctx: Context,
@@ -80,7 +128,41 @@ pub trait Consensus<T: Codec>: Send + Sync {
         commit: Commit<T>,
     ) -> Result<Status, Box<dyn Error + Send>>;
 
-    /// Get an authority list of the given height.
+    /// Hand a commit observed from the consensus messages to the application when self does not
+    /// have consensus power and the follower mode is on. The commit is assembled from a signed
+    /// proposal and a verified precommit quorum certificate of the same block hash, while the
+    /// block itself is not checked by `check_block`. Return the rich status of the next height to
+    /// keep following, or `None` to wait for a `RichStatus` from the application.
+    async fn on_observed_commit(
+        &self,
+        _ctx: Context,
+        _height: u64,
+        _commit: Commit<T>,
+    ) -> Result<Option<Status>, Box<dyn Error + Send>>
+    where
+        T: 'async_trait,
+    {
+        Ok(None)
+    }
+
+    /// Request the application to synchronize blocks up to the given height, when a verified
+    /// precommit quorum certificate shows that others have committed the height, or when `commit`
+    /// of the height fails. The proof is built from the quorum certificate. It is requested again
+    /// if the request fails, if a higher height is committed by others, or if self does not reach
+    /// the height in ten height intervals.
+    async fn request_sync(
+        &self,
+        _ctx: Context,
+        _target_height: u64,
+        _proof: Proof,
+    ) -> Result<(), Box<dyn Error + Send>> {
+        Ok(())
+    }
+
+    /// Get an authority list of the given height. Besides the heights of the future messages, it
+    /// is called for the heights before the list of a status takes effect when the authority delay
+    /// of the config is more than one, as self starts from a wal or goes to a height by a rich
+    /// status, since those lists are returned by the commits that self has not made.
     async fn get_authority_list(
         &self,
         ctx: Context,
@@ -107,6 +189,11 @@ pub trait Consensus<T: Codec>: Send + Sync {
 
     /// Report the overlord view change reason.
     fn report_view_change(&self, ctx: Context, height: u64, round: u64, reason: ViewChangeReason);
+
+    /// Report a proposal of which the block is rejected by `check_block`. A check failed with a
+    /// `TransientError` after the retries is reported by `report_error` as a `CheckBlockErr`
+    /// instead, and a timed out check is not reported.
+    fn report_invalid_proposal(&self, _ctx: Context, _invalid: InvalidProposal<T>) {}
 }
 
 /// Trait for doing serialize and deserialize.
@@ -0,0 +1,165 @@
+use std::fmt::Debug;
+use std::time::Duration;
+
+use crate::types::{Step, VoteType};
+
+/// Trait for collecting the metrics of the overlord consensus. Every method has an empty default
+/// implementation, so an implementation only needs to override the metrics it cares about. The
+/// methods are called in the consensus process and should return quickly.
+pub trait Metrics: Debug + Send + Sync {
+    /// Observe the time spent in a step. A step ends when the state machine goes to another step.
+    fn observe_step_duration(&self, _step: Step, _duration: Duration) {}
+
+    /// Observe the number of rounds that a height takes to be committed.
+    fn observe_height_rounds(&self, _height: u64, _rounds: u64) {}
+
+    /// Observe the latency from the start of a height to the commit of it.
+    fn observe_commit_latency(&self, _height: u64, _latency: Duration) {}
+
+    /// Increase the count of the verified signed votes of the given type handled by state.
+    fn inc_vote(&self, _vote_type: VoteType) {}
+
+    /// Increase the count of the verified signed chokes received from others.
+    fn inc_choke(&self, _height: u64, _round: u64) {}
+
+    /// Set the number of the messages that are waiting for or under signature verification.
+    fn set_verify_queue_depth(&self, _depth: usize) {}
+
+    /// Observe the time spent in saving a wal information.
+    fn observe_wal_save(&self, _duration: Duration) {}
+}
+
+/// A metrics implementation that drops all the metrics. It is the default metrics of overlord.
+#[derive(Clone, Debug, Default)]
+pub struct NoopMetrics;
+
+impl Metrics for NoopMetrics {}
+
+#[cfg(feature = "prometheus")]
+pub use self::prometheus_metrics::PrometheusMetrics;
+
+#[cfg(feature = "prometheus")]
+mod prometheus_metrics {
+    use std::time::Duration;
+
+    use prometheus::{
+        exponential_buckets, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec,
+        IntGauge, Opts, Registry,
+    };
+
+    use crate::metrics::Metrics;
+    use crate::types::{Step, VoteType};
+
+    /// A metrics implementation that records the metrics to the collectors registered in a
+    /// prometheus registry.
+    #[derive(Clone, Debug)]
+    pub struct PrometheusMetrics {
+        step_duration: HistogramVec,
+        height_rounds: Histogram,
+        commit_latency: Histogram,
+        votes: IntCounterVec,
+        chokes: IntCounter,
+        verify_queue_depth: IntGauge,
+        wal_save_duration: Histogram,
+    }
+
+    impl PrometheusMetrics {
+        /// Create the collectors and register them in the given registry.
+        pub fn new(registry: &Registry) -> prometheus::Result<Self> {
+            let metrics = PrometheusMetrics {
+                step_duration: HistogramVec::new(
+                    HistogramOpts::new(
+                        "overlord_step_duration_seconds",
+                        "The time spent in each step",
+                    ),
+                    &["step"],
+                )?,
+                height_rounds: Histogram::with_opts(
+                    HistogramOpts::new(
+                        "overlord_height_rounds",
+                        "The number of rounds that a height takes to be committed",
+                    )
+                    .buckets(exponential_buckets(1.0, 2.0, 6)?),
+                )?,
+                commit_latency: Histogram::with_opts(HistogramOpts::new(
+                    "overlord_commit_latency_seconds",
+                    "The latency from the start of a height to the commit of it",
+                ))?,
+                votes: IntCounterVec::new(
+                    Opts::new(
+                        "overlord_votes_total",
+                        "The count of the handled signed votes",
+                    ),
+                    &["vote_type"],
+                )?,
+                chokes: IntCounter::new(
+                    "overlord_chokes_total",
+                    "The count of the received signed chokes",
+                )?,
+                verify_queue_depth: IntGauge::new(
+                    "overlord_verify_queue_depth",
+                    "The number of the messages waiting for or under signature verification",
+                )?,
+                wal_save_duration: Histogram::with_opts(
+                    HistogramOpts::new(
+                        "overlord_wal_save_duration_seconds",
+                        "The time spent in saving a wal information",
+                    )
+                    .buckets(exponential_buckets(0.0005, 2.0, 12)?),
+                )?,
+            };
+
+            registry.register(Box::new(metrics.step_duration.clone()))?;
+            registry.register(Box::new(metrics.height_rounds.clone()))?;
+            registry.register(Box::new(metrics.commit_latency.clone()))?;
+            registry.register(Box::new(metrics.votes.clone()))?;
+            registry.register(Box::new(metrics.chokes.clone()))?;
+            registry.register(Box::new(metrics.verify_queue_depth.clone()))?;
+            registry.register(Box::new(metrics.wal_save_duration.clone()))?;
+            Ok(metrics)
+        }
+    }
+
+    impl Metrics for PrometheusMetrics {
+        fn observe_step_duration(&self, step: Step, duration: Duration) {
+            let label = match step {
+                Step::Propose => "propose",
+                Step::Prevote => "prevote",
+                Step::Precommit => "precommit",
+                Step::Brake => "brake",
+                Step::Commit => "commit",
+            };
+            self.step_duration
+                .with_label_values(&[label])
+                .observe(duration.as_secs_f64());
+        }
+
+        fn observe_height_rounds(&self, _height: u64, rounds: u64) {
+            self.height_rounds.observe(rounds as f64);
+        }
+
+        fn observe_commit_latency(&self, _height: u64, latency: Duration) {
+            self.commit_latency.observe(latency.as_secs_f64());
+        }
+
+        fn inc_vote(&self, vote_type: VoteType) {
+            let label = match vote_type {
+                VoteType::Prevote => "prevote",
+                VoteType::Precommit => "precommit",
+            };
+            self.votes.with_label_values(&[label]).inc();
+        }
+
+        fn inc_choke(&self, _height: u64, _round: u64) {
+            self.chokes.inc();
+        }
+
+        fn set_verify_queue_depth(&self, depth: usize) {
+            self.verify_queue_depth.set(depth as i64);
+        }
+
+        fn observe_wal_save(&self, duration: Duration) {
+            self.wal_save_duration.observe(duration.as_secs_f64());
+        }
+    }
+}
@@ -5,10 +5,12 @@ use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
 use parking_lot::RwLock;
 
 use crate::error::ConsensusError;
-use crate::state::process::State;
-use crate::types::{Address, Node, OverlordMsg};
-use crate::DurationConfig;
+use crate::record::{Entry, Recorder};
+use crate::state::process::{Runtime, State};
+use crate::types::{Address, Node, OverlordEvent, OverlordMsg};
+use crate::utils::event_bus::EventBus;
 use crate::{smr::SMR, timer::Timer};
+use crate::{Clock, Metrics, NoopMetrics, OverlordConfig, Spawner, SystemClock, TokioSpawner};
 use crate::{Codec, Consensus, ConsensusResult, Crypto, Wal};
 
 type Pile<T> = RwLock<Option<T>>;
@@ -21,6 +23,12 @@ pub struct Overlord<T: Codec, F: Consensus<T>, C: Crypto, W: Wal> {
     consensus: Pile<Arc<F>>,
     crypto: Pile<Arc<C>>,
     wal: Pile<Arc<W>>,
+    config: OverlordConfig,
+    metrics: Arc<dyn Metrics>,
+    clock: Arc<dyn Clock>,
+    spawner: Arc<dyn Spawner>,
+    recorder: Option<Arc<Recorder>>,
+    events: EventBus,
 }
 
 impl<T, F, C, W> Overlord<T, F, C, W>
@@ -31,7 +39,13 @@ where
     W: Wal + 'static,
 {
     /// Create a new overlord and return an overlord instance with an unbounded receiver.
-    pub fn new(address: Address, consensus: Arc<F>, crypto: Arc<C>, wal: Arc<W>) -> Self {
+    pub fn new(
+        address: Address,
+        consensus: Arc<F>,
+        crypto: Arc<C>,
+        wal: Arc<W>,
+        config: OverlordConfig,
+    ) -> Self {
         let (tx, rx) = unbounded();
         Overlord {
             sender: RwLock::new(Some(tx)),
@@ -40,31 +54,69 @@ where
             consensus: RwLock::new(Some(consensus)),
             crypto: RwLock::new(Some(crypto)),
             wal: RwLock::new(Some(wal)),
+            config,
+            metrics: Arc::new(NoopMetrics),
+            clock: Arc::new(SystemClock),
+            spawner: Arc::new(TokioSpawner::new()),
+            recorder: None,
+            events: EventBus::new(),
         }
     }
 
+    /// Set the metrics that the overlord reports to. The default metrics drops all the metrics.
+    pub fn with_metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Set the clock that the overlord gets the time from. The default clock follows the real
+    /// time.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Set the spawner that the overlord spawns its tasks by. The default spawner spawns onto the
+    /// tokio runtime in which `run` is called.
+    pub fn with_spawner(mut self, spawner: Arc<dyn Spawner>) -> Self {
+        self.spawner = spawner;
+        self
+    }
+
+    /// Set the recorder that the inputs of the consensus are written to, which can be replayed by
+    /// `Replay` offline. The overlord records nothing by default.
+    pub fn with_recorder(mut self, recorder: Arc<Recorder>) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
     /// Get the overlord handler from the overlord instance.
     pub fn get_handler(&self) -> OverlordHandler<T> {
         let sender = self.sender.write();
         assert!(sender.is_some());
         let tx = sender.clone().unwrap();
-        OverlordHandler::new(tx)
+        OverlordHandler::new(tx, self.events.clone())
     }
 
-    /// Run overlord consensus process. The `interval` is the height interval as millisecond.
-    pub async fn run(
-        &self,
-        init_height: u64,
-        interval: u64,
-        authority_list: Vec<Node>,
-        timer_config: Option<DurationConfig>,
-    ) -> ConsensusResult<()> {
+    /// Run overlord consensus process with the config given when creating. Return an error if the
+    /// config is invalid.
+    pub async fn run(&self, init_height: u64, authority_list: Vec<Node>) -> ConsensusResult<()> {
+        self.config.validate()?;
+
         let (mut smr_provider, evt_state, evt_timer) = SMR::new();
         let smr_handler = smr_provider.take_smr();
-        let timer = Timer::new(evt_timer, smr_handler.clone(), interval, timer_config);
+        let timer = Timer::new(
+            evt_timer,
+            smr_handler.clone(),
+            &self.config,
+            Arc::clone(&self.metrics),
+            Arc::clone(&self.clock),
+            Arc::clone(&self.spawner),
+            self.recorder.clone(),
+        );
         let (verify_sig_tx, verify_sig_rx) = unbounded();
 
-        let (rx, mut state, resp) = {
+        let (rx, mut state, resp, retry) = {
             let mut state_rx = self.state_rx.write();
             let mut address = self.address.write();
             let mut consensus = self.consensus.write();
@@ -73,16 +125,36 @@ where
             // let sender = self.sender.read();
 
             let tmp_rx = state_rx.take().unwrap();
-            let (tmp_state, tmp_resp) = State::new(
+            let addr = address.take().unwrap();
+            if let Some(recorder) = self.recorder.as_ref() {
+                recorder.record::<T>(
+                    self.clock.now(),
+                    Entry::Start {
+                        address: addr.clone(),
+                        init_height,
+                        authority_list: authority_list.clone(),
+                        config: self.config.clone(),
+                    },
+                );
+            }
+            let (tmp_state, tmp_resp, tmp_retry) = State::new(
                 smr_handler,
-                address.take().unwrap(),
+                addr,
                 init_height,
-                interval,
                 authority_list,
+                self.config.clone(),
                 verify_sig_tx,
                 consensus.take().unwrap(),
                 crypto.take().unwrap(),
                 wal.take().unwrap(),
+                Runtime {
+                    metrics: Arc::clone(&self.metrics),
+                    events: self.events.clone(),
+                    clock: Arc::clone(&self.clock),
+                    spawner: Arc::clone(&self.spawner),
+                    timeouts: timer.timer_config(),
+                    recorder: self.recorder.clone(),
+                },
             );
 
             // assert!(sender.is_none());
@@ -92,35 +164,43 @@ where
             assert!(state_rx.is_none());
             assert!(wal.is_none());
 
-            (tmp_rx, tmp_state, tmp_resp)
+            (tmp_rx, tmp_state, tmp_resp, tmp_retry)
         };
 
         log::info!("Overlord start running");
 
         // Run SMR.
-        smr_provider.run();
+        smr_provider.run(self.spawner.as_ref());
 
         // Run timer.
         timer.run();
 
         // Run state.
-        state.run(rx, evt_state, resp, verify_sig_rx).await;
+        state.run(rx, evt_state, resp, verify_sig_rx, retry).await;
 
         Ok(())
     }
 }
 
-/// An overlord handler to send messages to an overlord instance.
+/// An overlord handler to send messages to an overlord instance and subscribe the events of it.
 #[derive(Clone, Debug)]
-pub struct OverlordHandler<T: Codec>(UnboundedSender<(Context, OverlordMsg<T>)>);
+pub struct OverlordHandler<T: Codec>(UnboundedSender<(Context, OverlordMsg<T>)>, EventBus);
 
 impl<T: Codec> OverlordHandler<T> {
-    fn new(tx: UnboundedSender<(Context, OverlordMsg<T>)>) -> Self {
-        OverlordHandler(tx)
+    fn new(tx: UnboundedSender<(Context, OverlordMsg<T>)>, events: EventBus) -> Self {
+        OverlordHandler(tx, events)
+    }
+
+    /// Subscribe the events of the consensus process. The returned receiver gets the events
+    /// published after subscribing. Drop the receiver to unsubscribe.
+    pub fn subscribe(&self) -> UnboundedReceiver<OverlordEvent> {
+        self.1.subscribe()
     }
 
     /// Send overlord message to the instance. Return `Err()` when the message channel is closed.
+    /// With the `tracing` feature, the spans of handling the message follow the current span.
     pub fn send_msg(&self, ctx: Context, msg: OverlordMsg<T>) -> ConsensusResult<()> {
+        #[cfg(feature = "apm")]
         let ctx = match muta_apm::MUTA_TRACER.span(
             "overlord.send_msg_to_inner",
             vec![muta_apm::rustracing::tag::Tag::new("kind", "overlord")],
@@ -133,6 +213,8 @@ impl<T: Codec> OverlordHandler<T> {
             }
             None => ctx,
         };
+        #[cfg(feature = "tracing")]
+        let ctx = crate::utils::trace::with_current_span(ctx);
 
         if self.0.is_closed() {
             Err(ConsensusError::ChannelErr(
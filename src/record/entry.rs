@@ -0,0 +1,497 @@
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+use bytes::Bytes;
+use derive_more::Display;
+use hummer::coding::hex_encode;
+use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
+
+use crate::error::{ErrorSource, TransientError};
+use crate::smr::smr_types::SMREvent;
+use crate::state::process::RetryCall;
+use crate::types::{Address, Hash, Node, OverlordMsg, Status, VerifyResp};
+use crate::{Codec, OverlordConfig};
+
+/// A recorded entry with the duration since the first record.
+#[derive(Debug)]
+pub(crate) struct Record<T: Codec> {
+    pub(crate) at: Duration,
+    pub(crate) entry: Entry<T>,
+}
+
+/// An entry of a trace. The errors returned by the application are recorded as their messages
+/// and whether they are transient.
+#[derive(Debug)]
+pub(crate) enum Entry<T: Codec> {
+    /// The overlord starts.
+    Start {
+        address: Address,
+        init_height: u64,
+        authority_list: Vec<Node>,
+        config: OverlordConfig,
+    },
+    /// A message arrives from the network or the application.
+    Message(OverlordMsg<T>),
+    /// The signature of a message is verified.
+    Verified(OverlordMsg<T>),
+    /// An SMR event.
+    Event(SMREvent),
+    /// A block check response.
+    Resp(VerifyResp<T>),
+    /// The timeout of an SMR event fires, which triggers the SMR.
+    Timeout(SMREvent),
+    /// The result of `Wal::load`.
+    WalLoad(Result<Option<Bytes>, RecordedError>),
+    /// The result of an attempt of `Consensus::get_block`.
+    GetBlock(Result<(T, Hash), RecordedError>),
+    /// The result of `Consensus::should_propose`.
+    ShouldPropose(bool),
+    /// The result of an attempt of `Consensus::commit`.
+    Commit(Result<Status, RecordedError>),
+    /// The result of `Consensus::on_observed_commit`.
+    ObservedCommit(Result<Option<Status>, RecordedError>),
+    /// The result of `Consensus::get_authority_list`.
+    AuthorityList(Result<Vec<Node>, RecordedError>),
+    /// An application call is made again after the backoff.
+    Retry(RetryCall),
+}
+
+impl<T: Codec> Entry<T> {
+    /// Copy a block check response to record.
+    pub(crate) fn resp(resp: &VerifyResp<T>) -> Self {
+        Entry::Resp(VerifyResp {
+            height: resp.height,
+            round: resp.round,
+            block_hash: resp.block_hash.clone(),
+            is_pass: resp.is_pass,
+            is_timeout: resp.is_timeout,
+            invalid: resp
+                .invalid
+                .as_ref()
+                .map(|(sp, e)| (sp.clone(), RecordedError::new(e).source())),
+        })
+    }
+}
+
+/// Convert a result of the application to record.
+pub(crate) fn recorded<V: Clone>(res: &Result<V, ErrorSource>) -> Result<V, RecordedError> {
+    match res {
+        Ok(value) => Ok(value.clone()),
+        Err(e) => Err(RecordedError::new(e)),
+    }
+}
+
+/// An error of the application kept as its message and whether it is transient.
+#[derive(Clone, Display, PartialEq, Eq)]
+#[display(fmt = "{}", msg)]
+pub(crate) struct RecordedError {
+    msg: String,
+    transient: bool,
+}
+
+impl Error for RecordedError {}
+
+impl fmt::Debug for RecordedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.transient {
+            write!(f, "transient {:?}", self.msg)
+        } else {
+            write!(f, "{:?}", self.msg)
+        }
+    }
+}
+
+impl From<String> for RecordedError {
+    fn from(msg: String) -> Self {
+        RecordedError {
+            msg,
+            transient: false,
+        }
+    }
+}
+
+impl RecordedError {
+    pub(crate) fn new(e: &ErrorSource) -> Self {
+        RecordedError {
+            msg: e.to_string(),
+            transient: TransientError::is_transient(e),
+        }
+    }
+
+    /// Restore the error returned by the application.
+    pub(crate) fn source(self) -> ErrorSource {
+        if self.transient {
+            Box::new(TransientError(Box::new(self)))
+        } else {
+            Box::new(self)
+        }
+    }
+}
+
+impl<T: Codec> fmt::Display for Entry<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Entry::Start {
+                address,
+                init_height,
+                authority_list,
+                ..
+            } => write!(
+                f,
+                "start {} at height {} with {} authorities",
+                short(address),
+                init_height,
+                authority_list.len()
+            ),
+            Entry::Message(msg) => write!(f, "receive {}", describe(msg)),
+            Entry::Verified(msg) => write!(f, "verified {}", describe(msg)),
+            Entry::Event(event) => write!(f, "SMR {}", event),
+            Entry::Resp(resp) => write!(
+                f,
+                "check block height {}, round {}, hash {}: {}",
+                resp.height,
+                resp.round,
+                short(&resp.block_hash),
+                if resp.is_pass {
+                    "pass".to_string()
+                } else if resp.is_timeout {
+                    "timeout".to_string()
+                } else {
+                    format!(
+                        "fail {:?}",
+                        resp.invalid.as_ref().map(|(_, e)| e.to_string())
+                    )
+                }
+            ),
+            Entry::Timeout(event) => write!(f, "timeout of {}", event),
+            Entry::WalLoad(res) => write!(
+                f,
+                "load wal {:?}",
+                res.as_ref().map(|info| info.as_ref().map(Bytes::len))
+            ),
+            Entry::GetBlock(res) => write!(
+                f,
+                "get block {:?}",
+                res.as_ref().map(|(_, hash)| short(hash))
+            ),
+            Entry::ShouldPropose(propose) => write!(f, "should propose {}", propose),
+            Entry::Commit(res) => {
+                write!(f, "commit {:?}", res.as_ref().map(|status| status.height))
+            }
+            Entry::ObservedCommit(res) => write!(
+                f,
+                "observed commit {:?}",
+                res.as_ref()
+                    .map(|status| status.as_ref().map(|status| status.height))
+            ),
+            Entry::AuthorityList(res) => {
+                write!(f, "get authority list {:?}", res.as_ref().map(Vec::len))
+            }
+            Entry::Retry(RetryCall::GetBlock {
+                height,
+                round,
+                attempt,
+            }) => write!(
+                f,
+                "retry get block height {}, round {}, attempt {}",
+                height, round, attempt
+            ),
+            Entry::Retry(RetryCall::Commit {
+                height,
+                hash,
+                attempt,
+            }) => write!(
+                f,
+                "retry commit height {}, hash {}, attempt {}",
+                height,
+                short(hash),
+                attempt
+            ),
+        }
+    }
+}
+
+/// Describe a message in one line.
+pub(crate) fn describe<T: Codec>(msg: &OverlordMsg<T>) -> String {
+    match msg {
+        OverlordMsg::SignedProposal(sp) => format!(
+            "proposal height {}, round {}, hash {}, lock round {:?} from {}",
+            sp.proposal.height,
+            sp.proposal.round,
+            short(&sp.proposal.block_hash),
+            sp.proposal.lock.as_ref().map(|polc| polc.lock_round),
+            short(&sp.proposal.proposer)
+        ),
+        OverlordMsg::SignedVote(sv) => format!(
+            "{:?} vote height {}, round {}, hash {} from {}",
+            sv.vote.vote_type,
+            sv.vote.height,
+            sv.vote.round,
+            short(&sv.vote.block_hash),
+            short(&sv.voter)
+        ),
+        OverlordMsg::AggregatedVote(qc) => format!(
+            "{:?} QC height {}, round {}, hash {}",
+            qc.vote_type,
+            qc.height,
+            qc.round,
+            short(&qc.block_hash)
+        ),
+        OverlordMsg::SignedChoke(sc) => format!(
+            "choke height {}, round {} from {}",
+            sc.choke.height,
+            sc.choke.round,
+            short(&sc.address)
+        ),
+        OverlordMsg::RichStatus(status) => format!(
+            "rich status height {} with {} authorities",
+            status.height,
+            status.authority_list.len()
+        ),
+        OverlordMsg::Stop => "stop".to_string(),
+        #[cfg(test)]
+        OverlordMsg::Commit(commit) => format!("commit height {}", commit.height),
+    }
+}
+
+/// The first 4 bytes of a hash or an address in hex.
+pub(crate) fn short(hash: &Bytes) -> String {
+    if hash.is_empty() {
+        return "nil".to_string();
+    }
+    hex_encode(hash.slice(..hash.len().min(4)))
+}
+
+impl<T: Codec> Encodable for Record<T> {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        let at = self.at.as_micros() as u64;
+        match &self.entry {
+            Entry::Start {
+                address,
+                init_height,
+                authority_list,
+                config,
+            } => {
+                let config = serde_json::to_vec(config).unwrap_or_default();
+                s.begin_list(6)
+                    .append(&0u8)
+                    .append(&at)
+                    .append(&address.to_vec())
+                    .append(init_height)
+                    .append_list(authority_list)
+                    .append(&config);
+            }
+            Entry::Message(msg) => {
+                s.begin_list(3).append(&1u8).append(&at).append(msg);
+            }
+            Entry::Verified(msg) => {
+                s.begin_list(3).append(&2u8).append(&at).append(msg);
+            }
+            Entry::Event(event) => {
+                s.begin_list(3).append(&3u8).append(&at).append(event);
+            }
+            Entry::Resp(resp) => {
+                s.begin_list(8)
+                    .append(&4u8)
+                    .append(&at)
+                    .append(&resp.height)
+                    .append(&resp.round)
+                    .append(&resp.block_hash.to_vec())
+                    .append(&resp.is_pass)
+                    .append(&resp.is_timeout);
+                match &resp.invalid {
+                    Some((sp, e)) => {
+                        s.begin_list(3)
+                            .append(sp)
+                            .append(&e.to_string())
+                            .append(&TransientError::is_transient(e));
+                    }
+                    None => {
+                        s.begin_list(0);
+                    }
+                }
+            }
+            Entry::Timeout(event) => {
+                s.begin_list(3).append(&5u8).append(&at).append(event);
+            }
+            Entry::WalLoad(res) => {
+                s.begin_list(3).append(&6u8).append(&at);
+                append_result(s, res, |s, info| {
+                    s.append(&info.as_ref().map(|info| info.to_vec()));
+                });
+            }
+            Entry::GetBlock(res) => {
+                s.begin_list(3).append(&7u8).append(&at);
+                append_result(s, res, |s, (block, hash)| {
+                    let content = block.encode().unwrap().to_vec();
+                    s.begin_list(2).append(&content).append(&hash.to_vec());
+                });
+            }
+            Entry::ShouldPropose(propose) => {
+                s.begin_list(3).append(&8u8).append(&at).append(propose);
+            }
+            Entry::Commit(res) => {
+                s.begin_list(3).append(&9u8).append(&at);
+                append_result(s, res, |s, status| {
+                    s.append(status);
+                });
+            }
+            Entry::ObservedCommit(res) => {
+                s.begin_list(3).append(&10u8).append(&at);
+                append_result(s, res, |s, status| {
+                    s.append(status);
+                });
+            }
+            Entry::AuthorityList(res) => {
+                s.begin_list(3).append(&11u8).append(&at);
+                append_result(s, res, |s, authority_list| {
+                    s.append_list(authority_list);
+                });
+            }
+            Entry::Retry(RetryCall::GetBlock {
+                height,
+                round,
+                attempt,
+            }) => {
+                s.begin_list(6)
+                    .append(&12u8)
+                    .append(&at)
+                    .append(&0u8)
+                    .append(height)
+                    .append(round)
+                    .append(attempt);
+            }
+            Entry::Retry(RetryCall::Commit {
+                height,
+                hash,
+                attempt,
+            }) => {
+                s.begin_list(6)
+                    .append(&12u8)
+                    .append(&at)
+                    .append(&1u8)
+                    .append(height)
+                    .append(&hash.to_vec())
+                    .append(attempt);
+            }
+        }
+    }
+}
+
+impl<T: Codec> Decodable for Record<T> {
+    fn decode(r: &Rlp) -> Result<Self, DecoderError> {
+        if !r.is_list() {
+            return Err(DecoderError::RlpExpectedToBeList);
+        }
+        let tag: u8 = r.val_at(0)?;
+        let at: u64 = r.val_at(1)?;
+        let entry = match (tag, r.item_count()?) {
+            (0u8, 6) => {
+                let tmp: Vec<u8> = r.val_at(2)?;
+                let config: Vec<u8> = r.val_at(5)?;
+                Entry::Start {
+                    address: Address::from(tmp),
+                    init_height: r.val_at(3)?,
+                    authority_list: r.list_at(4)?,
+                    config: serde_json::from_slice(&config)
+                        .map_err(|_| DecoderError::Custom("Config decode error."))?,
+                }
+            }
+            (1u8, 3) => Entry::Message(r.val_at(2)?),
+            (2u8, 3) => Entry::Verified(r.val_at(2)?),
+            (3u8, 3) => Entry::Event(r.val_at(2)?),
+            (4u8, 8) => {
+                let tmp: Vec<u8> = r.val_at(4)?;
+                let invalid = r.at(7)?;
+                let invalid = if invalid.item_count()? == 3 {
+                    let e = RecordedError {
+                        msg: invalid.val_at(1)?,
+                        transient: invalid.val_at(2)?,
+                    };
+                    Some((invalid.val_at(0)?, e.source()))
+                } else {
+                    None
+                };
+                Entry::Resp(VerifyResp {
+                    height: r.val_at(2)?,
+                    round: r.val_at(3)?,
+                    block_hash: Hash::from(tmp),
+                    is_pass: r.val_at(5)?,
+                    is_timeout: r.val_at(6)?,
+                    invalid,
+                })
+            }
+            (5u8, 3) => Entry::Timeout(r.val_at(2)?),
+            (6u8, 3) => Entry::WalLoad(decode_result(&r.at(2)?, |r| {
+                let tmp: Option<Vec<u8>> = r.as_val()?;
+                Ok(tmp.map(Bytes::from))
+            })?),
+            (7u8, 3) => Entry::GetBlock(decode_result(&r.at(2)?, |r| {
+                let content: Vec<u8> = r.val_at(0)?;
+                let hash: Vec<u8> = r.val_at(1)?;
+                let block = Codec::decode(Bytes::from(content))
+                    .map_err(|_| DecoderError::Custom("Codec decode error."))?;
+                Ok((block, Hash::from(hash)))
+            })?),
+            (8u8, 3) => Entry::ShouldPropose(r.val_at(2)?),
+            (9u8, 3) => Entry::Commit(decode_result(&r.at(2)?, |r| r.as_val())?),
+            (10u8, 3) => Entry::ObservedCommit(decode_result(&r.at(2)?, |r| r.as_val())?),
+            (11u8, 3) => Entry::AuthorityList(decode_result(&r.at(2)?, |r| r.as_list())?),
+            (12u8, 6) => match r.val_at::<u8>(2)? {
+                0 => Entry::Retry(RetryCall::GetBlock {
+                    height: r.val_at(3)?,
+                    round: r.val_at(4)?,
+                    attempt: r.val_at(5)?,
+                }),
+                1 => {
+                    let hash: Vec<u8> = r.val_at(4)?;
+                    Entry::Retry(RetryCall::Commit {
+                        height: r.val_at(3)?,
+                        hash: Hash::from(hash),
+                        attempt: r.val_at(5)?,
+                    })
+                }
+                _ => return Err(DecoderError::Custom("Invalid retry call tag.")),
+            },
+            _ => return Err(DecoderError::RlpInconsistentLengthAndData),
+        };
+        Ok(Record {
+            at: Duration::from_micros(at),
+            entry,
+        })
+    }
+}
+
+fn append_result<V, F>(s: &mut RlpStream, res: &Result<V, RecordedError>, append: F)
+where
+    F: FnOnce(&mut RlpStream, &V),
+{
+    match res {
+        Ok(value) => {
+            s.begin_list(2).append(&0u8);
+            append(s, value);
+        }
+        Err(e) => {
+            s.begin_list(3)
+                .append(&1u8)
+                .append(&e.msg)
+                .append(&e.transient);
+        }
+    }
+}
+
+fn decode_result<V, F>(r: &Rlp, decode: F) -> Result<Result<V, RecordedError>, DecoderError>
+where
+    F: FnOnce(&Rlp) -> Result<V, DecoderError>,
+{
+    let tag: u8 = r.val_at(0)?;
+    match tag {
+        0u8 => Ok(Ok(decode(&r.at(1)?)?)),
+        1u8 => Ok(Err(RecordedError {
+            msg: r.val_at(1)?,
+            transient: r.val_at(2)?,
+        })),
+        _ => Err(DecoderError::Custom("Invalid result tag.")),
+    }
+}
@@ -0,0 +1,368 @@
+mod entry;
+mod replay;
+
+pub(crate) use self::entry::{recorded, Entry, Record};
+pub use self::replay::{Replay, ReplayStep};
+
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, sync_channel, Receiver, Sender, SyncSender, TryRecvError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+
+use derive_more::Display;
+use parking_lot::Mutex;
+
+use crate::smr::smr_types::SMREvent;
+use crate::types::RawBlock;
+use crate::Codec;
+
+/// The magic bytes at the beginning of a trace.
+const MAGIC: &[u8; 4] = b"OVLT";
+/// The version of the trace format.
+const VERSION: u8 = 1;
+
+/// The error of recording or replaying a trace.
+#[derive(Debug, Display)]
+pub enum TraceError {
+    /// Reading or writing the trace failed.
+    #[display(fmt = "trace io error {}", _0)]
+    Io(io::Error),
+    /// The trace is not in the trace format.
+    #[display(fmt = "invalid trace {}", _0)]
+    Format(String),
+}
+
+impl Error for TraceError {}
+
+impl From<io::Error> for TraceError {
+    fn from(e: io::Error) -> Self {
+        TraceError::Io(e)
+    }
+}
+
+/// A recorder which writes every input of the consensus state to a trace, so that the state can
+/// be replayed offline by `Replay`. The inputs are the messages from the network with their
+/// arrival time, the SMR events, the block check responses, the results of the application
+/// callbacks and the wal and the timer firings. The trace starts with the magic bytes and the
+/// version, followed by the records each of which is a big endian `u32` length and an RLP item.
+///
+/// The records are encoded by the caller and written by a writer thread, so that recording never
+/// waits for the io. The writer flushes whenever no record is queued, so the trace of a stalled
+/// node is complete, and writes the queued records before the recorder is dropped. A recorder
+/// stops recording after a write error.
+pub struct Recorder {
+    inner: Mutex<RecorderInner>,
+    stopped: Arc<AtomicBool>,
+}
+
+struct RecorderInner {
+    tx: Option<Sender<Command>>,
+    writer: Option<JoinHandle<()>>,
+    start: Option<Instant>,
+}
+
+/// A command to the writer thread of a recorder.
+enum Command {
+    Write(Vec<u8>),
+    Flush(SyncSender<()>),
+}
+
+impl fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let inner = self.inner.lock();
+        f.debug_struct("Recorder")
+            .field("recording", &!self.stopped.load(Ordering::Relaxed))
+            .field("start", &inner.start)
+            .finish()
+    }
+}
+
+impl Recorder {
+    /// Create a recorder which writes the trace to the writer on a writer thread.
+    pub fn new<W: Write + Send + 'static>(writer: W) -> Self {
+        let (tx, rx) = channel();
+        let stopped = Arc::new(AtomicBool::new(false));
+        let writer_stopped = Arc::clone(&stopped);
+        let writer = thread::Builder::new()
+            .name("overlord-recorder".to_string())
+            .spawn(move || write_trace(writer, rx, &writer_stopped))
+            .map_err(|e| log::error!("Overlord: recorder spawn writer error {:?}", e))
+            .ok();
+        stopped.store(writer.is_none(), Ordering::Relaxed);
+        Recorder {
+            inner: Mutex::new(RecorderInner {
+                tx: Some(tx),
+                writer,
+                start: None,
+            }),
+            stopped,
+        }
+    }
+
+    /// Create a recorder which writes the trace to the file of the given path.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, TraceError> {
+        let file = File::create(path)?;
+        Ok(Recorder::new(BufWriter::new(file)))
+    }
+
+    /// Wait until the records so far are written and flushed.
+    pub fn flush(&self) {
+        let (done_tx, done_rx) = sync_channel(1);
+        let sent = match self.inner.lock().tx.as_ref() {
+            Some(tx) => tx.send(Command::Flush(done_tx)).is_ok(),
+            None => false,
+        };
+        if sent {
+            let _ = done_rx.recv();
+        }
+    }
+
+    /// Record an entry at the given time. The time of a record is the duration since the first
+    /// record.
+    pub(crate) fn record<T: Codec>(&self, now: Instant, entry: Entry<T>) {
+        if self.stopped.load(Ordering::Relaxed) {
+            return;
+        }
+        let start = *self.inner.lock().start.get_or_insert(now);
+        let record = Record {
+            at: now.saturating_duration_since(start),
+            entry,
+        };
+        let data = rlp::encode(&record);
+        let mut frame = Vec::with_capacity(data.len() + 4);
+        frame.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&data);
+
+        if let Some(tx) = self.inner.lock().tx.as_ref() {
+            let _ = tx.send(Command::Write(frame));
+        }
+    }
+
+    /// Record an SMR event of which the timeout fires.
+    pub(crate) fn record_timeout(&self, now: Instant, event: SMREvent) {
+        self.record::<RawBlock>(now, Entry::Timeout(event));
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        let mut inner = self.inner.lock();
+        inner.tx = None;
+        if let Some(writer) = inner.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+/// Write the header and the records received to the writer until the recorder is dropped. The
+/// writer is flushed whenever no record is queued.
+fn write_trace<W: Write>(mut writer: W, rx: Receiver<Command>, stopped: &AtomicBool) {
+    let mut res = writer
+        .write_all(MAGIC)
+        .and_then(|_| writer.write_all(&[VERSION]));
+    loop {
+        let command = match rx.try_recv() {
+            Ok(command) => command,
+            Err(TryRecvError::Empty) => {
+                if res.is_ok() {
+                    res = writer.flush();
+                }
+                match rx.recv() {
+                    Ok(command) => command,
+                    Err(_) => break,
+                }
+            }
+            Err(TryRecvError::Disconnected) => break,
+        };
+        match command {
+            Command::Write(frame) => {
+                if res.is_ok() {
+                    res = writer.write_all(&frame);
+                }
+            }
+            Command::Flush(done) => {
+                if res.is_ok() {
+                    res = writer.flush();
+                }
+                let _ = done.send(());
+            }
+        }
+        if let Err(e) = res.as_ref() {
+            if !stopped.swap(true, Ordering::Relaxed) {
+                log::error!("Overlord: recorder write error {:?}, stop recording", e);
+            }
+        }
+    }
+    if res.is_ok() {
+        let _ = writer.flush();
+    }
+}
+
+/// Read the records of a trace. A record truncated at the end, which is left by a node killed in
+/// the middle of writing, is ignored.
+pub(crate) fn read_trace<T: Codec, R: Read>(reader: R) -> Result<Vec<Record<T>>, TraceError> {
+    let mut reader = BufReader::new(reader);
+    let mut header = [0u8; 5];
+    reader.read_exact(&mut header)?;
+    if &header[..4] != MAGIC {
+        return Err(TraceError::Format("wrong magic bytes".to_string()));
+    }
+    if header[4] != VERSION {
+        return Err(TraceError::Format(format!(
+            "unsupported version {}",
+            header[4]
+        )));
+    }
+
+    let mut records = Vec::new();
+    loop {
+        let mut len = [0u8; 4];
+        let mut data = Vec::new();
+        match reader.read_exact(&mut len) {
+            Ok(()) => (),
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into()),
+        }
+        data.resize(u32::from_be_bytes(len) as usize, 0);
+        match reader.read_exact(&mut data) {
+            Ok(()) => (),
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+                log::warn!("Overlord: ignore a truncated record at the end of the trace");
+                break;
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        let record = rlp::decode(&data).map_err(|e| {
+            TraceError::Format(format!("record {} decode error {:?}", records.len(), e))
+        })?;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{self, Write};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    use bytes::Bytes;
+    use parking_lot::Mutex;
+
+    use crate::error::{ErrorSource, TransientError};
+    use crate::record::{read_trace, Entry, Recorder};
+    use crate::smr::smr_types::SMREvent;
+    use crate::types::{OverlordMsg, Proposal, RawBlock, SignedProposal, Status, VerifyResp};
+
+    #[derive(Clone, Default)]
+    struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_recorder() {
+        let buf = SharedBuf::default();
+        let recorder = Recorder::new(buf.clone());
+        let start = Instant::now();
+        let status = Status {
+            height: 2,
+            interval: Some(3000),
+            timer_config: None,
+            authority_list: Vec::new(),
+        };
+        recorder.record::<RawBlock>(
+            start,
+            Entry::Message(OverlordMsg::RichStatus(status.clone())),
+        );
+        recorder.record_timeout(start + Duration::from_millis(5), SMREvent::Stop);
+        recorder.record::<RawBlock>(
+            start + Duration::from_millis(7),
+            Entry::Commit(Err("commit error".to_string().into())),
+        );
+        recorder.flush();
+
+        // A record cut off in the middle is ignored.
+        let mut data = buf.0.lock().clone();
+        data.extend_from_slice(&[0, 0, 0, 9, 1]);
+        let records = read_trace::<RawBlock, _>(data.as_slice()).unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].at, Duration::ZERO);
+        assert!(matches!(
+            &records[0].entry,
+            Entry::Message(OverlordMsg::RichStatus(s)) if *s == status
+        ));
+        assert_eq!(records[1].at, Duration::from_millis(5));
+        assert!(matches!(records[1].entry, Entry::Timeout(SMREvent::Stop)));
+        assert!(
+            matches!(&records[2].entry, Entry::Commit(Err(e)) if e.to_string() == "commit error")
+        );
+
+        data[0] = b'X';
+        assert!(read_trace::<RawBlock, _>(data.as_slice()).is_err());
+        assert!(read_trace::<RawBlock, _>(Bytes::new().as_ref()).is_err());
+    }
+
+    #[test]
+    fn test_record_check_resp() {
+        let buf = SharedBuf::default();
+        let recorder = Recorder::new(buf.clone());
+        let sp = SignedProposal {
+            signature: Bytes::from("signature"),
+            proposal: Proposal {
+                height: 1,
+                round: 0,
+                content: RawBlock(Bytes::from("block")),
+                block_hash: Bytes::from("hash"),
+                lock: None,
+                proposer: Bytes::from("proposer"),
+            },
+        };
+        let now = Instant::now();
+        for error in [
+            TransientError::boxed(io::Error::from(io::ErrorKind::TimedOut)),
+            Box::new(io::Error::from(io::ErrorKind::InvalidData)) as ErrorSource,
+        ] {
+            let resp = VerifyResp {
+                height: 1,
+                round: 0,
+                block_hash: Bytes::from("hash"),
+                is_pass: false,
+                is_timeout: false,
+                invalid: Some((sp.clone(), error)),
+            };
+            recorder.record(now, Entry::resp(&resp));
+        }
+        recorder.flush();
+
+        // A replayed check failure is still told apart as transient or a rejection.
+        let data = buf.0.lock().clone();
+        let transient = read_trace::<RawBlock, _>(data.as_slice())
+            .unwrap()
+            .into_iter()
+            .map(|record| match record.entry {
+                Entry::Resp(VerifyResp {
+                    invalid: Some((replayed, e)),
+                    ..
+                }) if replayed == sp => TransientError::is_transient(&e),
+                _ => panic!("not a failed check"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(transient, vec![true, false]);
+    }
+}
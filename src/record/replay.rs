@@ -0,0 +1,593 @@
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use creep::Context;
+use futures::channel::mpsc::{unbounded, UnboundedReceiver};
+use futures::future::{self, BoxFuture, FutureExt};
+use parking_lot::Mutex;
+
+use crate::error::{ConsensusError, ErrorSource};
+use crate::record::entry::{describe, short, RecordedError};
+use crate::record::{read_trace, Entry, Record, TraceError};
+use crate::smr::smr_types::SMRTrigger;
+use crate::smr::SMRHandler;
+use crate::state::process::{Runtime, State, StateInput};
+use crate::types::{
+    Address, Commit, Hash, InvalidProposal, Node, OverlordMsg, Proof, RawBlock, Signature, Status,
+    ViewChangeReason,
+};
+use crate::utils::event_bus::EventBus;
+use crate::utils::timer_config::TimerConfig;
+use crate::{Clock, Consensus, Crypto, NoopMetrics, Spawner, Wal};
+
+/// A replay of a trace written by `Recorder`. The recorded inputs are fed one by one into a fresh
+/// consensus state, which runs with a mocked application returning the recorded results, a crypto
+/// that accepts every signature and a wal returning the recorded information. The signature
+/// verifications and the block checks are not run again, since their results are in the trace as
+/// the verified messages and the check responses. The timer and the SMR are not run either, and
+/// their outputs are in the trace as the timeouts and the SMR events.
+#[derive(Debug)]
+pub struct Replay {
+    state: State<RawBlock, ReplayConsensus, ReplayCrypto, ReplayWal>,
+    inputs: VecDeque<Record<RawBlock>>,
+    consensus: Arc<ReplayConsensus>,
+    clock: Arc<ReplayClock>,
+    triggers: UnboundedReceiver<SMRTrigger>,
+    started: bool,
+    stopped: bool,
+}
+
+impl Replay {
+    /// Load the trace of the given path.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, TraceError> {
+        Replay::from_reader(File::open(path)?)
+    }
+
+    /// Load a trace from the reader.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, TraceError> {
+        let mut records = read_trace::<RawBlock, R>(reader)?.into_iter();
+        let (address, init_height, authority_list, config) = match records.next() {
+            Some(Record {
+                entry:
+                    Entry::Start {
+                        address,
+                        init_height,
+                        authority_list,
+                        config,
+                    },
+                ..
+            }) => (address, init_height, authority_list, config),
+            _ => {
+                return Err(TraceError::Format(
+                    "the trace does not begin with a start".to_string(),
+                ))
+            }
+        };
+        let consensus = Arc::new(ReplayConsensus::default());
+        let wal = Arc::new(ReplayWal::default());
+        let mut inputs = VecDeque::new();
+        for record in records {
+            match record.entry {
+                Entry::WalLoad(res) => wal.loads.lock().push_back(res),
+                Entry::GetBlock(res) => consensus.blocks.lock().push_back(res),
+                Entry::ShouldPropose(res) => consensus.proposes.lock().push_back(res),
+                Entry::Commit(res) => consensus.commits.lock().push_back(res),
+                Entry::ObservedCommit(res) => consensus.observed.lock().push_back(res),
+                Entry::AuthorityList(res) => consensus.authority_lists.lock().push_back(res),
+                _ => inputs.push_back(record),
+            }
+        }
+
+        let (trigger_tx, triggers) = unbounded();
+        let (verify_tx, _) = unbounded();
+        let clock = Arc::new(ReplayClock::new());
+        let timeouts = Arc::new(Mutex::new(TimerConfig::from_config(&config)));
+        let (state, _, _) = State::new(
+            SMRHandler::new(trigger_tx),
+            address,
+            init_height,
+            authority_list,
+            config,
+            verify_tx,
+            Arc::clone(&consensus),
+            Arc::new(ReplayCrypto),
+            wal,
+            Runtime {
+                metrics: Arc::new(NoopMetrics),
+                events: EventBus::new(),
+                clock: Arc::<ReplayClock>::clone(&clock),
+                spawner: Arc::new(DropSpawner),
+                timeouts,
+                recorder: None,
+            },
+        );
+
+        Ok(Replay {
+            state,
+            inputs,
+            consensus,
+            clock,
+            triggers,
+            started: false,
+            stopped: false,
+        })
+    }
+
+    /// Get the number of the inputs left.
+    pub fn remaining(&self) -> usize {
+        self.inputs.len()
+    }
+
+    /// Feed the next input into the state and return what the state does, or `None` if the trace
+    /// ends or the state stops. The first step starts the state with the recorded wal.
+    pub async fn step(&mut self) -> Option<ReplayStep> {
+        if self.stopped {
+            return None;
+        }
+
+        let (at, input) = if !self.started {
+            self.started = true;
+            if let Err(e) = self.state.start_with_wal().await {
+                self.consensus
+                    .output(format!("start with wal error {:?}", e));
+            }
+            (Duration::ZERO, "start with wal".to_string())
+        } else {
+            let record = self.inputs.pop_front()?;
+            self.clock.set(record.at);
+            let input = record.entry.to_string();
+            let state_input = match record.entry {
+                Entry::Message(msg) => Some(StateInput::Raw(Some((Context::new(), msg)))),
+                Entry::Verified(msg) => Some(StateInput::Verified(Some((Context::new(), msg)))),
+                Entry::Event(event) => Some(StateInput::Event(Some(event))),
+                Entry::Resp(resp) => Some(StateInput::Resp(Some(resp))),
+                Entry::Retry(call) => Some(StateInput::Retry(Some(call))),
+                _ => None,
+            };
+            if let Some(state_input) = state_input {
+                self.stopped = !self.state.handle_input(state_input).await;
+            }
+            (record.at, input)
+        };
+
+        let mut outputs = std::mem::take(&mut *self.consensus.outputs.lock());
+        while let Ok(trigger) = self.triggers.try_recv() {
+            outputs.push(format!(
+                "trigger SMR {} from {}, height {}, round {}, hash {}",
+                trigger.trigger_type,
+                trigger.source,
+                trigger.height,
+                trigger.round,
+                short(&trigger.hash)
+            ));
+        }
+
+        Some(ReplayStep {
+            at,
+            input,
+            height: self.state.height(),
+            round: self.state.round(),
+            outputs,
+        })
+    }
+}
+
+/// A step of a replay.
+#[derive(Clone, Debug)]
+pub struct ReplayStep {
+    /// The time of the input since the first record.
+    pub at: Duration,
+    /// The description of the input.
+    pub input: String,
+    /// The height of the state after the input.
+    pub height: u64,
+    /// The round of the state after the input.
+    pub round: u64,
+    /// The descriptions of what the state does on the input, including the calls to the
+    /// application and the SMR triggers.
+    pub outputs: Vec<String>,
+}
+
+impl fmt::Display for ReplayStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[{:?}] height {}, round {}: {}",
+            self.at, self.height, self.round, self.input
+        )?;
+        for output in self.outputs.iter() {
+            write!(f, "\n    {}", output)?;
+        }
+        Ok(())
+    }
+}
+
+/// A clock at the time of the input being replayed, of which the sleeps complete at once.
+#[derive(Debug)]
+struct ReplayClock {
+    start: Instant,
+    at: Mutex<Duration>,
+}
+
+impl ReplayClock {
+    fn new() -> Self {
+        ReplayClock {
+            start: Instant::now(),
+            at: Mutex::new(Duration::ZERO),
+        }
+    }
+
+    fn set(&self, at: Duration) {
+        *self.at.lock() = at;
+    }
+}
+
+impl Clock for ReplayClock {
+    fn now(&self) -> Instant {
+        self.start + *self.at.lock()
+    }
+
+    fn sleep(&self, _duration: Duration) -> BoxFuture<'static, ()> {
+        future::ready(()).boxed()
+    }
+}
+
+/// A spawner that drops the tasks, which are the signature verifications and the block checks.
+#[derive(Debug)]
+struct DropSpawner;
+
+impl Spawner for DropSpawner {
+    fn spawn(&self, _future: BoxFuture<'static, ()>) {}
+}
+
+/// An application that returns the recorded results in order and describes the other calls.
+#[derive(Debug, Default)]
+struct ReplayConsensus {
+    blocks: Mutex<VecDeque<Result<(RawBlock, Hash), RecordedError>>>,
+    proposes: Mutex<VecDeque<bool>>,
+    commits: Mutex<VecDeque<Result<Status, RecordedError>>>,
+    observed: Mutex<VecDeque<Result<Option<Status>, RecordedError>>>,
+    authority_lists: Mutex<VecDeque<Result<Vec<Node>, RecordedError>>>,
+    outputs: Mutex<Vec<String>>,
+}
+
+impl ReplayConsensus {
+    fn output(&self, output: String) {
+        self.outputs.lock().push(output);
+    }
+}
+
+/// Take the next recorded result. The trace may end in the middle of a call.
+fn next<V>(
+    queue: &Mutex<VecDeque<Result<V, RecordedError>>>,
+    call: &str,
+) -> Result<V, ErrorSource> {
+    queue
+        .lock()
+        .pop_front()
+        .unwrap_or_else(|| Err(format!("no recorded result of {}", call).into()))
+        .map_err(RecordedError::source)
+}
+
+#[async_trait]
+impl Consensus<RawBlock> for ReplayConsensus {
+    async fn get_block(
+        &self,
+        _ctx: Context,
+        height: u64,
+    ) -> Result<(RawBlock, Hash), Box<dyn Error + Send>> {
+        self.output(format!("get block height {}", height));
+        next(&self.blocks, "get block")
+    }
+
+    async fn should_propose(
+        &self,
+        _ctx: Context,
+        _height: u64,
+        _round: u64,
+        _elapsed: Duration,
+    ) -> bool {
+        self.proposes.lock().pop_front().unwrap_or(true)
+    }
+
+    async fn check_block(
+        &self,
+        _ctx: Context,
+        _height: u64,
+        _hash: Hash,
+        _block: RawBlock,
+    ) -> Result<(), Box<dyn Error + Send>> {
+        Ok(())
+    }
+
+    async fn commit(
+        &self,
+        _ctx: Context,
+        height: u64,
+        commit: Commit<RawBlock>,
+    ) -> Result<Status, Box<dyn Error + Send>> {
+        self.output(format!(
+            "commit height {}, hash {}",
+            height,
+            short(&commit.proof.block_hash)
+        ));
+        next(&self.commits, "commit")
+    }
+
+    async fn on_observed_commit(
+        &self,
+        _ctx: Context,
+        height: u64,
+        commit: Commit<RawBlock>,
+    ) -> Result<Option<Status>, Box<dyn Error + Send>> {
+        self.output(format!(
+            "observed commit height {}, hash {}",
+            height,
+            short(&commit.proof.block_hash)
+        ));
+        next(&self.observed, "observed commit")
+    }
+
+    async fn request_sync(
+        &self,
+        _ctx: Context,
+        target_height: u64,
+        _proof: Proof,
+    ) -> Result<(), Box<dyn Error + Send>> {
+        self.output(format!("request sync to height {}", target_height));
+        Ok(())
+    }
+
+    async fn get_authority_list(
+        &self,
+        _ctx: Context,
+        _height: u64,
+    ) -> Result<Vec<Node>, Box<dyn Error + Send>> {
+        next(&self.authority_lists, "get authority list")
+    }
+
+    async fn broadcast_to_other(
+        &self,
+        _ctx: Context,
+        msg: OverlordMsg<RawBlock>,
+    ) -> Result<(), Box<dyn Error + Send>> {
+        self.output(format!("broadcast {}", describe(&msg)));
+        Ok(())
+    }
+
+    async fn transmit_to_relayer(
+        &self,
+        _ctx: Context,
+        addr: Address,
+        msg: OverlordMsg<RawBlock>,
+    ) -> Result<(), Box<dyn Error + Send>> {
+        self.output(format!("transmit {} to {}", describe(&msg), short(&addr)));
+        Ok(())
+    }
+
+    fn report_error(&self, _ctx: Context, error: ConsensusError) {
+        self.output(format!("report error {}", error));
+    }
+
+    fn report_view_change(&self, _ctx: Context, height: u64, round: u64, reason: ViewChangeReason) {
+        self.output(format!(
+            "report view change height {}, round {}, reason {}",
+            height, round, reason
+        ));
+    }
+
+    fn report_invalid_proposal(&self, _ctx: Context, invalid: InvalidProposal<RawBlock>) {
+        self.output(format!(
+            "report invalid proposal height {}, round {}, hash {}",
+            invalid.signed_proposal.proposal.height,
+            invalid.signed_proposal.proposal.round,
+            short(&invalid.signed_proposal.proposal.block_hash)
+        ));
+    }
+}
+
+/// A crypto that accepts every signature.
+#[derive(Debug)]
+struct ReplayCrypto;
+
+impl Crypto for ReplayCrypto {
+    fn hash(&self, msg: Bytes) -> Hash {
+        msg
+    }
+
+    fn sign(&self, _hash: Hash) -> Result<Signature, Box<dyn Error + Send>> {
+        Ok(Signature::new())
+    }
+
+    fn aggregate_signatures(
+        &self,
+        _signatures: Vec<Signature>,
+        _voters: Vec<Address>,
+    ) -> Result<Signature, Box<dyn Error + Send>> {
+        Ok(Signature::new())
+    }
+
+    fn verify_signature(
+        &self,
+        _signature: Signature,
+        _hash: Hash,
+        _voter: Address,
+    ) -> Result<(), Box<dyn Error + Send>> {
+        Ok(())
+    }
+
+    fn verify_aggregated_signature(
+        &self,
+        _aggregate_signature: Signature,
+        _msg_hash: Hash,
+        _voters: Vec<Address>,
+    ) -> Result<(), Box<dyn Error + Send>> {
+        Ok(())
+    }
+}
+
+/// A wal that returns the recorded information and saves nothing.
+#[derive(Debug, Default)]
+struct ReplayWal {
+    loads: Mutex<VecDeque<Result<Option<Bytes>, RecordedError>>>,
+}
+
+#[async_trait]
+impl Wal for ReplayWal {
+    async fn save(&self, _info: Bytes) -> Result<(), Box<dyn Error + Send>> {
+        Ok(())
+    }
+
+    async fn load(&self) -> Result<Option<Bytes>, Box<dyn Error + Send>> {
+        next(&self.loads, "load wal")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    use bytes::Bytes;
+
+    use crate::record::entry::short;
+    use crate::record::{Entry, Recorder, Replay};
+    use crate::sim::{SimConfig, Simulation};
+    use crate::smr::smr_types::{FromWhere, SMREvent};
+    use crate::types::{Node, OverlordMsg, RawBlock, Status};
+    use crate::OverlordConfig;
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_replay() {
+        let path = std::env::temp_dir().join(format!("overlord-replay-{}", std::process::id()));
+        let address = Bytes::from_static(b"node");
+        let authority_list = vec![Node::new(address.clone())];
+        let status = Status {
+            height: 1,
+            interval: None,
+            timer_config: None,
+            authority_list: authority_list.clone(),
+        };
+
+        let recorder = Recorder::create(&path).unwrap();
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        recorder.record::<RawBlock>(
+            at(0),
+            Entry::Start {
+                address,
+                init_height: 0,
+                authority_list,
+                config: OverlordConfig::default(),
+            },
+        );
+        recorder.record::<RawBlock>(at(0), Entry::WalLoad(Ok(None)));
+        recorder.record::<RawBlock>(at(1), Entry::Verified(OverlordMsg::RichStatus(status)));
+        recorder.record::<RawBlock>(
+            at(2),
+            Entry::Event(SMREvent::NewRoundInfo {
+                height: 1,
+                round: 0,
+                lock_round: None,
+                lock_proposal: None,
+                from_where: FromWhere::PrecommitQC(u64::MAX),
+                new_interval: None,
+                new_config: None,
+            }),
+        );
+        recorder.record::<RawBlock>(
+            at(2),
+            Entry::GetBlock(Ok((
+                RawBlock(Bytes::from_static(b"block")),
+                Bytes::from_static(b"hash"),
+            ))),
+        );
+        drop(recorder);
+
+        let mut replay = Replay::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(replay.remaining(), 2);
+
+        let step = replay.step().await.unwrap();
+        assert_eq!(step.input, "start with wal");
+
+        let step = replay.step().await.unwrap();
+        assert_eq!(step.height, 1);
+        assert!(
+            step.outputs
+                .iter()
+                .any(|o| o.starts_with("trigger SMR New height")),
+            "{}",
+            step
+        );
+
+        let step = replay.step().await.unwrap();
+        assert_eq!(step.at, Duration::from_millis(2));
+        assert!(
+            step.outputs.contains(&"get block height 1".to_string()),
+            "{}",
+            step
+        );
+        assert!(
+            step.outputs
+                .iter()
+                .any(|o| o.starts_with("broadcast proposal height 1, round 0, hash 68617368")),
+            "{}",
+            step
+        );
+        assert!(replay.step().await.is_none());
+    }
+
+    #[test]
+    fn test_replay_sim_node() {
+        let path = std::env::temp_dir().join(format!("overlord-replay-sim-{}", std::process::id()));
+        let recorder = Arc::new(Recorder::create(&path).unwrap());
+        let config = SimConfig {
+            seed: 7,
+            heights: 5,
+            ..SimConfig::default()
+        };
+        let report = Simulation::new(config)
+            .with_recorder(1, Arc::clone(&recorder))
+            .run();
+        report.check().unwrap();
+        recorder.flush();
+
+        let mut replay = Replay::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        let mut commits = Vec::new();
+        runtime.block_on(async {
+            while let Some(step) = replay.step().await {
+                commits.extend(
+                    step.outputs
+                        .into_iter()
+                        .filter(|o| o.starts_with("commit height")),
+                );
+            }
+        });
+
+        // The replayed node commits the same blocks as the recorded run.
+        let expected = report
+            .commits
+            .iter()
+            .filter_map(|(height, hashes)| {
+                let hash = hashes.get(&1)?;
+                Some(format!("commit height {}, hash {}", height, short(hash)))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(expected.len() as u64, report.heights[1]);
+        assert_eq!(commits, expected);
+    }
+}
@@ -0,0 +1,292 @@
+use bit_vec::BitVec;
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+
+use crate::sim::node::{sim_hash, SimBlock, SimCrypto};
+use crate::types::{
+    Address, AggregatedChoke, AggregatedSignature, AggregatedVote, Choke, Hash, Node, OverlordMsg,
+    Proposal, SignedChoke, SignedProposal, SignedVote, UpdateFrom, Vote, VoteType,
+};
+use crate::{Codec, Crypto, SignDomain};
+
+/// The number of the rounds ahead that a spammer or a flooder sends messages of.
+const FLOOD_ROUNDS: u64 = 10;
+
+/// A byzantine behaviour of a simulated node. The overlord of the node runs as usual, and the
+/// messages it sends are tampered on the way out and signed by its own key.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Behaviour {
+    /// Propose another block to the nodes of odd indexes in the same round.
+    EquivocatingProposer,
+    /// Vote for a fake block besides each vote.
+    DoubleVoter,
+    /// Send a precommit quorum certificate whose bitmap is below the threshold besides each vote,
+    /// for the block of the vote and for a fake block.
+    QcForger,
+    /// Send chokes of the rounds ahead besides each message.
+    ChokeSpammer,
+    /// Send votes and proposals of the rounds ahead besides each vote and proposal.
+    FutureRoundFlooder,
+    /// Propose blocks that do not pass `check_block`.
+    InvalidProposer,
+}
+
+/// The tamperer of the messages of a byzantine node.
+#[derive(Clone, Debug)]
+pub(crate) struct Byzantine {
+    behaviour: Behaviour,
+    address: Address,
+    crypto: SimCrypto,
+    domain: SignDomain,
+    authority_list: Vec<Node>,
+}
+
+impl Byzantine {
+    pub(crate) fn new(
+        behaviour: Behaviour,
+        address: Address,
+        authority_list: Vec<Node>,
+        domain: SignDomain,
+    ) -> Self {
+        let mut authority_list = authority_list;
+        authority_list.sort();
+        Byzantine {
+            behaviour,
+            crypto: SimCrypto::new(address.clone()),
+            domain,
+            address,
+            authority_list,
+        }
+    }
+
+    /// Return whether the proposed blocks are invalid.
+    pub(crate) fn is_invalid_proposer(&self) -> bool {
+        self.behaviour == Behaviour::InvalidProposer
+    }
+
+    /// Tamper a message sent to the node of the given index. Return the messages to send instead
+    /// and the number of them that are forged.
+    pub(crate) fn tamper(
+        &self,
+        to: usize,
+        msg: OverlordMsg<SimBlock>,
+    ) -> (Vec<OverlordMsg<SimBlock>>, u64) {
+        match (self.behaviour, msg) {
+            (Behaviour::EquivocatingProposer, OverlordMsg::SignedProposal(sp))
+                if sp.proposal.lock.is_none() && to % 2 == 1 =>
+            {
+                (vec![OverlordMsg::SignedProposal(self.equivocate(sp))], 1)
+            }
+            (Behaviour::DoubleVoter, OverlordMsg::SignedVote(sv)) => {
+                let mut vote = sv.vote.clone();
+                vote.block_hash = fake_hash(&vote.block_hash);
+                let msgs = vec![
+                    OverlordMsg::SignedVote(sv),
+                    OverlordMsg::SignedVote(self.sign_vote(vote)),
+                ];
+                (msgs, 1)
+            }
+            (Behaviour::QcForger, OverlordMsg::SignedVote(sv)) => {
+                let real = self.forge_qc(&sv.vote, sv.vote.block_hash.clone());
+                let fake = self.forge_qc(&sv.vote, fake_hash(&sv.vote.block_hash));
+                let msgs = vec![
+                    OverlordMsg::SignedVote(sv),
+                    OverlordMsg::AggregatedVote(real),
+                    OverlordMsg::AggregatedVote(fake),
+                ];
+                (msgs, 2)
+            }
+            (Behaviour::ChokeSpammer, msg) => {
+                let (height, round) = match &msg {
+                    OverlordMsg::SignedProposal(sp) => (sp.proposal.height, sp.proposal.round),
+                    OverlordMsg::SignedVote(sv) => (sv.vote.height, sv.vote.round),
+                    OverlordMsg::AggregatedVote(qc) => (qc.height, qc.round),
+                    OverlordMsg::SignedChoke(sc) => (sc.choke.height, sc.choke.round),
+                    _ => return (vec![msg], 0),
+                };
+                let mut msgs = vec![msg];
+                msgs.extend(
+                    (round + 1..=round + FLOOD_ROUNDS)
+                        .map(|round| OverlordMsg::SignedChoke(self.sign_choke(height, round))),
+                );
+                (msgs, FLOOD_ROUNDS)
+            }
+            (Behaviour::FutureRoundFlooder, OverlordMsg::SignedVote(sv)) => {
+                let round = sv.vote.round;
+                let mut msgs = vec![OverlordMsg::SignedVote(sv.clone())];
+                msgs.extend((round + 1..=round + FLOOD_ROUNDS).map(|round| {
+                    let mut vote = sv.vote.clone();
+                    vote.round = round;
+                    OverlordMsg::SignedVote(self.sign_vote(vote))
+                }));
+                (msgs, FLOOD_ROUNDS)
+            }
+            (Behaviour::FutureRoundFlooder, OverlordMsg::SignedProposal(sp)) => {
+                let round = sp.proposal.round;
+                let mut msgs = vec![OverlordMsg::SignedProposal(sp.clone())];
+                msgs.extend((round + 1..=round + FLOOD_ROUNDS).map(|round| {
+                    let mut proposal = sp.proposal.clone();
+                    proposal.round = round;
+                    proposal.lock = None;
+                    OverlordMsg::SignedProposal(self.sign_proposal(proposal))
+                }));
+                (msgs, FLOOD_ROUNDS)
+            }
+            (_, msg) => (vec![msg], 0),
+        }
+    }
+
+    /// Propose another valid block in the same round.
+    fn equivocate(&self, sp: SignedProposal<SimBlock>) -> SignedProposal<SimBlock> {
+        let mut proposal = sp.proposal;
+        proposal.content.nonce += 1 << 32;
+        proposal.block_hash = sim_hash(&proposal.content.encode().unwrap_or_default());
+        self.sign_proposal(proposal)
+    }
+
+    /// Forge a precommit quorum certificate of the block signed by the most nodes that are still
+    /// below the threshold.
+    fn forge_qc(&self, vote: &Vote, block_hash: Hash) -> AggregatedVote {
+        let hash = self.crypto.hash(self.domain.vote_payload(&Vote {
+            height: vote.height,
+            round: vote.round,
+            vote_type: VoteType::Precommit,
+            block_hash: block_hash.clone(),
+        }));
+
+        let count = self.authority_list.len() * 2 / 3;
+        let mut bitmap = BitVec::from_elem(self.authority_list.len(), false);
+        let mut voters = Vec::new();
+        for (index, node) in self.authority_list.iter().enumerate().take(count) {
+            bitmap.set(index, true);
+            voters.push(node.address.clone());
+        }
+        let signatures = voters
+            .iter()
+            .map(|voter| {
+                SimCrypto::new(voter.clone())
+                    .sign(hash.clone())
+                    .unwrap_or_default()
+            })
+            .collect();
+        let signature = self
+            .crypto
+            .aggregate_signatures(signatures, voters)
+            .unwrap_or_default();
+
+        AggregatedVote {
+            signature: AggregatedSignature {
+                signature,
+                address_bitmap: Bytes::from(bitmap.to_bytes()),
+            },
+            vote_type: VoteType::Precommit,
+            height: vote.height,
+            round: vote.round,
+            block_hash,
+            leader: self.address.clone(),
+        }
+    }
+
+    fn sign_proposal(&self, proposal: Proposal<SimBlock>) -> SignedProposal<SimBlock> {
+        let hash = self.crypto.hash(self.domain.proposal_payload(&proposal));
+        SignedProposal {
+            signature: self.crypto.sign(hash).unwrap_or_default(),
+            proposal,
+        }
+    }
+
+    fn sign_vote(&self, vote: Vote) -> SignedVote {
+        let hash = self.crypto.hash(self.domain.vote_payload(&vote));
+        SignedVote {
+            signature: self.crypto.sign(hash).unwrap_or_default(),
+            vote,
+            voter: self.address.clone(),
+        }
+    }
+
+    fn sign_choke(&self, height: u64, round: u64) -> SignedChoke {
+        let choke = Choke {
+            height,
+            round,
+            from: UpdateFrom::ChokeQC(AggregatedChoke {
+                height,
+                round: round.saturating_sub(1),
+                signature: Bytes::new(),
+                voters: vec![self.address.clone()],
+            }),
+        };
+        let hash = self.crypto.hash(self.domain.choke_payload(height, round));
+        SignedChoke {
+            signature: self.crypto.sign(hash).unwrap_or_default(),
+            choke,
+            address: self.address.clone(),
+        }
+    }
+}
+
+/// Get the hash of a block that does not exist.
+fn fake_hash(hash: &Hash) -> Hash {
+    sim_hash(&[hash.as_ref(), b"fake"].concat())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sim::byzantine::Behaviour;
+    use crate::sim::{Fault, SimConfig, Simulation};
+
+    /// Run the simulation and check that every byzantine node has forged some messages.
+    fn run(nodes: usize, seed: u64, byzantine: &[(usize, Behaviour)]) {
+        let config = SimConfig {
+            nodes,
+            seed,
+            heights: 6,
+            ..Default::default()
+        };
+        let faults = byzantine
+            .iter()
+            .map(|(node, behaviour)| Fault::Byzantine {
+                node: *node,
+                behaviour: *behaviour,
+            })
+            .collect();
+        let report = Simulation::new(config).with_faults(faults).run();
+        assert_eq!(report.check(), Ok(()), "{:?}", byzantine);
+        for (node, behaviour) in byzantine.iter() {
+            let forged = report.forged.get(node).copied().unwrap_or_default();
+            assert!(forged > 0, "{:?}", behaviour);
+        }
+    }
+
+    #[test]
+    fn test_byzantine_behaviours() {
+        let behaviours = [
+            Behaviour::EquivocatingProposer,
+            Behaviour::DoubleVoter,
+            Behaviour::QcForger,
+            Behaviour::ChokeSpammer,
+            Behaviour::FutureRoundFlooder,
+            Behaviour::InvalidProposer,
+        ];
+        for (seed, behaviour) in behaviours.iter().enumerate() {
+            run(4, seed as u64, &[(1, *behaviour)]);
+        }
+    }
+
+    #[test]
+    fn test_byzantine_minority() {
+        run(
+            7,
+            7,
+            &[
+                (1, Behaviour::EquivocatingProposer),
+                (3, Behaviour::QcForger),
+            ],
+        );
+        run(
+            7,
+            8,
+            &[(2, Behaviour::InvalidProposer), (5, Behaviour::DoubleVoter)],
+        );
+    }
+}
@@ -0,0 +1,774 @@
+/// The byzantine behaviours of the simulated nodes.
+pub mod byzantine;
+/// The simulated network which delays, drops, reorders and duplicates the messages.
+pub mod network;
+/// The simulated application, crypto and wal of a node.
+pub mod node;
+/// The scripted fault scenarios.
+pub mod scenario;
+
+pub use self::byzantine::Behaviour;
+pub use self::network::{chance, Envelope, NetworkConfig, NetworkPolicy, SimRng};
+pub use self::node::{
+    sim_address, sim_hash, SimBlock, SimConsensus, SimCrypto, SimError, SimWal, SkewedClock,
+};
+pub use self::scenario::{Fault, Scenario, Trigger};
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use creep::Context;
+use derive_more::Display;
+use futures::FutureExt;
+use hummer::coding::hex_encode;
+use parking_lot::Mutex;
+use rand_core::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+use crate::clock::{Clock, ManualClock};
+use crate::config::OverlordConfig;
+use crate::overlord::{Overlord, OverlordHandler};
+use crate::record::Recorder;
+use crate::sim::byzantine::Byzantine;
+use crate::sim::network::Network;
+use crate::sim::node::Ledger;
+use crate::sim::scenario::Phase;
+use crate::spawner::{Spawner, TokioSpawner};
+use crate::types::{Hash, Node, OverlordMsg, Status};
+use crate::utils::auth_manage::get_leader;
+
+/// The number of times that the driver yields to let the nodes handle the delivered messages and
+/// the expired timers before the virtual time moves on.
+const SETTLE_YIELDS: usize = 64;
+
+/// The setting of a simulation.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct SimConfig {
+    /// The number of the nodes.
+    pub nodes: usize,
+    /// The seed of the random number generator.
+    pub seed: u64,
+    /// The height that every node should commit to finish the simulation.
+    pub heights: u64,
+    /// The maximum virtual time of the simulation in millisecond.
+    pub max_time: u64,
+    /// The setting of the default network policy.
+    pub network: NetworkConfig,
+    /// The config of the overlord of each node.
+    pub overlord: OverlordConfig,
+    /// Whether the nodes decline to propose by `Consensus::should_propose` as on an idle chain.
+    pub idle: bool,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        SimConfig {
+            nodes: 4,
+            seed: 0,
+            heights: 10,
+            max_time: 600_000,
+            network: NetworkConfig::default(),
+            overlord: OverlordConfig::default(),
+            idle: false,
+        }
+    }
+}
+
+/// A deterministic simulation of the overlord consensus. The nodes run in one thread on a manual
+/// clock, and the driver moves the virtual time to the next message delivery or timer deadline.
+/// All the randomness comes from the seed, so a run can be reproduced by the seed.
+pub struct Simulation {
+    config: SimConfig,
+    policy: Box<dyn NetworkPolicy>,
+    faults: Vec<Fault>,
+    recorder: Option<(usize, Arc<Recorder>)>,
+}
+
+impl Simulation {
+    /// Create a simulation whose network follows the `NetworkConfig` of the config.
+    pub fn new(config: SimConfig) -> Self {
+        let policy = Box::new(config.network.clone());
+        Simulation {
+            config,
+            policy,
+            faults: Vec::new(),
+            recorder: None,
+        }
+    }
+
+    /// Set the policy which decides the delays of the messages.
+    pub fn with_policy(mut self, policy: Box<dyn NetworkPolicy>) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Inject the faults in the simulation.
+    pub fn with_faults(mut self, faults: Vec<Fault>) -> Self {
+        self.faults = faults;
+        self
+    }
+
+    /// Record the inputs of the node of the given index, so that its run can be replayed. Only
+    /// the first run of the node is recorded, a restarted node is not.
+    pub fn with_recorder(mut self, index: usize, recorder: Arc<Recorder>) -> Self {
+        self.recorder = Some((index, recorder));
+        self
+    }
+
+    /// Run the simulation until every node commits the target height or the virtual time runs
+    /// out, and return the report.
+    pub fn run(self) -> SimReport {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .expect("Overlord: build simulation runtime failed");
+        let spawner = Arc::new(TokioSpawner::with_handle(runtime.handle().clone()));
+        runtime.block_on(async move { Driver::new(self, spawner).drive().await })
+    }
+}
+
+/// A node run by the driver.
+struct SimNode {
+    handler: OverlordHandler<SimBlock>,
+    wal: SimWal,
+    clock: Arc<dyn Clock>,
+}
+
+/// The driver of a simulation, which starts and crashes the nodes, injects the faults and moves
+/// the virtual time. The nodes and their tasks are spawned by the `spawner` onto the runtime of
+/// the simulation.
+struct Driver {
+    config: SimConfig,
+    clock: Arc<ManualClock>,
+    shared: Arc<Mutex<SimShared>>,
+    authority_list: Vec<Node>,
+    nodes: Vec<SimNode>,
+    faults: Vec<(Fault, Phase)>,
+    recorder: Option<(usize, Arc<Recorder>)>,
+    spawner: Arc<dyn Spawner>,
+}
+
+impl Driver {
+    fn new(simulation: Simulation, spawner: Arc<dyn Spawner>) -> Self {
+        let Simulation {
+            config,
+            policy,
+            faults,
+            recorder,
+        } = simulation;
+        let clock = Arc::new(ManualClock::new());
+        let network = Network::new(policy, SimRng::seed_from_u64(config.seed));
+        let byzantine = faults
+            .iter()
+            .filter_map(|fault| match fault {
+                Fault::Byzantine { node, .. } => Some(*node),
+                _ => None,
+            })
+            .collect();
+        let shared = SimShared::new(Arc::clone(&clock), network, config.nodes, byzantine);
+        let authority_list = (0..config.nodes)
+            .map(|i| Node::new(sim_address(i)))
+            .collect::<Vec<_>>();
+
+        let mut driver = Driver {
+            config,
+            clock,
+            shared: Arc::new(Mutex::new(shared)),
+            authority_list,
+            nodes: Vec::new(),
+            faults: faults.into_iter().map(|f| (f, Phase::Pending)).collect(),
+            recorder,
+            spawner,
+        };
+        for index in 0..driver.config.nodes {
+            let rate = driver.faults.iter().find_map(|(fault, _)| match fault {
+                Fault::ClockSkew { node, rate } if *node == index => Some(*rate),
+                _ => None,
+            });
+            let clock: Arc<dyn Clock> = match rate {
+                Some(rate) => Arc::new(SkewedClock::new(Arc::clone(&driver.clock), rate)),
+                None => Arc::clone(&driver.clock) as _,
+            };
+            let wal = SimWal::default();
+            let handler = driver.start(index, Arc::clone(&clock), wal.clone());
+            driver.nodes.push(SimNode {
+                handler,
+                wal,
+                clock,
+            });
+        }
+        driver
+    }
+
+    /// Start the overlord of the node from the height it has committed.
+    fn start(&self, index: usize, clock: Arc<dyn Clock>, wal: SimWal) -> OverlordHandler<SimBlock> {
+        let address = self.authority_list[index].address.clone();
+        let (generation, height) = {
+            let shared = self.shared.lock();
+            (shared.generations[index], shared.height(index))
+        };
+        let byzantine = self.faults.iter().find_map(|(fault, _)| match fault {
+            Fault::Byzantine { node, behaviour } if *node == index => Some(Byzantine::new(
+                *behaviour,
+                address.clone(),
+                self.authority_list.clone(),
+                self.config.overlord.sign_domain.clone(),
+            )),
+            _ => None,
+        });
+        let consensus = SimConsensus::new(
+            index,
+            generation,
+            self.authority_list.clone(),
+            self.config.overlord.interval,
+            self.config.idle,
+            byzantine,
+            Arc::clone(&self.shared),
+        );
+        let mut overlord = Overlord::new(
+            address.clone(),
+            Arc::new(consensus),
+            Arc::new(SimCrypto::new(address)),
+            Arc::new(wal),
+            self.config.overlord.clone(),
+        )
+        .with_clock(clock)
+        .with_spawner(Arc::clone(&self.spawner));
+        match &self.recorder {
+            Some((node, recorder)) if *node == index && generation == 0 => {
+                overlord = overlord.with_recorder(Arc::clone(recorder));
+            }
+            _ => (),
+        }
+        let handler = overlord.get_handler();
+        self.send_status(&handler, height + 1);
+
+        let list = self.authority_list.clone();
+        self.spawner.spawn(
+            async move {
+                if let Err(e) = overlord.run(height, list).await {
+                    log::error!("Overlord: sim node {} error {:?}", index, e);
+                }
+            }
+            .boxed(),
+        );
+        handler
+    }
+
+    fn send_status(&self, handler: &OverlordHandler<SimBlock>, height: u64) {
+        let _ = handler.send_msg(
+            Context::new(),
+            OverlordMsg::RichStatus(Status {
+                height,
+                interval: Some(self.config.overlord.interval),
+                timer_config: None,
+                authority_list: self.authority_list.clone(),
+            }),
+        );
+    }
+
+    fn crash(&mut self, index: usize) {
+        if !self.shared.lock().crash(index) {
+            return;
+        }
+        log::info!("Overlord: sim node {} crashes", index);
+        let _ = self.nodes[index]
+            .handler
+            .send_msg(Context::new(), OverlordMsg::Stop);
+    }
+
+    fn restart(&mut self, index: usize, lose_wal: bool) {
+        if !self.shared.lock().restart(index) {
+            return;
+        }
+        log::info!(
+            "Overlord: sim node {} restarts, lose wal {}",
+            index,
+            lose_wal
+        );
+        let node = &self.nodes[index];
+        if lose_wal {
+            node.wal.clear();
+        }
+        let handler = self.start(index, Arc::clone(&node.clock), node.wal.clone());
+        self.nodes[index].handler = handler;
+    }
+
+    /// Start and end the faults whose triggers fire.
+    fn update_faults(&mut self) {
+        let height = self.shared.lock().top_height();
+        let time = self.clock.elapsed().as_millis() as u64;
+        for i in 0..self.faults.len() {
+            let (fault, phase) = self.faults[i].clone();
+            match phase {
+                Phase::Pending if matches!(fault.start(), Some(t) if t.fired(height, time)) => {
+                    if let Fault::Crash { node, .. } = fault {
+                        self.crash(node);
+                    }
+                    self.faults[i].1 = Phase::Active;
+                }
+                Phase::Active if matches!(fault.end(), Some(t) if t.fired(height, time)) => {
+                    match fault {
+                        Fault::Crash { node, lose_wal, .. } => self.restart(node, lose_wal),
+                        Fault::CrashLeader {
+                            height, lose_wal, ..
+                        } => self.restart(self.leader(height), lose_wal),
+                        _ => (),
+                    }
+                    self.faults[i].1 = Phase::Done;
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn leader(&self, height: u64) -> usize {
+        let leader = get_leader(height, 0, self.authority_list.clone());
+        self.authority_list
+            .iter()
+            .position(|node| node.address == leader)
+            .expect("Overlord: the leader is in the authority list")
+    }
+
+    /// Deliver a message unless the receiver is down or an active fault blocks it.
+    fn deliver(&mut self, envelope: Envelope) {
+        let blocked = !self.shared.lock().up[envelope.to]
+            || self
+                .faults
+                .iter()
+                .any(|(fault, phase)| *phase == Phase::Active && fault.blocks(&envelope));
+        if blocked {
+            self.shared.lock().network.dropped += 1;
+            return;
+        }
+
+        let vote_height = match &envelope.msg {
+            OverlordMsg::SignedVote(vote) => Some(vote.get_height()),
+            _ => None,
+        };
+        self.shared.lock().delivered += 1;
+        let _ = self.nodes[envelope.to]
+            .handler
+            .send_msg(Context::new(), envelope.msg);
+
+        // Crash the leader once it starts to aggregate the votes of the height.
+        if let Some(vote_height) = vote_height {
+            for i in 0..self.faults.len() {
+                if let (Fault::CrashLeader { height, .. }, Phase::Pending) = &self.faults[i] {
+                    if *height == vote_height && self.leader(*height) == envelope.to {
+                        self.faults[i].1 = Phase::Active;
+                        self.crash(envelope.to);
+                    }
+                }
+            }
+        }
+    }
+
+    async fn drive(mut self) -> SimReport {
+        let max_time = Duration::from_millis(self.config.max_time);
+        loop {
+            settle().await;
+            self.update_faults();
+            let synced = self.shared.lock().take_syncs();
+            if !synced.is_empty() {
+                for (index, height) in synced {
+                    self.send_status(&self.nodes[index].handler, height + 1);
+                }
+                continue;
+            }
+
+            let next = {
+                let shared = self.shared.lock();
+                if shared.reached(self.config.heights) || self.clock.elapsed() >= max_time {
+                    break;
+                }
+                match (shared.network.next_delivery(), self.clock.next_deadline()) {
+                    (Some(a), Some(b)) => a.min(b),
+                    (a, b) => match a.or(b) {
+                        Some(next) => next,
+                        None => break,
+                    },
+                }
+            };
+
+            let now = self.clock.elapsed();
+            if next > now {
+                self.clock.advance(next - now);
+            }
+            let due = self.shared.lock().network.take_due(self.clock.elapsed());
+            for envelope in due {
+                self.deliver(envelope);
+            }
+        }
+
+        for node in self.nodes.iter() {
+            let _ = node.handler.send_msg(Context::new(), OverlordMsg::Stop);
+        }
+        settle().await;
+
+        let shared = self.shared.lock();
+        SimReport::new(&self.config, self.clock.elapsed(), &shared)
+    }
+}
+
+async fn settle() {
+    for _ in 0..SETTLE_YIELDS {
+        tokio::task::yield_now().await;
+    }
+}
+
+/// The state shared by the driver and the simulated nodes.
+pub(crate) struct SimShared {
+    clock: Arc<ManualClock>,
+    network: Network,
+    ledger: Ledger,
+    syncs: BTreeMap<usize, u64>,
+    generations: Vec<u64>,
+    up: Vec<bool>,
+    byzantine: Vec<usize>,
+    forged: BTreeMap<usize, u64>,
+    delivered: u64,
+}
+
+impl SimShared {
+    fn new(clock: Arc<ManualClock>, network: Network, nodes: usize, byzantine: Vec<usize>) -> Self {
+        SimShared {
+            clock,
+            network,
+            ledger: Ledger::new(),
+            syncs: BTreeMap::new(),
+            generations: vec![0; nodes],
+            up: vec![true; nodes],
+            byzantine,
+            forged: BTreeMap::new(),
+            delivered: 0,
+        }
+    }
+
+    /// Return whether the node is up and runs the overlord of the given generation.
+    pub(crate) fn is_alive(&self, index: usize, generation: u64) -> bool {
+        self.up[index] && self.generations[index] == generation
+    }
+
+    /// Mark the node down. Return false if it is already down.
+    fn crash(&mut self, index: usize) -> bool {
+        if !self.up[index] {
+            return false;
+        }
+        self.up[index] = false;
+        self.syncs.remove(&index);
+        true
+    }
+
+    /// Mark the node up with a new generation. Return false if it is already up.
+    fn restart(&mut self, index: usize) -> bool {
+        if self.up[index] {
+            return false;
+        }
+        self.up[index] = true;
+        self.generations[index] += 1;
+        true
+    }
+
+    pub(crate) fn send(&mut self, from: usize, to: usize, msg: OverlordMsg<SimBlock>) {
+        let sent_at = self.clock.elapsed();
+        self.network.send(Envelope {
+            from,
+            to,
+            sent_at,
+            msg,
+        });
+    }
+
+    /// Count the messages forged by a byzantine node.
+    pub(crate) fn forge(&mut self, index: usize, count: u64) {
+        if count > 0 {
+            *self.forged.entry(index).or_default() += count;
+        }
+    }
+
+    pub(crate) fn commit(&mut self, index: usize, height: u64, hash: Hash) {
+        self.ledger.entry(height).or_default().insert(index, hash);
+    }
+
+    pub(crate) fn request_sync(&mut self, index: usize, height: u64) {
+        let target = self.syncs.entry(index).or_default();
+        *target = (*target).max(height);
+    }
+
+    /// Synchronize the nodes which request it by the blocks committed by the others, and return
+    /// the nodes with the synchronized heights. A request waits until all the blocks it needs
+    /// are committed by some node.
+    fn take_syncs(&mut self) -> Vec<(usize, u64)> {
+        let mut synced = Vec::new();
+        for (index, target) in std::mem::take(&mut self.syncs) {
+            let from = self.height(index) + 1;
+            let blocks = (from..=target)
+                .map(|height| {
+                    self.ledger
+                        .get(&height)
+                        .and_then(|commits| commits.values().next().cloned())
+                })
+                .collect::<Option<Vec<_>>>();
+            match blocks {
+                Some(blocks) => {
+                    for (height, hash) in (from..=target).zip(blocks) {
+                        self.commit(index, height, hash);
+                    }
+                    synced.push((index, target));
+                }
+                None => {
+                    self.syncs.insert(index, target);
+                }
+            }
+        }
+        synced
+    }
+
+    /// Get the highest height that the node has committed.
+    fn height(&self, index: usize) -> u64 {
+        self.ledger
+            .iter()
+            .rev()
+            .find(|(_, commits)| commits.contains_key(&index))
+            .map_or(0, |(height, _)| *height)
+    }
+
+    /// Return whether every honest node which is up commits the height.
+    fn reached(&self, height: u64) -> bool {
+        match self.ledger.get(&height) {
+            Some(commits) => (0..self.up.len()).all(|index| {
+                !self.up[index] || self.byzantine.contains(&index) || commits.contains_key(&index)
+            }),
+            None => false,
+        }
+    }
+
+    /// Get the highest height that some node has committed.
+    fn top_height(&self) -> u64 {
+        self.ledger.keys().next_back().copied().unwrap_or_default()
+    }
+}
+
+/// The result of a simulation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimReport {
+    /// The seed of the simulation.
+    pub seed: u64,
+    /// The height that every node should commit.
+    pub target_height: u64,
+    /// The virtual time that the simulation lasts.
+    pub elapsed: Duration,
+    /// The highest committed height of each node.
+    pub heights: Vec<u64>,
+    /// The nodes which are down at the end.
+    pub down: Vec<usize>,
+    /// The byzantine nodes.
+    pub byzantine: Vec<usize>,
+    /// The number of the messages forged by each byzantine node, including the invalid blocks it
+    /// proposes.
+    pub forged: BTreeMap<usize, u64>,
+    /// The committed block hash of each node, indexed by the height and then the node.
+    pub commits: BTreeMap<u64, BTreeMap<usize, Hash>>,
+    /// The number of the messages sent.
+    pub sent: u64,
+    /// The number of the messages delivered, including the duplicates.
+    pub delivered: u64,
+    /// The number of the messages dropped.
+    pub dropped: u64,
+    /// The number of the duplicates of the messages.
+    pub duplicated: u64,
+}
+
+impl SimReport {
+    fn new(config: &SimConfig, elapsed: Duration, shared: &SimShared) -> Self {
+        let heights = (0..config.nodes)
+            .map(|index| shared.height(index))
+            .collect();
+        SimReport {
+            seed: config.seed,
+            target_height: config.heights,
+            elapsed,
+            heights,
+            down: (0..config.nodes).filter(|i| !shared.up[*i]).collect(),
+            byzantine: shared.byzantine.clone(),
+            forged: shared.forged.clone(),
+            commits: shared.ledger.clone(),
+            sent: shared.network.sent,
+            delivered: shared.delivered,
+            dropped: shared.network.dropped,
+            duplicated: shared.network.duplicated,
+        }
+    }
+
+    /// Return whether no two honest nodes commit different blocks at one height.
+    pub fn is_safe(&self) -> bool {
+        self.check_safety().is_ok()
+    }
+
+    /// Return whether every honest node which is up commits the target height.
+    pub fn is_live(&self) -> bool {
+        self.check_liveness().is_ok()
+    }
+
+    /// Check the safety and then the liveness of the simulation.
+    pub fn check(&self) -> Result<(), SimViolation> {
+        self.check_safety()?;
+        self.check_liveness()
+    }
+
+    fn check_safety(&self) -> Result<(), SimViolation> {
+        for (height, commits) in self.commits.iter() {
+            let mut hashes = commits
+                .iter()
+                .filter(|(index, _)| !self.byzantine.contains(index))
+                .map(|(_, hash)| hash.clone())
+                .collect::<Vec<_>>();
+            hashes.sort();
+            hashes.dedup();
+            if hashes.len() > 1 {
+                return Err(SimViolation::Safety {
+                    height: *height,
+                    hashes: hashes.iter().map(hex_encode).collect(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn check_liveness(&self) -> Result<(), SimViolation> {
+        match (0..self.heights.len()).find(|i| {
+            self.heights[*i] < self.target_height
+                && !self.down.contains(i)
+                && !self.byzantine.contains(i)
+        }) {
+            Some(node) => Err(SimViolation::Liveness {
+                node,
+                height: self.heights[node],
+                target: self.target_height,
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A violation of the consensus properties found by a simulation.
+#[derive(Clone, Debug, Display, PartialEq, Eq)]
+pub enum SimViolation {
+    /// Different blocks are committed at one height.
+    #[display(
+        fmt = "different blocks {:?} are committed at height {}",
+        hashes,
+        height
+    )]
+    Safety {
+        /// The height of the conflicting commits.
+        height: u64,
+        /// The committed block hashes in hex.
+        hashes: Vec<String>,
+    },
+    /// A node does not commit the target height.
+    #[display(fmt = "node {} only commits height {} of {}", node, height, target)]
+    Liveness {
+        /// The index of the node.
+        node: usize,
+        /// The highest committed height of the node.
+        height: u64,
+        /// The target height.
+        target: u64,
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+
+    use crate::sim::{NetworkConfig, SimConfig, Simulation};
+    use crate::SignDomain;
+
+    #[test]
+    fn test_simulation() {
+        let config = SimConfig {
+            seed: 42,
+            network: NetworkConfig {
+                min_delay: 10,
+                max_delay: 300,
+                drop_rate: 0.05,
+                duplicate_rate: 0.05,
+            },
+            ..Default::default()
+        };
+        let report = Simulation::new(config).run();
+        assert_eq!(report.check(), Ok(()));
+        assert!(report.dropped > 0 && report.duplicated > 0);
+    }
+
+    #[test]
+    fn test_simulation_chain_domain() {
+        let mut config = SimConfig {
+            seed: 3,
+            heights: 4,
+            ..Default::default()
+        };
+        config.overlord.sign_domain = SignDomain::Chain(Bytes::from("sim chain"));
+        let report = Simulation::new(config).run();
+        assert_eq!(report.check(), Ok(()), "{:?}", report.heights);
+    }
+
+    #[test]
+    fn test_simulation_reproducible() {
+        let config = SimConfig {
+            nodes: 7,
+            seed: 7,
+            heights: 5,
+            network: NetworkConfig {
+                max_delay: 500,
+                duplicate_rate: 0.1,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let report = Simulation::new(config.clone()).run();
+        assert_eq!(report.check(), Ok(()), "{:?}", report.heights);
+        assert_eq!(report, Simulation::new(config.clone()).run());
+
+        // A dropped message is not sent again, so a lossy run may stall, which is reproduced all
+        // the same.
+        let config = SimConfig {
+            network: NetworkConfig {
+                drop_rate: 0.1,
+                ..Default::default()
+            },
+            ..config
+        };
+        assert_eq!(
+            Simulation::new(config.clone()).run(),
+            Simulation::new(config).run()
+        );
+    }
+
+    #[test]
+    fn test_simulation_idle() {
+        let mut config = SimConfig {
+            seed: 5,
+            heights: 3,
+            idle: true,
+            ..Default::default()
+        };
+        config.overlord.max_idle_interval = 10000;
+        let report = Simulation::new(config.clone()).run();
+        assert_eq!(report.check(), Ok(()), "{:?}", report.heights);
+
+        // Every height proposes a block in the round which starts in the idle bound, so it lasts
+        // no longer than the bound, the round and the interval between the heights.
+        let height_time = config.overlord.max_idle_interval + 2 * config.overlord.interval;
+        assert!(
+            report.elapsed <= Duration::from_millis(config.heights * height_time),
+            "{:?}",
+            report.elapsed
+        );
+    }
+}
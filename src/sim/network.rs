@@ -0,0 +1,181 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use rand_core::RngCore;
+use rand_pcg::Pcg64Mcg;
+use serde::{Deserialize, Serialize};
+
+use crate::sim::node::SimBlock;
+use crate::types::OverlordMsg;
+
+/// The random number generator of the simulation.
+pub type SimRng = Pcg64Mcg;
+
+/// A message in flight in the simulated network.
+#[derive(Clone, Debug)]
+pub struct Envelope {
+    /// The index of the sender.
+    pub from: usize,
+    /// The index of the receiver.
+    pub to: usize,
+    /// The virtual time at which the message is sent.
+    pub sent_at: Duration,
+    /// The message.
+    pub msg: OverlordMsg<SimBlock>,
+}
+
+/// Trait for deciding the fate of each message in the simulated network.
+pub trait NetworkPolicy: Send {
+    /// Return the delays after which the copies of the message are delivered. An empty list drops
+    /// the message and more than one delay duplicates it. Messages overtake each other when a
+    /// later one gets a shorter delay.
+    fn route(&mut self, rng: &mut SimRng, envelope: &Envelope) -> Vec<Duration>;
+}
+
+/// The setting of the default network policy.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct NetworkConfig {
+    /// The minimum delay of a message in millisecond.
+    pub min_delay: u64,
+    /// The maximum delay of a message in millisecond.
+    pub max_delay: u64,
+    /// The probability of dropping a message. A dropped message is not sent again, so the nodes
+    /// which miss a quorum certificate may stall the consensus.
+    pub drop_rate: f64,
+    /// The probability of duplicating a message.
+    pub duplicate_rate: f64,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        NetworkConfig {
+            min_delay: 10,
+            max_delay: 100,
+            drop_rate: 0.0,
+            duplicate_rate: 0.0,
+        }
+    }
+}
+
+impl NetworkConfig {
+    fn delay(&self, rng: &mut SimRng) -> Duration {
+        let span = self.max_delay.saturating_sub(self.min_delay) + 1;
+        Duration::from_millis(self.min_delay + rng.next_u64() % span)
+    }
+}
+
+impl NetworkPolicy for NetworkConfig {
+    fn route(&mut self, rng: &mut SimRng, _envelope: &Envelope) -> Vec<Duration> {
+        if chance(rng, self.drop_rate) {
+            return Vec::new();
+        }
+
+        let mut delays = vec![self.delay(rng)];
+        if chance(rng, self.duplicate_rate) {
+            delays.push(self.delay(rng));
+        }
+        delays
+    }
+}
+
+/// Return true with the given probability.
+pub fn chance(rng: &mut SimRng, probability: f64) -> bool {
+    probability > 0.0 && (rng.next_u64() as f64) < probability * (u64::MAX as f64)
+}
+
+/// The messages in flight, ordered by the delivery time and then the sending order.
+pub(crate) struct Network {
+    policy: Box<dyn NetworkPolicy>,
+    rng: SimRng,
+    queue: BTreeMap<(Duration, u64), Envelope>,
+    seq: u64,
+    pub(crate) sent: u64,
+    pub(crate) dropped: u64,
+    pub(crate) duplicated: u64,
+}
+
+impl Network {
+    pub(crate) fn new(policy: Box<dyn NetworkPolicy>, rng: SimRng) -> Self {
+        Network {
+            policy,
+            rng,
+            queue: BTreeMap::new(),
+            seq: 0,
+            sent: 0,
+            dropped: 0,
+            duplicated: 0,
+        }
+    }
+
+    pub(crate) fn send(&mut self, envelope: Envelope) {
+        let delays = self.policy.route(&mut self.rng, &envelope);
+        self.sent += 1;
+        match delays.len() {
+            0 => self.dropped += 1,
+            n => self.duplicated += n as u64 - 1,
+        }
+
+        for delay in delays {
+            self.seq += 1;
+            self.queue
+                .insert((envelope.sent_at + delay, self.seq), envelope.clone());
+        }
+    }
+
+    /// Get the time at which the next message is delivered.
+    pub(crate) fn next_delivery(&self) -> Option<Duration> {
+        self.queue.keys().next().map(|(at, _)| *at)
+    }
+
+    /// Take the messages which are due at the given time.
+    pub(crate) fn take_due(&mut self, now: Duration) -> Vec<Envelope> {
+        let pending = self.queue.split_off(&(now, u64::MAX));
+        std::mem::replace(&mut self.queue, pending)
+            .into_values()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use rand_core::SeedableRng;
+
+    use crate::sim::network::{Envelope, Network, NetworkConfig, SimRng};
+    use crate::types::OverlordMsg;
+
+    #[test]
+    fn test_network_order() {
+        let config = NetworkConfig {
+            min_delay: 10,
+            max_delay: 50,
+            drop_rate: 0.2,
+            duplicate_rate: 0.2,
+        };
+        let mut network = Network::new(Box::new(config), SimRng::seed_from_u64(7));
+        for i in 0..100 {
+            network.send(Envelope {
+                from: 0,
+                to: i,
+                sent_at: Duration::from_millis(i as u64),
+                msg: OverlordMsg::Stop,
+            });
+        }
+        assert!(network.dropped > 0 && network.duplicated > 0);
+
+        let mut last = Duration::ZERO;
+        let mut delivered = 0;
+        while let Some(at) = network.next_delivery() {
+            assert!(at >= last);
+            let due = network.take_due(at);
+            assert!(due
+                .iter()
+                .all(|e| e.sent_at + Duration::from_millis(10) <= at));
+            delivered += due.len() as u64;
+            last = at;
+        }
+        assert_eq!(delivered, 100 - network.dropped + network.duplicated);
+    }
+}
@@ -0,0 +1,410 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use bytes::{BufMut, Bytes, BytesMut};
+use creep::Context;
+use derive_more::Display;
+use futures::future::BoxFuture;
+use parking_lot::Mutex;
+
+use crate::clock::{Clock, ManualClock};
+use crate::error::ConsensusError;
+use crate::sim::byzantine::Byzantine;
+use crate::sim::SimShared;
+use crate::types::{
+    Address, Commit, Hash, Node, OverlordMsg, Proof, Signature, Status, ViewChangeReason,
+};
+use crate::{Codec, Consensus, Crypto, Wal};
+
+/// The block proposed in the simulation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimBlock {
+    /// The height of the block.
+    pub height: u64,
+    /// The index of the node which proposes the block.
+    pub proposer: u64,
+    /// The number of the blocks that the proposer has proposed before.
+    pub nonce: u64,
+}
+
+impl Codec for SimBlock {
+    fn encode(&self) -> Result<Bytes, Box<dyn Error + Send>> {
+        let mut buf = BytesMut::with_capacity(24);
+        buf.put_u64(self.height);
+        buf.put_u64(self.proposer);
+        buf.put_u64(self.nonce);
+        Ok(buf.freeze())
+    }
+
+    fn decode(data: Bytes) -> Result<Self, Box<dyn Error + Send>> {
+        if data.len() != 24 {
+            return Err(Box::new(SimError::Codec(data.len())));
+        }
+        let word = |i: usize| {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&data[i * 8..(i + 1) * 8]);
+            u64::from_be_bytes(bytes)
+        };
+        Ok(SimBlock {
+            height: word(0),
+            proposer: word(1),
+            nonce: word(2),
+        })
+    }
+}
+
+/// The error of the simulated application.
+#[derive(Debug, Display)]
+pub enum SimError {
+    /// The length of an encoded block is wrong.
+    #[display(fmt = "invalid block length {}", _0)]
+    Codec(usize),
+    /// A signature does not match the signer.
+    #[display(fmt = "invalid signature")]
+    Signature,
+    /// The receiver of a message is not in the simulation.
+    #[display(fmt = "unknown node {:?}", _0)]
+    UnknownNode(Address),
+    /// A block does not match its height or hash.
+    #[display(fmt = "invalid block of height {}", _0)]
+    InvalidBlock(u64),
+    /// A scenario cannot be loaded or is invalid.
+    #[display(fmt = "invalid scenario {}", _0)]
+    Scenario(String),
+}
+
+impl Error for SimError {}
+
+/// Hash the bytes into 32 bytes deterministically. It is not a cryptographic hash.
+pub fn sim_hash(data: &[u8]) -> Hash {
+    let mut out = BytesMut::with_capacity(32);
+    for lane in 0..4u64 {
+        let mut state = 0xcbf2_9ce4_8422_2325u64 ^ lane.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+        for byte in data {
+            state ^= u64::from(*byte);
+            state = state.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+        out.put_u64(state);
+    }
+    out.freeze()
+}
+
+/// Get the address of the node of the given index.
+pub fn sim_address(index: usize) -> Address {
+    sim_hash(format!("overlord-sim-node-{}", index).as_bytes())
+}
+
+/// The crypto of a simulated node. A signature is the hash of the signer and the signed hash, and
+/// an aggregated signature is the signatures concatenated in the order of the voters.
+#[derive(Clone, Debug)]
+pub struct SimCrypto {
+    address: Address,
+}
+
+impl SimCrypto {
+    /// Create the crypto of the node of the given address.
+    pub fn new(address: Address) -> Self {
+        SimCrypto { address }
+    }
+
+    fn signature(address: &Address, hash: &Hash) -> Signature {
+        let mut data = BytesMut::from(address.as_ref());
+        data.extend_from_slice(hash);
+        sim_hash(&data)
+    }
+}
+
+impl Crypto for SimCrypto {
+    fn hash(&self, msg: Bytes) -> Hash {
+        sim_hash(&msg)
+    }
+
+    fn sign(&self, hash: Hash) -> Result<Signature, Box<dyn Error + Send>> {
+        Ok(SimCrypto::signature(&self.address, &hash))
+    }
+
+    fn aggregate_signatures(
+        &self,
+        signatures: Vec<Signature>,
+        voters: Vec<Address>,
+    ) -> Result<Signature, Box<dyn Error + Send>> {
+        // The votes are collected in no particular order, so sort them by the voters.
+        let mut pairs = voters.into_iter().zip(signatures).collect::<Vec<_>>();
+        pairs.sort();
+        let mut aggregated = BytesMut::new();
+        for (_, signature) in pairs {
+            aggregated.extend_from_slice(&signature);
+        }
+        Ok(aggregated.freeze())
+    }
+
+    fn verify_signature(
+        &self,
+        signature: Signature,
+        hash: Hash,
+        voter: Address,
+    ) -> Result<(), Box<dyn Error + Send>> {
+        if signature != SimCrypto::signature(&voter, &hash) {
+            return Err(Box::new(SimError::Signature));
+        }
+        Ok(())
+    }
+
+    fn verify_aggregated_signature(
+        &self,
+        aggregate_signature: Signature,
+        msg_hash: Hash,
+        voters: Vec<Address>,
+    ) -> Result<(), Box<dyn Error + Send>> {
+        let signatures = voters
+            .iter()
+            .map(|voter| SimCrypto::signature(voter, &msg_hash))
+            .collect::<Vec<_>>();
+        if aggregate_signature != self.aggregate_signatures(signatures, voters)? {
+            return Err(Box::new(SimError::Signature));
+        }
+        Ok(())
+    }
+}
+
+/// The clock of a simulated node, which runs at a rate of the virtual time of the simulation.
+#[derive(Debug)]
+pub struct SkewedClock {
+    inner: Arc<ManualClock>,
+    start: Instant,
+    rate: f64,
+}
+
+impl SkewedClock {
+    /// Create a clock which runs at the given rate of the manual clock.
+    pub fn new(inner: Arc<ManualClock>, rate: f64) -> Self {
+        let start = inner.now() - inner.elapsed();
+        SkewedClock { inner, start, rate }
+    }
+}
+
+impl Clock for SkewedClock {
+    fn now(&self) -> Instant {
+        self.start + self.inner.elapsed().mul_f64(self.rate)
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        self.inner.sleep(duration.div_f64(self.rate))
+    }
+}
+
+/// The write ahead log of a simulated node, kept in memory.
+#[derive(Clone, Debug, Default)]
+pub struct SimWal {
+    inner: Arc<Mutex<Option<Bytes>>>,
+}
+
+impl SimWal {
+    /// Drop the saved wal information.
+    pub fn clear(&self) {
+        *self.inner.lock() = None;
+    }
+}
+
+#[async_trait]
+impl Wal for SimWal {
+    async fn save(&self, info: Bytes) -> Result<(), Box<dyn Error + Send>> {
+        *self.inner.lock() = Some(info);
+        Ok(())
+    }
+
+    async fn load(&self) -> Result<Option<Bytes>, Box<dyn Error + Send>> {
+        Ok(self.inner.lock().clone())
+    }
+}
+
+/// The application of a simulated node. The messages are sent through the simulated network and
+/// the commits are recorded to the ledger of the simulation. The application of a crashed node
+/// does nothing, and that of a byzantine node tampers the messages it sends.
+pub struct SimConsensus {
+    index: usize,
+    generation: u64,
+    authority_list: Vec<Node>,
+    interval: u64,
+    idle: bool,
+    nonce: Mutex<u64>,
+    byzantine: Option<Byzantine>,
+    shared: Arc<Mutex<SimShared>>,
+}
+
+impl SimConsensus {
+    pub(crate) fn new(
+        index: usize,
+        generation: u64,
+        authority_list: Vec<Node>,
+        interval: u64,
+        idle: bool,
+        byzantine: Option<Byzantine>,
+        shared: Arc<Mutex<SimShared>>,
+    ) -> Self {
+        SimConsensus {
+            index,
+            generation,
+            authority_list,
+            interval,
+            idle,
+            nonce: Mutex::new(0),
+            byzantine,
+            shared,
+        }
+    }
+
+    fn send(&self, shared: &mut SimShared, to: usize, msg: OverlordMsg<SimBlock>) {
+        match self.byzantine.as_ref() {
+            Some(byzantine) => {
+                let (msgs, forged) = byzantine.tamper(to, msg);
+                shared.forge(self.index, forged);
+                for msg in msgs {
+                    shared.send(self.index, to, msg);
+                }
+            }
+            None => shared.send(self.index, to, msg),
+        }
+    }
+}
+
+#[async_trait]
+impl Consensus<SimBlock> for SimConsensus {
+    async fn get_block(
+        &self,
+        _ctx: Context,
+        height: u64,
+    ) -> Result<(SimBlock, Hash), Box<dyn Error + Send>> {
+        let nonce = {
+            let mut nonce = self.nonce.lock();
+            *nonce += 1;
+            *nonce
+        };
+        let mut block = SimBlock {
+            height,
+            proposer: self.index as u64,
+            nonce,
+        };
+        if matches!(&self.byzantine, Some(byzantine) if byzantine.is_invalid_proposer()) {
+            block.height += 1;
+            self.shared.lock().forge(self.index, 1);
+        }
+        let hash = sim_hash(&block.encode()?);
+        Ok((block, hash))
+    }
+
+    async fn should_propose(
+        &self,
+        _ctx: Context,
+        _height: u64,
+        _round: u64,
+        _elapsed: Duration,
+    ) -> bool {
+        !self.idle
+    }
+
+    async fn check_block(
+        &self,
+        _ctx: Context,
+        height: u64,
+        hash: Hash,
+        block: SimBlock,
+    ) -> Result<(), Box<dyn Error + Send>> {
+        if block.height != height || sim_hash(&block.encode()?) != hash {
+            return Err(Box::new(SimError::InvalidBlock(height)));
+        }
+        Ok(())
+    }
+
+    async fn commit(
+        &self,
+        _ctx: Context,
+        height: u64,
+        commit: Commit<SimBlock>,
+    ) -> Result<Status, Box<dyn Error + Send>> {
+        let mut shared = self.shared.lock();
+        if shared.is_alive(self.index, self.generation) {
+            shared.commit(self.index, height, commit.proof.block_hash);
+        }
+        Ok(Status {
+            height: height + 1,
+            interval: Some(self.interval),
+            timer_config: None,
+            authority_list: self.authority_list.clone(),
+        })
+    }
+
+    async fn request_sync(
+        &self,
+        _ctx: Context,
+        target_height: u64,
+        _proof: Proof,
+    ) -> Result<(), Box<dyn Error + Send>> {
+        let mut shared = self.shared.lock();
+        if shared.is_alive(self.index, self.generation) {
+            shared.request_sync(self.index, target_height);
+        }
+        Ok(())
+    }
+
+    async fn get_authority_list(
+        &self,
+        _ctx: Context,
+        _height: u64,
+    ) -> Result<Vec<Node>, Box<dyn Error + Send>> {
+        Ok(self.authority_list.clone())
+    }
+
+    async fn broadcast_to_other(
+        &self,
+        _ctx: Context,
+        msg: OverlordMsg<SimBlock>,
+    ) -> Result<(), Box<dyn Error + Send>> {
+        let mut shared = self.shared.lock();
+        if !shared.is_alive(self.index, self.generation) {
+            return Ok(());
+        }
+        for to in (0..self.authority_list.len()).filter(|to| *to != self.index) {
+            self.send(&mut shared, to, msg.clone());
+        }
+        Ok(())
+    }
+
+    async fn transmit_to_relayer(
+        &self,
+        _ctx: Context,
+        addr: Address,
+        msg: OverlordMsg<SimBlock>,
+    ) -> Result<(), Box<dyn Error + Send>> {
+        let to = self
+            .authority_list
+            .iter()
+            .position(|node| node.address == addr)
+            .ok_or_else(|| Box::new(SimError::UnknownNode(addr)) as Box<dyn Error + Send>)?;
+        let mut shared = self.shared.lock();
+        if shared.is_alive(self.index, self.generation) {
+            self.send(&mut shared, to, msg);
+        }
+        Ok(())
+    }
+
+    fn report_error(&self, _ctx: Context, error: ConsensusError) {
+        log::debug!("Overlord: sim node {} error {:?}", self.index, error);
+    }
+
+    fn report_view_change(&self, _ctx: Context, height: u64, round: u64, reason: ViewChangeReason) {
+        log::debug!(
+            "Overlord: sim node {} view change height {} round {} reason {}",
+            self.index,
+            height,
+            round,
+            reason
+        );
+    }
+}
+
+/// The ledger of the commits of all the nodes, indexed by the height and then the node.
+pub(crate) type Ledger = BTreeMap<u64, BTreeMap<usize, Hash>>;
@@ -0,0 +1,279 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::sim::byzantine::Behaviour;
+use crate::sim::network::Envelope;
+use crate::sim::node::SimError;
+use crate::sim::{SimConfig, SimReport, Simulation};
+use crate::types::OverlordMsg;
+
+/// A scripted fault scenario, saved as JSON. The `config` sets up the simulation and the faults
+/// are injected in it while the nodes run:
+///
+/// ```json
+/// {
+///   "name": "partition_heal",
+///   "description": "node 3 is cut off until height 5",
+///   "config": { "nodes": 4, "seed": 1, "heights": 10 },
+///   "faults": [
+///     { "type": "partition", "groups": [[0, 1, 2], [3]], "until": { "height": 5 } },
+///     {
+///       "type": "crash", "node": 1, "at": { "time": 20000 }, "until": { "height": 8 },
+///       "lose_wal": true
+///     },
+///     { "type": "crash_leader", "height": 3, "until": { "time": 30000 } },
+///     { "type": "withhold_qc", "node": 2, "at": { "height": 2 }, "until": { "height": 4 } },
+///     { "type": "byzantine", "node": 3, "behaviour": "double_voter" },
+///     { "type": "clock_skew", "node": 0, "rate": 1.5 }
+///   ]
+/// }
+/// ```
+///
+/// The omitted fields of the config take the default values. A fault starts when `at` fires,
+/// which is at the beginning by default, and ends when `until` fires, or never if it is omitted.
+/// The node which is crashed and not restarted is excluded from the liveness check, and the
+/// byzantine nodes are excluded from both checks.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    /// The name of the scenario.
+    pub name: String,
+    /// What the scenario reproduces.
+    #[serde(default)]
+    pub description: String,
+    /// The setting of the simulation.
+    #[serde(default)]
+    pub config: SimConfig,
+    /// The faults injected in the simulation.
+    #[serde(default)]
+    pub faults: Vec<Fault>,
+}
+
+impl Scenario {
+    /// Load a scenario from a JSON file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Scenario, SimError> {
+        let path = path.as_ref();
+        let file =
+            File::open(path).map_err(|e| SimError::Scenario(format!("{:?}: {}", path, e)))?;
+        let scenario: Scenario = serde_json::from_reader(BufReader::new(file))
+            .map_err(|e| SimError::Scenario(format!("{:?}: {}", path, e)))?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    /// Check that the faults refer to the nodes in the simulation.
+    pub fn validate(&self) -> Result<(), SimError> {
+        let nodes = self.config.nodes;
+        let check = |node: usize| {
+            if node >= nodes {
+                return Err(SimError::Scenario(format!(
+                    "{}: node {} out of {} nodes",
+                    self.name, node, nodes
+                )));
+            }
+            Ok(())
+        };
+
+        for fault in self.faults.iter() {
+            match fault {
+                Fault::Partition { groups, .. } => {
+                    for node in groups.iter().flatten() {
+                        check(*node)?;
+                    }
+                }
+                Fault::Crash { node, .. }
+                | Fault::WithholdQc { node, .. }
+                | Fault::Byzantine { node, .. } => check(*node)?,
+                Fault::ClockSkew { node, rate } => {
+                    check(*node)?;
+                    if rate.is_nan() || *rate <= 0.0 {
+                        return Err(SimError::Scenario(format!(
+                            "{}: clock rate {} of node {} is not positive",
+                            self.name, rate, node
+                        )));
+                    }
+                }
+                Fault::CrashLeader { .. } => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Run the scenario and return the report.
+    pub fn run(&self) -> SimReport {
+        Simulation::new(self.config.clone())
+            .with_faults(self.faults.clone())
+            .run()
+    }
+}
+
+/// A fault injected in a simulation.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Fault {
+    /// Cut the messages between the nodes in different groups. The nodes not in any group are not
+    /// affected.
+    Partition {
+        /// The groups of the node indexes.
+        groups: Vec<Vec<usize>>,
+        /// When the partition starts.
+        #[serde(default)]
+        at: Trigger,
+        /// When the partition heals.
+        until: Option<Trigger>,
+    },
+    /// Crash a node and restart it when `until` fires.
+    Crash {
+        /// The index of the node.
+        node: usize,
+        /// When the node crashes.
+        #[serde(default)]
+        at: Trigger,
+        /// When the node restarts.
+        until: Option<Trigger>,
+        /// Whether the wal of the node is lost on restart.
+        #[serde(default)]
+        lose_wal: bool,
+    },
+    /// Crash the leader of the first round of a height once it receives a vote of the height,
+    /// which is in the middle of aggregating the votes, and restart it when `until` fires.
+    CrashLeader {
+        /// The height of which the leader crashes.
+        height: u64,
+        /// When the leader restarts.
+        until: Option<Trigger>,
+        /// Whether the wal of the leader is lost on restart.
+        #[serde(default)]
+        lose_wal: bool,
+    },
+    /// Drop the quorum certificates that a node sends as the relayer.
+    WithholdQc {
+        /// The index of the node.
+        node: usize,
+        /// When the node starts withholding.
+        #[serde(default)]
+        at: Trigger,
+        /// When the node stops withholding.
+        until: Option<Trigger>,
+    },
+    /// Make a node behave byzantine for the whole run. The byzantine nodes are excluded from the
+    /// safety and liveness checks.
+    Byzantine {
+        /// The index of the node.
+        node: usize,
+        /// The behaviour of the node.
+        behaviour: Behaviour,
+    },
+    /// Make the clock of a node run at the given rate of the virtual time for the whole run, so
+    /// its timeouts expire earlier or later than the others.
+    ClockSkew {
+        /// The index of the node.
+        node: usize,
+        /// The rate of the clock of the node.
+        rate: f64,
+    },
+}
+
+impl Fault {
+    /// When the fault starts. A crash of the leader starts on the vote it receives instead.
+    pub(crate) fn start(&self) -> Option<Trigger> {
+        match self {
+            Fault::Partition { at, .. }
+            | Fault::Crash { at, .. }
+            | Fault::WithholdQc { at, .. } => Some(*at),
+            Fault::CrashLeader { .. } | Fault::Byzantine { .. } | Fault::ClockSkew { .. } => None,
+        }
+    }
+
+    /// When the fault ends.
+    pub(crate) fn end(&self) -> Option<Trigger> {
+        match self {
+            Fault::Partition { until, .. }
+            | Fault::Crash { until, .. }
+            | Fault::CrashLeader { until, .. }
+            | Fault::WithholdQc { until, .. } => *until,
+            Fault::Byzantine { .. } | Fault::ClockSkew { .. } => None,
+        }
+    }
+
+    /// Return whether the fault drops the message while it is active.
+    pub(crate) fn blocks(&self, envelope: &Envelope) -> bool {
+        match self {
+            Fault::Partition { groups, .. } => {
+                let group = |node| groups.iter().position(|group| group.contains(&node));
+                match (group(envelope.from), group(envelope.to)) {
+                    (Some(from), Some(to)) => from != to,
+                    _ => false,
+                }
+            }
+            Fault::WithholdQc { node, .. } => {
+                envelope.from == *node && matches!(envelope.msg, OverlordMsg::AggregatedVote(_))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// A condition that starts or ends a fault.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Trigger {
+    /// Fire when some node commits the height.
+    Height(u64),
+    /// Fire at the virtual time in millisecond.
+    Time(u64),
+}
+
+impl Default for Trigger {
+    fn default() -> Self {
+        Trigger::Time(0)
+    }
+}
+
+impl Trigger {
+    pub(crate) fn fired(&self, height: u64, time: u64) -> bool {
+        match self {
+            Trigger::Height(h) => height >= *h,
+            Trigger::Time(t) => time >= *t,
+        }
+    }
+}
+
+/// The phase of a fault in a simulation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Phase {
+    Pending,
+    Active,
+    Done,
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sim::scenario::{Fault, Scenario, Trigger};
+
+    #[test]
+    fn test_scenario_format() {
+        let json = r#"{
+            "name": "partition_heal",
+            "config": { "nodes": 4, "heights": 6 },
+            "faults": [
+                { "type": "partition", "groups": [[0, 1, 2], [3]], "until": { "height": 3 } },
+                { "type": "clock_skew", "node": 0, "rate": 0.0 }
+            ]
+        }"#;
+        let scenario: Scenario = serde_json::from_str(json).unwrap();
+        assert_eq!(scenario.config.seed, 0);
+        assert_eq!(
+            scenario.faults[0],
+            Fault::Partition {
+                groups: vec![vec![0, 1, 2], vec![3]],
+                at: Trigger::Time(0),
+                until: Some(Trigger::Height(3)),
+            }
+        );
+        assert!(scenario.validate().is_err());
+    }
+}
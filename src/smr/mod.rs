@@ -7,13 +7,14 @@ use std::pin::Pin;
 use std::task::{Context, Poll};
 
 use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use futures::future::FutureExt;
 use futures::stream::{FusedStream, Stream, StreamExt};
 use log::error;
 
 use crate::smr::smr_types::{SMREvent, SMRStatus, SMRTrigger, TriggerSource, TriggerType};
 use crate::smr::state_machine::StateMachine;
 use crate::types::Hash;
-use crate::{error::ConsensusError, ConsensusResult, INIT_ROUND};
+use crate::{error::ConsensusError, ConsensusResult, Spawner, INIT_ROUND};
 
 ///
 #[allow(clippy::upper_case_acronyms)]
@@ -43,18 +44,21 @@ impl SMR {
         self.smr_handler.take().unwrap()
     }
 
-    /// Run SMR module in tokio environment.
-    pub fn run(mut self) {
-        tokio::spawn(async move {
-            loop {
-                let res = self.state_machine.next().await;
-                if let Some(Err(err)) = res {
-                    error!("Overlord: SMR error {:?}", err);
-                } else if res.is_none() {
-                    break;
+    /// Run SMR module in a task spawned by the spawner.
+    pub fn run(mut self, spawner: &dyn Spawner) {
+        spawner.spawn(
+            async move {
+                loop {
+                    let res = self.state_machine.next().await;
+                    if let Some(Err(err)) = res {
+                        error!("Overlord: SMR error {:?}", err);
+                    } else if res.is_none() {
+                        break;
+                    }
                 }
             }
-        });
+            .boxed(),
+        );
     }
 }
 
@@ -578,6 +578,10 @@ impl StateMachine {
     }
 }
 
+/// An exhaustive explorer of the trigger sequences which checks the lock rules.
+#[cfg(test)]
+mod explorer;
+
 #[cfg(test)]
 mod test {
     use bytes::Bytes;
@@ -0,0 +1,334 @@
+use std::collections::{BTreeSet, VecDeque};
+use std::fmt;
+use std::task::{Context, Poll};
+
+use derive_more::Display;
+use futures::channel::mpsc::unbounded;
+use futures::stream::StreamExt;
+use futures::task::noop_waker_ref;
+
+use crate::smr::smr_types::{Lock, SMREvent, SMRTrigger, Step, TriggerSource, TriggerType};
+use crate::smr::state_machine::StateMachine;
+use crate::types::Hash;
+
+/// The height that the explorer runs in.
+const HEIGHT: u64 = 1;
+/// The highest round of the triggers. The state machine may go one round further.
+const MAX_ROUND: u64 = 2;
+
+/// The hashes of the two conflicting blocks.
+const BLOCK_A: &[u8] = b"A";
+const BLOCK_B: &[u8] = b"B";
+
+/// An abstract state of the state machine in a height. Besides the fields of the state machine,
+/// it keeps the highest lock round and the last precommit of a block in the height, which the
+/// invariants are checked against.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Model {
+    round: u64,
+    step: Step,
+    block_hash: Hash,
+    lock: Option<(u64, Hash)>,
+    highest_lock: Option<u64>,
+    precommit: Option<(u64, Hash)>,
+}
+
+impl Default for Model {
+    /// The state right after a new height.
+    fn default() -> Self {
+        Model {
+            round: 0,
+            step: Step::Propose,
+            block_hash: Hash::new(),
+            lock: None,
+            highest_lock: None,
+            precommit: None,
+        }
+    }
+}
+
+/// The invariants of the lock rules in a height.
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq)]
+pub(crate) enum Invariant {
+    /// A lock only comes from a prevote QC of its round and hash, and its round never decreases.
+    #[display(fmt = "lock monotonicity")]
+    LockMonotonicity,
+    /// A precommit for another block needs a prevote QC of that block in a higher round than the
+    /// last precommit, so there are no precommits for two blocks in a round.
+    #[display(fmt = "conflicting precommit")]
+    ConflictingPrecommit,
+    /// A lock is only removed by a nil prevote QC or a proposal of a higher round PoLC.
+    #[display(fmt = "unlock without a higher round PoLC")]
+    Unlock,
+}
+
+/// A violation of an invariant, with the shortest trace of the triggers which leads to it.
+#[derive(Clone, Debug)]
+pub(crate) struct Violation {
+    pub(crate) invariant: Invariant,
+    pub(crate) trace: Vec<SMRTrigger>,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} is violated after new height {}:",
+            self.invariant, HEIGHT
+        )?;
+        for (i, trigger) in self.trace.iter().enumerate() {
+            writeln!(
+                f,
+                "  {}. {} from {}, round {}, hash {}, lock round {:?}",
+                i + 1,
+                trigger.trigger_type,
+                trigger.source,
+                trigger.round,
+                hash_name(&trigger.hash),
+                trigger.lock_round
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Explore every state reachable by the triggers of the alphabet in breadth first order, and
+/// return the number of the states or the first violation, whose trace is the shortest one.
+pub(crate) fn explore<F>(step: F) -> Result<usize, Violation>
+where
+    F: Fn(&Model, &SMRTrigger) -> Model,
+{
+    let alphabet = alphabet();
+    let mut visited = BTreeSet::new();
+    let mut queue = VecDeque::new();
+    visited.insert(Model::default());
+    queue.push_back((Model::default(), Vec::new()));
+
+    while let Some((model, trace)) = queue.pop_front() {
+        for trigger in alphabet.iter() {
+            let next = step(&model, trigger);
+            let mut next_trace = trace.clone();
+            next_trace.push(trigger.clone());
+
+            if let Err(invariant) = check(&model, trigger, &next) {
+                return Err(Violation {
+                    invariant,
+                    trace: next_trace,
+                });
+            }
+            if visited.insert(next.clone()) {
+                queue.push_back((next, next_trace));
+            }
+        }
+    }
+    Ok(visited.len())
+}
+
+/// Apply a trigger to a state machine in the given state, through the stream of the state
+/// machine, and return the state after it.
+pub(crate) fn apply(model: &Model, trigger: &SMRTrigger) -> Model {
+    let (tx, rx) = unbounded();
+    let (mut state_machine, mut evt_state, _evt_timer) = StateMachine::new(rx);
+    state_machine.height = HEIGHT;
+    state_machine.round = model.round;
+    state_machine.step = model.step.clone();
+    state_machine.block_hash = model.block_hash.clone();
+    state_machine.lock = model.lock.clone().map(|(round, hash)| Lock { round, hash });
+
+    let mut cx = Context::from_waker(noop_waker_ref());
+    tx.unbounded_send(trigger.clone()).unwrap();
+    // The errors, such as a fork, are reported by the state machine and do not stop it.
+    let _ = state_machine.poll_next_unpin(&mut cx);
+
+    let mut precommit = model.precommit.clone();
+    while let Poll::Ready(Some(event)) = evt_state.poll_next_unpin(&mut cx) {
+        if let SMREvent::PrecommitVote {
+            round, block_hash, ..
+        } = event
+        {
+            if !block_hash.is_empty() {
+                precommit = Some((round, block_hash));
+            }
+        }
+    }
+
+    let lock = state_machine.lock.map(|lock| (lock.round, lock.hash));
+    let highest_lock = match (&lock, model.highest_lock) {
+        (Some((round, _)), Some(highest)) => Some(highest.max(*round)),
+        (Some((round, _)), None) => Some(*round),
+        (None, highest) => highest,
+    };
+
+    Model {
+        round: state_machine.round,
+        step: state_machine.step,
+        block_hash: state_machine.block_hash,
+        lock,
+        highest_lock,
+        precommit,
+    }
+}
+
+/// Check the invariants on a transition.
+fn check(before: &Model, trigger: &SMRTrigger, after: &Model) -> Result<(), Invariant> {
+    let is_prevote_qc =
+        trigger.trigger_type == TriggerType::PrevoteQC && trigger.source == TriggerSource::State;
+
+    match (&before.lock, &after.lock) {
+        (_, Some((round, hash))) if before.lock != after.lock => {
+            let from_qc = is_prevote_qc && trigger.round == *round && trigger.hash == *hash;
+            let higher = match before.highest_lock {
+                Some(highest) => *round > highest,
+                None => true,
+            };
+            if !from_qc || !higher {
+                return Err(Invariant::LockMonotonicity);
+            }
+        }
+        (Some((round, _)), None) => {
+            let nil_polc = is_prevote_qc && trigger.hash.is_empty() && trigger.round > *round;
+            let proposal_polc = trigger.trigger_type == TriggerType::Proposal
+                && trigger.source == TriggerSource::State
+                && matches!(trigger.lock_round, Some(lock_round) if lock_round > *round);
+            if !nil_polc && !proposal_polc {
+                return Err(Invariant::Unlock);
+            }
+        }
+        _ => (),
+    }
+
+    if let (Some((last_round, last_hash)), Some((round, hash))) =
+        (&before.precommit, &after.precommit)
+    {
+        if hash != last_hash {
+            let polc = is_prevote_qc && trigger.hash == *hash && trigger.round > *last_round;
+            if !polc || round <= last_round {
+                return Err(Invariant::ConflictingPrecommit);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// All the triggers of the height up to the highest round, from the sources which may send them.
+/// The wal information and new height triggers are left out as they replace the state.
+fn alphabet() -> Vec<SMRTrigger> {
+    let blocks = [Hash::from(BLOCK_A), Hash::from(BLOCK_B)];
+    let qc_hashes = [Hash::new(), Hash::from(BLOCK_A), Hash::from(BLOCK_B)];
+    let mut triggers = Vec::new();
+
+    for round in 0..=MAX_ROUND {
+        for hash in blocks.iter() {
+            triggers.push(trigger(
+                TriggerType::Proposal,
+                TriggerSource::State,
+                hash,
+                round,
+                None,
+            ));
+            for lock_round in 0..round {
+                triggers.push(trigger(
+                    TriggerType::Proposal,
+                    TriggerSource::State,
+                    hash,
+                    round,
+                    Some(lock_round),
+                ));
+            }
+        }
+        for trigger_type in [TriggerType::PrevoteQC, TriggerType::PrecommitQC] {
+            for hash in qc_hashes.iter() {
+                triggers.push(trigger(
+                    trigger_type.clone(),
+                    TriggerSource::State,
+                    hash,
+                    round,
+                    None,
+                ));
+            }
+        }
+        for trigger_type in [
+            TriggerType::Proposal,
+            TriggerType::PrevoteQC,
+            TriggerType::PrecommitQC,
+            TriggerType::BrakeTimeout,
+        ] {
+            triggers.push(trigger(
+                trigger_type,
+                TriggerSource::Timer,
+                &Hash::new(),
+                round,
+                None,
+            ));
+        }
+        if round > 0 {
+            triggers.push(trigger(
+                TriggerType::ContinueRound,
+                TriggerSource::State,
+                &Hash::new(),
+                round,
+                None,
+            ));
+        }
+    }
+    triggers
+}
+
+fn trigger(
+    trigger_type: TriggerType,
+    source: TriggerSource,
+    hash: &Hash,
+    round: u64,
+    lock_round: Option<u64>,
+) -> SMRTrigger {
+    SMRTrigger {
+        trigger_type,
+        source,
+        hash: hash.clone(),
+        lock_round,
+        round,
+        height: HEIGHT,
+        wal_info: None,
+    }
+}
+
+fn hash_name(hash: &Hash) -> String {
+    if hash.is_empty() {
+        "nil".to_string()
+    } else {
+        String::from_utf8_lossy(hash).to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::smr::smr_types::{TriggerSource, TriggerType};
+    use crate::smr::state_machine::explorer::{apply, explore, Invariant};
+
+    #[test]
+    fn test_smr_invariants() {
+        match explore(apply) {
+            Ok(states) => assert!(states > 100, "only {} states explored", states),
+            Err(violation) => panic!("{}", violation),
+        }
+    }
+
+    #[test]
+    fn test_minimal_trace() {
+        // A broken state machine which drops the lock on a choke QC.
+        let violation = explore(|model, trigger| {
+            let mut next = apply(model, trigger);
+            if trigger.trigger_type == TriggerType::ContinueRound {
+                next.lock = None;
+            }
+            next
+        })
+        .unwrap_err();
+
+        assert_eq!(violation.invariant, Invariant::Unlock);
+        assert_eq!(violation.trace.len(), 2, "{}", violation);
+        assert_eq!(violation.trace[0].trigger_type, TriggerType::PrevoteQC);
+        assert_eq!(violation.trace[0].source, TriggerSource::State);
+        assert_eq!(violation.trace[1].trigger_type, TriggerType::ContinueRound);
+    }
+}
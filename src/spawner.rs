@@ -0,0 +1,68 @@
+use std::fmt::Debug;
+
+use futures::future::BoxFuture;
+use tokio::runtime::Handle;
+
+/// Trait for spawning the tasks of the overlord consensus. The SMR, the timer, the signature
+/// verifications and the block checks run in the tasks spawned by the spawner, so the caller
+/// decides which runtime or task set they live in.
+pub trait Spawner: Debug + Send + Sync {
+    /// Spawn a task which runs the given future to completion.
+    fn spawn(&self, future: BoxFuture<'static, ()>);
+}
+
+/// A spawner that spawns the tasks onto a tokio runtime. It is the default spawner of overlord.
+#[derive(Clone, Debug, Default)]
+pub struct TokioSpawner {
+    handle: Option<Handle>,
+}
+
+impl TokioSpawner {
+    /// Create a spawner that spawns onto the runtime in which the task is spawned.
+    pub fn new() -> Self {
+        TokioSpawner::default()
+    }
+
+    /// Create a spawner that spawns onto the runtime of the given handle.
+    pub fn with_handle(handle: Handle) -> Self {
+        TokioSpawner {
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Spawner for TokioSpawner {
+    fn spawn(&self, future: BoxFuture<'static, ()>) {
+        match self.handle.as_ref() {
+            Some(handle) => drop(handle.spawn(future)),
+            None => drop(tokio::spawn(future)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::channel::oneshot::channel;
+    use futures::FutureExt;
+    use tokio::runtime::Builder;
+
+    use crate::spawner::{Spawner, TokioSpawner};
+
+    #[test]
+    fn test_tokio_spawner() {
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(1)
+            .build()
+            .unwrap();
+        let spawner = TokioSpawner::with_handle(runtime.handle().clone());
+
+        let (tx, rx) = channel();
+        spawner.spawn(
+            async move {
+                let _ = tx.send(1);
+            }
+            .boxed(),
+        );
+        assert_eq!(futures::executor::block_on(rx), Ok(1));
+    }
+}
@@ -71,6 +71,14 @@ where
         )
     }
 
+    /// Get the number of signed proposals that height is higher than the given height.
+    pub fn count_above(&self, height: u64) -> usize {
+        self.0
+            .range(height + 1..)
+            .map(|(_, round_collector)| round_collector.0.len())
+            .sum()
+    }
+
     /// Remove items that height is less than `till`.
     pub fn flush(&mut self, till: u64) {
         self.0 = self.0.split_off(&till);
@@ -236,6 +244,12 @@ impl VoteCollector {
         0
     }
 
+    /// Get the number of signed votes and quorum certificates that height is higher than the
+    /// given height.
+    pub fn count_above(&self, height: u64) -> usize {
+        self.0.range(height + 1..).map(|(_, vrc)| vrc.len()).sum()
+    }
+
     /// Remove items that height is less than `till`.
     pub fn flush(&mut self, till: u64) {
         self.0 = self.0.split_off(&till);
@@ -321,6 +335,10 @@ impl VoteRoundCollector {
         }
         0
     }
+
+    fn len(&self) -> usize {
+        self.general.values().map(|rc| rc.len()).sum()
+    }
 }
 
 /// A round collector contains a qc and prevote votes and precommit votes.
@@ -380,6 +398,10 @@ impl RoundCollector {
         }
         self.precommit.vote_count()
     }
+
+    fn len(&self) -> usize {
+        self.prevote.vote_count() + self.precommit.vote_count() + self.qc.len()
+    }
 }
 
 /// A struct includes prevoteQC and precommitQC in a round.
@@ -412,6 +434,10 @@ impl QuorumCertificate {
         }
     }
 
+    fn len(&self) -> usize {
+        usize::from(self.prevote.is_some()) + usize::from(self.precommit.is_some())
+    }
+
     fn get_all_qcs(&mut self) -> Vec<AggregatedVote> {
         let mut res = Vec::new();
 
@@ -701,6 +727,34 @@ mod test {
         assert!(proposals.get(2, 0).is_err());
     }
 
+    #[test]
+    fn test_count_above() {
+        let mut proposals = ProposalCollector::<Pill>::new();
+        let mut votes = VoteCollector::new();
+
+        for height in 1..4 {
+            assert!(proposals
+                .insert(Context::new(), height, 0, gen_signed_proposal(height, 0))
+                .is_ok());
+            assert!(proposals
+                .insert(Context::new(), height, 1, gen_signed_proposal(height, 1))
+                .is_ok());
+
+            let hash = gen_hash();
+            let addr = gen_address();
+            let vote = gen_signed_vote(height, 0, VoteType::Prevote, hash.clone(), addr.clone());
+            votes.insert_vote(Context::new(), hash, vote, addr);
+            votes.set_qc(_gen_aggregated_vote(height, 0, VoteType::Precommit));
+        }
+
+        assert_eq!(proposals.count_above(0), 6);
+        assert_eq!(proposals.count_above(2), 2);
+        assert_eq!(proposals.count_above(3), 0);
+        assert_eq!(votes.count_above(0), 6);
+        assert_eq!(votes.count_above(1), 4);
+        assert_eq!(votes.count_above(3), 0);
+    }
+
     #[test]
     fn test_vote_collector() {
         let mut votes = VoteCollector::new();
@@ -0,0 +1,361 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::error::Error;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bit_vec::BitVec;
+use bytes::Bytes;
+use creep::Context;
+use parking_lot::Mutex;
+
+use crate::error::{ConsensusError, ErrorSource};
+use crate::sim::{sim_address, sim_hash, SimCrypto, SimError, SimWal};
+use crate::types::{
+    Address, AggregatedSignature, AggregatedVote, Commit, Hash, InvalidProposal, Node, OverlordMsg,
+    Proof, Proposal, RawBlock, SignedProposal, SignedVote, Status, ViewChangeReason, VoteType,
+};
+use crate::{
+    get_leader, Consensus, Crypto, ManualClock, Overlord, OverlordConfig, OverlordHandler,
+    SignDomain,
+};
+
+/// The number of times to yield to let the node handle the inputs before the time moves on.
+const SETTLE_YIELDS: usize = 64;
+
+/// The application of a node under test, which records what the node sends, commits and
+/// requests to synchronize, and the heights of which the authority list is got. The list of a
+/// height is the latest one of `authority_lists` from a height not above it, or the list which the
+/// node starts with. The commits fail with the queued `commit_errors` in order, and the sync
+/// requests and the authority lists fail while `fail_sync` and `fail_authority` are set. The
+/// observed commits are recorded, and the rich status of the next height is returned for them
+/// while `follow_status` is set. The block checks fail with the queued `check_errors` in order,
+/// and the codes of the reported errors and the hashes of the reported invalid proposals are
+/// recorded.
+#[derive(Default)]
+pub(crate) struct TestApp {
+    authority_list: Vec<Node>,
+    pub(crate) sent: Mutex<Vec<OverlordMsg<RawBlock>>>,
+    pub(crate) commits: Mutex<Vec<(u64, Hash)>>,
+    pub(crate) commit_errors: Mutex<VecDeque<ErrorSource>>,
+    pub(crate) observed: Mutex<Vec<(u64, Hash)>>,
+    pub(crate) follow_status: Mutex<bool>,
+    pub(crate) syncs: Mutex<Vec<u64>>,
+    pub(crate) fail_sync: Mutex<bool>,
+    pub(crate) authority_lists: Mutex<BTreeMap<u64, Vec<Node>>>,
+    pub(crate) authority_calls: Mutex<Vec<u64>>,
+    pub(crate) fail_authority: Mutex<bool>,
+    pub(crate) check_errors: Mutex<VecDeque<ErrorSource>>,
+    pub(crate) errors: Mutex<Vec<u16>>,
+    pub(crate) invalid: Mutex<Vec<Hash>>,
+}
+
+#[async_trait]
+impl Consensus<RawBlock> for TestApp {
+    async fn get_block(
+        &self,
+        _ctx: Context,
+        height: u64,
+    ) -> Result<(RawBlock, Hash), Box<dyn Error + Send>> {
+        let block = Bytes::from(height.to_be_bytes().to_vec());
+        let hash = sim_hash(&block);
+        Ok((RawBlock(block), hash))
+    }
+
+    async fn check_block(
+        &self,
+        _ctx: Context,
+        _height: u64,
+        _hash: Hash,
+        _block: RawBlock,
+    ) -> Result<(), Box<dyn Error + Send>> {
+        match self.check_errors.lock().pop_front() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    async fn commit(
+        &self,
+        _ctx: Context,
+        height: u64,
+        commit: Commit<RawBlock>,
+    ) -> Result<Status, Box<dyn Error + Send>> {
+        self.commits.lock().push((height, commit.proof.block_hash));
+        if let Some(e) = self.commit_errors.lock().pop_front() {
+            return Err(e);
+        }
+        Ok(Status {
+            height: height + 1,
+            interval: None,
+            timer_config: None,
+            authority_list: self.authority_list.clone(),
+        })
+    }
+
+    async fn on_observed_commit(
+        &self,
+        _ctx: Context,
+        height: u64,
+        commit: Commit<RawBlock>,
+    ) -> Result<Option<Status>, Box<dyn Error + Send>> {
+        self.observed.lock().push((height, commit.proof.block_hash));
+        if !*self.follow_status.lock() {
+            return Ok(None);
+        }
+        Ok(Some(Status {
+            height: height + 1,
+            interval: None,
+            timer_config: None,
+            authority_list: self.authority_list.clone(),
+        }))
+    }
+
+    async fn request_sync(
+        &self,
+        _ctx: Context,
+        target_height: u64,
+        _proof: Proof,
+    ) -> Result<(), Box<dyn Error + Send>> {
+        self.syncs.lock().push(target_height);
+        if *self.fail_sync.lock() {
+            return Err(Box::new(SimError::InvalidBlock(target_height)));
+        }
+        Ok(())
+    }
+
+    async fn get_authority_list(
+        &self,
+        _ctx: Context,
+        height: u64,
+    ) -> Result<Vec<Node>, Box<dyn Error + Send>> {
+        self.authority_calls.lock().push(height);
+        if *self.fail_authority.lock() {
+            return Err(Box::new(SimError::InvalidBlock(height)));
+        }
+        let lists = self.authority_lists.lock();
+        let list = lists.range(..=height).next_back().map(|(_, list)| list);
+        Ok(list.unwrap_or(&self.authority_list).clone())
+    }
+
+    async fn broadcast_to_other(
+        &self,
+        _ctx: Context,
+        msg: OverlordMsg<RawBlock>,
+    ) -> Result<(), Box<dyn Error + Send>> {
+        self.sent.lock().push(msg);
+        Ok(())
+    }
+
+    async fn transmit_to_relayer(
+        &self,
+        _ctx: Context,
+        _addr: Address,
+        msg: OverlordMsg<RawBlock>,
+    ) -> Result<(), Box<dyn Error + Send>> {
+        self.sent.lock().push(msg);
+        Ok(())
+    }
+
+    fn report_error(&self, _ctx: Context, error: ConsensusError) {
+        log::debug!("Overlord: test node error {:?}", error);
+        self.errors.lock().push(error.code());
+    }
+
+    fn report_invalid_proposal(&self, _ctx: Context, invalid: InvalidProposal<RawBlock>) {
+        let hash = invalid.signed_proposal.proposal.block_hash;
+        self.invalid.lock().push(hash);
+    }
+
+    fn report_view_change(
+        &self,
+        _ctx: Context,
+        _height: u64,
+        _round: u64,
+        _reason: ViewChangeReason,
+    ) {
+    }
+}
+
+/// A node run on a manual clock among an authority list of simulated nodes, of which the others
+/// are driven by the test. It must run in a current thread runtime.
+pub(crate) struct Harness {
+    pub(crate) clock: Arc<ManualClock>,
+    pub(crate) app: Arc<TestApp>,
+    pub(crate) authority_list: Vec<Node>,
+    domain: SignDomain,
+    handler: OverlordHandler<RawBlock>,
+}
+
+impl Harness {
+    /// Start the node of the given index among four nodes from the committed height and the wal.
+    pub(crate) fn start(index: usize, height: u64, config: OverlordConfig, wal: SimWal) -> Self {
+        Harness::start_among(4, index, height, config, wal)
+    }
+
+    /// Start the node of the given index among the given number of nodes.
+    pub(crate) fn start_among(
+        nodes: usize,
+        index: usize,
+        height: u64,
+        config: OverlordConfig,
+        wal: SimWal,
+    ) -> Self {
+        let mut authority_list = (0..nodes)
+            .map(|i| Node::new(sim_address(i)))
+            .collect::<Vec<_>>();
+        authority_list.sort();
+        let app = Arc::new(TestApp {
+            authority_list: authority_list.clone(),
+            ..Default::default()
+        });
+        let domain = config.sign_domain.clone();
+        let clock = Arc::new(ManualClock::new());
+        let address = sim_address(index);
+        let overlord = Overlord::new(
+            address.clone(),
+            Arc::clone(&app),
+            Arc::new(SimCrypto::new(address)),
+            Arc::new(wal),
+            config,
+        )
+        .with_clock(Arc::<ManualClock>::clone(&clock));
+        let handler = overlord.get_handler();
+
+        let list = authority_list.clone();
+        tokio::spawn(async move {
+            if let Err(e) = overlord.run(height, list).await {
+                log::error!("Overlord: test node error {:?}", e);
+            }
+        });
+
+        let harness = Harness {
+            clock,
+            app,
+            authority_list,
+            domain,
+            handler,
+        };
+        harness.send(OverlordMsg::RichStatus(Status {
+            height: height + 1,
+            interval: None,
+            timer_config: None,
+            authority_list: harness.authority_list.clone(),
+        }));
+        harness
+    }
+
+    /// Send a message to the node.
+    pub(crate) fn send(&self, msg: OverlordMsg<RawBlock>) {
+        let _ = self.handler.send_msg(Context::new(), msg);
+    }
+
+    /// Let the node handle the inputs and then fire the earliest timer, for the given times.
+    pub(crate) async fn run_timers(&self, times: usize) {
+        for _ in 0..times {
+            settle().await;
+            self.clock.advance_to_next();
+        }
+        settle().await;
+    }
+
+    /// Build a quorum certificate signed by the first voters of the sorted authority list.
+    pub(crate) fn gen_qc(
+        &self,
+        voters: usize,
+        vote_type: VoteType,
+        height: u64,
+        round: u64,
+        block_hash: Hash,
+    ) -> AggregatedVote {
+        let list = self.authority_list.clone();
+        self.gen_qc_of(&list, voters, vote_type, height, round, block_hash)
+    }
+
+    /// Build a quorum certificate signed by the first voters of the given sorted authority list.
+    pub(crate) fn gen_qc_of(
+        &self,
+        authority_list: &[Node],
+        voters: usize,
+        vote_type: VoteType,
+        height: u64,
+        round: u64,
+        block_hash: Hash,
+    ) -> AggregatedVote {
+        let mut qc = AggregatedVote {
+            signature: AggregatedSignature {
+                signature: Bytes::new(),
+                address_bitmap: Bytes::new(),
+            },
+            vote_type,
+            height,
+            round,
+            block_hash,
+            leader: get_leader(height, round, authority_list.to_vec()),
+        };
+        let hash = sim_hash(&self.domain.vote_payload(&qc.to_vote()));
+        let mut bitmap = BitVec::from_elem(authority_list.len(), false);
+        let mut signatures = Vec::new();
+        let mut addresses = Vec::new();
+        for (i, node) in authority_list.iter().take(voters).enumerate() {
+            bitmap.set(i, true);
+            let crypto = SimCrypto::new(node.address.clone());
+            signatures.push(crypto.sign(hash.clone()).unwrap());
+            addresses.push(node.address.clone());
+        }
+        qc.signature = AggregatedSignature {
+            signature: SimCrypto::new(Bytes::new())
+                .aggregate_signatures(signatures, addresses)
+                .unwrap(),
+            address_bitmap: Bytes::from(bitmap.to_bytes()),
+        };
+        qc
+    }
+
+    /// Build a proposal of the block hash signed by the leader of the height and round.
+    pub(crate) fn gen_proposal(
+        &self,
+        height: u64,
+        round: u64,
+        block_hash: Hash,
+    ) -> SignedProposal<RawBlock> {
+        let proposal = Proposal {
+            height,
+            round,
+            content: RawBlock(block_hash.clone()),
+            block_hash,
+            lock: None,
+            proposer: get_leader(height, round, self.authority_list.clone()),
+        };
+        let crypto = SimCrypto::new(proposal.proposer.clone());
+        let hash = crypto.hash(self.domain.proposal_payload(&proposal));
+        SignedProposal {
+            signature: crypto.sign(hash).unwrap(),
+            proposal,
+        }
+    }
+
+    /// Get the votes that the node has sent.
+    pub(crate) fn sent_votes(&self) -> Vec<SignedVote> {
+        self.app
+            .sent
+            .lock()
+            .iter()
+            .filter_map(|msg| match msg {
+                OverlordMsg::SignedVote(vote) => Some(vote.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        self.send(OverlordMsg::Stop);
+    }
+}
+
+async fn settle() {
+    for _ in 0..SETTLE_YIELDS {
+        tokio::task::yield_now().await;
+    }
+}
@@ -1,5 +1,8 @@
 ///
 mod collection;
+/// A node under test driven on a manual clock.
+#[cfg(test)]
+pub(crate) mod harness;
 ///
 mod parallel;
 ///
@@ -1,27 +1,97 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use bytes::Bytes;
 use creep::Context;
 use futures::channel::mpsc::UnboundedSender;
+use futures::FutureExt;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+#[cfg(feature = "apm")]
 use muta_apm::derive::tracing_span;
 
+use crate::error::ConsensusError;
 use crate::types::{Address, AggregatedVote, OverlordMsg};
 use crate::utils::auth_manage::AuthorityManage;
-use crate::{Codec, ConsensusResult, Crypto};
+use crate::{Codec, ConsensusResult, Crypto, Metrics, SignDomain, Spawner};
+
+/// A queue that limits the number of signature verifications running concurrently and reports
+/// the number of messages waiting for or under verification to the metrics. The verifications
+/// run in the tasks spawned by the spawner.
+#[derive(Debug)]
+pub struct VerifyQueue {
+    permits: Semaphore,
+    depth: AtomicUsize,
+    metrics: Arc<dyn Metrics>,
+    spawner: Arc<dyn Spawner>,
+}
+
+impl VerifyQueue {
+    pub fn new(concurrency: usize, metrics: Arc<dyn Metrics>, spawner: Arc<dyn Spawner>) -> Self {
+        VerifyQueue {
+            permits: Semaphore::new(concurrency),
+            depth: AtomicUsize::new(0),
+            metrics,
+            spawner,
+        }
+    }
+
+    fn enter(&self) {
+        let depth = self.depth.fetch_add(1, Ordering::SeqCst) + 1;
+        self.metrics.set_verify_queue_depth(depth);
+    }
+
+    async fn acquire(&self) -> VerifyGuard<'_> {
+        let permit = self.permits.acquire().await.ok();
+        VerifyGuard {
+            queue: self,
+            _permit: permit,
+        }
+    }
+}
+
+/// Hold a verification permit and leave the queue when dropped.
+struct VerifyGuard<'a> {
+    queue: &'a VerifyQueue,
+    _permit: Option<SemaphorePermit<'a>>,
+}
 
-#[tracing_span(kind = "overlord.vreify_sig_pool")]
+impl Drop for VerifyGuard<'_> {
+    fn drop(&mut self) {
+        let depth = self.queue.depth.fetch_sub(1, Ordering::SeqCst) - 1;
+        self.queue.metrics.set_verify_queue_depth(depth);
+    }
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "overlord.parallel_verify",
+        skip_all,
+        parent = crate::utils::trace::parent_span(&ctx),
+        fields(height = msg.get_height())
+    )
+)]
+#[cfg_attr(feature = "apm", tracing_span(kind = "overlord.vreify_sig_pool"))]
 pub async fn parallel_verify<T: Codec + 'static, C: Crypto + Sync + 'static>(
     ctx: Context,
     msg: OverlordMsg<T>,
     crypto: Arc<C>,
+    queue: Arc<VerifyQueue>,
     authority: AuthorityManage,
+    domain: SignDomain,
     tx: UnboundedSender<(Context, OverlordMsg<T>)>,
 ) {
     let msg_clone = msg.clone();
-    tokio::spawn(async move {
+    queue.enter();
+    let spawner = Arc::clone(&queue.spawner);
+    let task = async move {
+        // Limit the number of verifications running concurrently.
+        let _guard = queue.acquire().await;
+
         match msg {
             OverlordMsg::SignedProposal(sp) => {
-                let hash = crypto.hash(Bytes::from(rlp::encode(&sp.proposal)));
+                let hash = crypto.hash(domain.proposal_payload(&sp.proposal));
                 if let Err(err) = crypto.verify_signature(
                     sp.signature.clone(),
                     hash,
@@ -41,6 +111,7 @@ pub async fn parallel_verify<T: Codec + 'static, C: Crypto + Sync + 'static>(
                         crypto,
                         polc.lock_votes,
                         authority,
+                        &domain,
                         tx.clone(),
                         msg_clone.clone(),
                     );
@@ -50,7 +121,7 @@ pub async fn parallel_verify<T: Codec + 'static, C: Crypto + Sync + 'static>(
             }
 
             OverlordMsg::SignedVote(sv) => {
-                let hash = crypto.hash(Bytes::from(rlp::encode(&sv.vote)));
+                let hash = crypto.hash(domain.vote_payload(&sv.vote));
                 crypto
                     .verify_signature(sv.signature.clone(), hash, sv.voter.clone())
                     .map_or_else(
@@ -68,11 +139,11 @@ pub async fn parallel_verify<T: Codec + 'static, C: Crypto + Sync + 'static>(
             }
 
             OverlordMsg::AggregatedVote(qc) => {
-                verify_qc(ctx, crypto, qc, authority, tx, msg_clone);
+                verify_qc(ctx, crypto, qc, authority, &domain, tx, msg_clone);
             }
 
             OverlordMsg::SignedChoke(sc) => {
-                let hash = crypto.hash(Bytes::from(rlp::encode(&sc.choke.to_hash())));
+                let hash = crypto.hash(domain.choke_payload(sc.choke.height, sc.choke.round));
                 crypto
                     .verify_signature(sc.signature.clone(), hash, sc.address.clone())
                     .map_or_else(
@@ -91,14 +162,19 @@ pub async fn parallel_verify<T: Codec + 'static, C: Crypto + Sync + 'static>(
 
             _ => (),
         }
-    });
+    };
+    spawner.spawn(task.boxed());
 }
 
 fn get_voters(
     addr_bitmap: &Bytes,
     authority_manage: AuthorityManage,
 ) -> ConsensusResult<Vec<Address>> {
-    authority_manage.is_above_threshold(addr_bitmap)?;
+    if !authority_manage.is_above_threshold(addr_bitmap)? {
+        return Err(ConsensusError::AggregatedSignatureErr(
+            "the vote weight of the voters is not above the threshold".to_string(),
+        ));
+    }
     authority_manage.get_voters(addr_bitmap)
 }
 
@@ -107,10 +183,11 @@ fn verify_qc<T: Codec, C: Crypto>(
     crypto: Arc<C>,
     qc: AggregatedVote,
     authority: AuthorityManage,
+    domain: &SignDomain,
     tx: UnboundedSender<(Context, OverlordMsg<T>)>,
     msg_clone: OverlordMsg<T>,
 ) {
-    let hash = crypto.hash(Bytes::from(rlp::encode(&qc.to_vote())));
+    let hash = crypto.hash(domain.vote_payload(&qc.to_vote()));
     if let Ok(voters) = get_voters(&qc.signature.address_bitmap, authority) {
         crypto
             .verify_aggregated_signature(qc.signature.signature.clone(), hash, voters)
@@ -128,3 +205,137 @@ fn verify_qc<T: Codec, C: Crypto>(
             );
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::error::Error;
+    use std::sync::Arc;
+
+    use bit_vec::BitVec;
+    use bytes::Bytes;
+    use creep::Context;
+    use futures::channel::mpsc::unbounded;
+    use futures::StreamExt;
+
+    use crate::types::{AggregatedSignature, AggregatedVote, Node, OverlordMsg, VoteType};
+    use crate::utils::auth_manage::AuthorityManage;
+    use crate::{Codec, Crypto, NoopMetrics, SignDomain, TokioSpawner};
+
+    use super::{get_voters, parallel_verify, VerifyQueue};
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct Pill;
+
+    impl Codec for Pill {
+        fn encode(&self) -> Result<Bytes, Box<dyn Error + Send>> {
+            Ok(Bytes::new())
+        }
+
+        fn decode(_data: Bytes) -> Result<Self, Box<dyn Error + Send>> {
+            Ok(Pill)
+        }
+    }
+
+    struct MockCrypto;
+
+    impl Crypto for MockCrypto {
+        fn hash(&self, msg: Bytes) -> Bytes {
+            msg
+        }
+
+        fn sign(&self, hash: Bytes) -> Result<Bytes, Box<dyn Error + Send>> {
+            Ok(hash)
+        }
+
+        fn aggregate_signatures(
+            &self,
+            _signatures: Vec<Bytes>,
+            _voters: Vec<Bytes>,
+        ) -> Result<Bytes, Box<dyn Error + Send>> {
+            Ok(Bytes::new())
+        }
+
+        fn verify_signature(
+            &self,
+            _signature: Bytes,
+            _hash: Bytes,
+            _voter: Bytes,
+        ) -> Result<(), Box<dyn Error + Send>> {
+            Ok(())
+        }
+
+        fn verify_aggregated_signature(
+            &self,
+            _aggregated_signature: Bytes,
+            _hash: Bytes,
+            _voters: Vec<Bytes>,
+        ) -> Result<(), Box<dyn Error + Send>> {
+            Ok(())
+        }
+    }
+
+    fn gen_authority() -> AuthorityManage {
+        let mut authority_list = (0u8..4)
+            .map(|i| Node::new(Bytes::from(vec![i])))
+            .collect::<Vec<_>>();
+        let mut authority = AuthorityManage::new();
+        authority.update(&mut authority_list);
+        authority
+    }
+
+    fn gen_qc(voters: usize) -> OverlordMsg<Pill> {
+        OverlordMsg::AggregatedVote(AggregatedVote {
+            signature: AggregatedSignature {
+                signature: Bytes::new(),
+                address_bitmap: gen_bitmap(voters),
+            },
+            vote_type: VoteType::Precommit,
+            height: 1,
+            round: 0,
+            block_hash: Bytes::from(vec![1u8]),
+            leader: Bytes::from(vec![0u8]),
+        })
+    }
+
+    async fn is_verified(msg: OverlordMsg<Pill>) -> bool {
+        let (tx, mut rx) = unbounded();
+        parallel_verify(
+            Context::new(),
+            msg,
+            Arc::new(MockCrypto),
+            Arc::new(VerifyQueue::new(
+                1,
+                Arc::new(NoopMetrics),
+                Arc::new(TokioSpawner::new()),
+            )),
+            gen_authority(),
+            SignDomain::Legacy,
+            tx,
+        )
+        .await;
+        // The verify task drops the sender once done, so a rejected message ends the stream.
+        rx.next().await.is_some()
+    }
+
+    fn gen_bitmap(voters: usize) -> Bytes {
+        let mut bitmap = BitVec::from_elem(4, false);
+        for i in 0..voters {
+            bitmap.set(i, true);
+        }
+        Bytes::from(bitmap.to_bytes())
+    }
+
+    #[test]
+    fn test_get_voters() {
+        let authority = gen_authority();
+        let voters = get_voters(&gen_bitmap(3), authority.clone()).unwrap();
+        assert_eq!(voters.len(), 3);
+        // A QC of a minority of the voters is rejected.
+        assert!(get_voters(&gen_bitmap(2), authority).is_err());
+    }
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_verify_minority_qc() {
+        assert!(is_verified(gen_qc(3)).await);
+        assert!(!is_verified(gen_qc(2)).await);
+    }
+}
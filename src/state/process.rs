@@ -1,6 +1,7 @@
 use std::cmp::{Ord, Ordering};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::string::ToString;
+use std::task::{Context as TaskContext, Poll};
 use std::time::{Duration, Instant};
 use std::{ops::BitXor, sync::Arc};
 
@@ -8,27 +9,52 @@ use bit_vec::BitVec;
 use bytes::Bytes;
 use creep::Context;
 use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
-use futures::{select, StreamExt};
+use futures::future::poll_fn;
+use futures::stream::FusedStream;
+use futures::{FutureExt, StreamExt};
 use hummer::coding::hex_encode;
+use parking_lot::Mutex;
+
+#[cfg(feature = "apm")]
 use muta_apm::derive::tracing_span;
-use tokio::time::sleep;
 
-use crate::error::ConsensusError;
+use crate::error::{ConsensusError, ErrorSource, TransientError};
+use crate::record::{recorded, Entry, Recorder};
 use crate::smr::smr_types::{FromWhere, SMREvent, SMRTrigger, Step, TriggerSource, TriggerType};
 use crate::smr::{Event, SMRHandler};
 use crate::state::collection::{ChokeCollector, ProposalCollector, VoteCollector};
-use crate::state::parallel::parallel_verify;
+use crate::state::parallel::{parallel_verify, VerifyQueue};
 use crate::types::{
-    Address, AggregatedChoke, AggregatedSignature, AggregatedVote, Choke, Commit, Hash, Node,
-    OverlordMsg, PoLC, Proof, Proposal, Signature, SignedChoke, SignedProposal, SignedVote, Status,
-    UpdateFrom, VerifyResp, ViewChangeReason, Vote, VoteType,
+    Address, AggregatedChoke, AggregatedSignature, AggregatedVote, Choke, Commit, Hash,
+    InvalidProposal, Node, OverlordEvent, OverlordMsg, PoLC, Proof, Proposal, Signature,
+    SignedChoke, SignedProposal, SignedVote, Status, UpdateFrom, VerifyResp, ViewChangeReason,
+    Vote, VoteType,
 };
-use crate::utils::auth_manage::AuthorityManage;
+use crate::utils::auth_manage::{AuthorityManage, AuthoritySchedule};
+use crate::utils::cancel::CancelToken;
+use crate::utils::event_bus::EventBus;
+use crate::utils::retry::retry;
+use crate::utils::timer_config::{round_timeout, TimerConfig};
 use crate::wal::{SMRBase, WalInfo, WalLock};
-use crate::{Codec, Consensus, ConsensusResult, Crypto, Wal, INIT_HEIGHT, INIT_ROUND};
+use crate::{
+    Clock, Codec, Consensus, ConsensusResult, Crypto, Metrics, OverlordConfig, Spawner, Wal,
+    INIT_HEIGHT, INIT_ROUND,
+};
 
-const FUTURE_HEIGHT_GAP: u64 = 5;
-const FUTURE_ROUND_GAP: u64 = 10;
+/// The number of height intervals to wait for a requested synchronization before requesting it
+/// again.
+const SYNC_RETRY_INTERVALS: u64 = 10;
+
+/// The services that the state runs on and reports to. The `timeouts` are shared with the timer,
+/// which tunes them in the adaptive mode.
+pub(crate) struct Runtime {
+    pub(crate) metrics: Arc<dyn Metrics>,
+    pub(crate) events: EventBus,
+    pub(crate) clock: Arc<dyn Clock>,
+    pub(crate) spawner: Arc<dyn Spawner>,
+    pub(crate) timeouts: Arc<Mutex<TimerConfig>>,
+    pub(crate) recorder: Option<Arc<Recorder>>,
+}
 
 /// Overlord state struct. It maintains the local state of the node, and monitor the SMR event. The
 /// `proposals` is used to cache the signed proposals that are with higher height or round. The
@@ -45,21 +71,54 @@ pub struct State<T: Codec, F: Consensus<T>, C: Crypto, W: Wal> {
     votes: VoteCollector,
     chokes: ChokeCollector,
     authority: AuthorityManage,
+    /// The authority lists returned by the commits, which may take effect at a later height, and
+    /// the lists of the future heights got from `get_authority_list`. The lists of the commits not
+    /// made by self are got from `get_authority_list` as well.
+    authority_schedule: AuthoritySchedule,
+    /// The future messages that can not be verified until the authority list of their height is
+    /// known. Their number is limited by the `config`.
+    unverified_msgs: BTreeMap<u64, Vec<(Context, OverlordMsg<T>)>>,
     hash_with_block: HashMap<Hash, T>,
     is_full_transaction: HashMap<Hash, bool>,
+    /// Cancel the block checks of the current height when going to a new height.
+    check_tokens: Vec<CancelToken>,
+    /// The hashes of the blocks whose check exceeds the deadline derived from the `timeouts`.
+    check_timeouts: HashSet<Hash>,
+    /// The number of the proposals that do not pass the check by proposer.
+    invalid_proposals: HashMap<Address, u64>,
     is_leader: bool,
     leader_address: Address,
     update_from_where: UpdateFrom,
     height_start: Instant,
     block_interval: u64,
+    /// The timeouts shared with the timer, which tunes them in the adaptive mode.
+    timeouts: Arc<Mutex<TimerConfig>>,
     consensus_power: bool,
+    /// If the follower mode is on, state keeps collecting proposals and precommit quorum
+    /// certificates without consensus power, and hands the observed commits to the consensus.
+    config: OverlordConfig,
+    verify_queue: Arc<VerifyQueue>,
+    last_observed: Option<u64>,
+    /// The height that self has requested to synchronize to and the time of the request.
+    sync_target: Option<(u64, Instant)>,
+    /// The lock round and the locked proposal hash reported by SMR, to publish the lock changes.
+    locked: Option<(u64, Hash)>,
     stopped: bool,
 
     verify_sig_tx: UnboundedSender<(Context, OverlordMsg<T>)>,
-    resp_tx: UnboundedSender<VerifyResp>,
+    resp_tx: UnboundedSender<VerifyResp<T>>,
+    retry_tx: UnboundedSender<RetryCall>,
     function: Arc<F>,
     wal: Arc<W>,
     util: Arc<C>,
+    /// Report the round statistics, the vote and choke counts and the wal save latency.
+    metrics: Arc<dyn Metrics>,
+    /// Publish the consensus events to the subscribers.
+    events: EventBus,
+    clock: Arc<dyn Clock>,
+    spawner: Arc<dyn Spawner>,
+    /// Record the inputs of the state and the results of the application callbacks if any.
+    recorder: Option<Arc<Recorder>>,
 }
 
 impl<T, F, C, W> State<T, F, C, W>
@@ -74,16 +133,32 @@ where
         smr: SMRHandler,
         addr: Address,
         init_height: u64,
-        interval: u64,
         mut authority_list: Vec<Node>,
+        config: OverlordConfig,
         verify_tx: UnboundedSender<(Context, OverlordMsg<T>)>,
         consensus: Arc<F>,
         crypto: Arc<C>,
         wal_engine: Arc<W>,
-    ) -> (Self, UnboundedReceiver<VerifyResp>) {
+        runtime: Runtime,
+    ) -> (
+        Self,
+        UnboundedReceiver<VerifyResp<T>>,
+        UnboundedReceiver<RetryCall>,
+    ) {
+        let Runtime {
+            metrics,
+            events,
+            clock,
+            spawner,
+            timeouts,
+            recorder,
+        } = runtime;
         let (tx, rx) = unbounded();
+        let (retry_tx, retry_rx) = unbounded();
         let mut auth = AuthorityManage::new();
         auth.update(&mut authority_list);
+        let mut schedule = AuthoritySchedule::new(config.authority_delay);
+        schedule.insert(init_height, &mut authority_list);
 
         let state = State {
             height: init_height,
@@ -95,23 +170,44 @@ where
             votes: VoteCollector::new(),
             chokes: ChokeCollector::new(),
             authority: auth,
+            authority_schedule: schedule,
+            unverified_msgs: BTreeMap::new(),
             hash_with_block: HashMap::new(),
             is_full_transaction: HashMap::new(),
+            check_tokens: Vec::new(),
+            check_timeouts: HashSet::new(),
+            invalid_proposals: HashMap::new(),
             is_leader: false,
             leader_address: Address::default(),
             update_from_where: UpdateFrom::PrecommitQC(mock_init_qc()),
-            height_start: Instant::now(),
-            block_interval: interval,
+            height_start: clock.now(),
+            block_interval: config.interval,
+            timeouts,
             stopped: false,
+            verify_queue: Arc::new(VerifyQueue::new(
+                config.verify_concurrency,
+                Arc::clone(&metrics),
+                Arc::clone(&spawner),
+            )),
+            config,
+            last_observed: None,
+            sync_target: None,
+            locked: None,
 
             verify_sig_tx: verify_tx,
             resp_tx: tx,
+            retry_tx,
             function: consensus,
             util: crypto,
             wal: wal_engine,
+            metrics,
+            events,
+            clock,
+            spawner,
+            recorder,
         };
 
-        (state, rx)
+        (state, rx, retry_rx)
     }
 
     /// Run state module.
@@ -119,84 +215,163 @@ where
         &mut self,
         mut raw_rx: UnboundedReceiver<(Context, OverlordMsg<T>)>,
         mut event: Event,
-        mut verify_resp: UnboundedReceiver<VerifyResp>,
+        mut verify_resp: UnboundedReceiver<VerifyResp<T>>,
         mut verify_sig: UnboundedReceiver<(Context, OverlordMsg<T>)>,
+        mut retry: UnboundedReceiver<RetryCall>,
     ) {
         log::debug!("Overlord: state start running");
         if let Err(e) = self.start_with_wal().await {
             log::error!("Overlord: start with wal error {:?}", e);
         }
 
+        // Poll the inputs in turn from a rotating start, so the order of handling them is fair
+        // and deterministic.
+        let mut start = 0;
         loop {
-            select! {
-                raw = raw_rx.next() => {
-                    let (ctx, msg) = raw.expect("Overlord message handler dropped");
-
-                    if msg.is_rich_status() {
-                        let _ = self.verify_sig_tx.unbounded_send((ctx, msg));
-                    } else {
-                        match self.height.cmp(&msg.get_height()) {
-                            Ordering::Less => {
-                                let _ = self.verify_sig_tx.unbounded_send((ctx, msg));
-                            }
-                            Ordering::Equal => {
-                                parallel_verify(
-                                    ctx,
-                                    msg,
-                                    Arc::clone(&self.util),
-                                    self.authority.clone(),
-                                    self.verify_sig_tx.clone()
-                                )
-                                .await;
-                            }
-                            Ordering::Greater => (),
-                        };
+            let input = poll_fn(|cx| {
+                for i in 0..5 {
+                    let input = match (start + i) % 5 {
+                        0 => poll_input(&mut raw_rx, cx).map(StateInput::Raw),
+                        1 => poll_input(&mut event, cx).map(StateInput::Event),
+                        2 => poll_input(&mut verify_resp, cx).map(StateInput::Resp),
+                        3 => poll_input(&mut verify_sig, cx).map(StateInput::Verified),
+                        _ => poll_input(&mut retry, cx).map(StateInput::Retry),
+                    };
+                    if input.is_ready() {
+                        return input;
                     }
                 }
+                Poll::Pending
+            })
+            .await;
+            start = (start + 1) % 5;
+
+            self.record(|| match &input {
+                StateInput::Raw(Some((_, msg))) => Some(Entry::Message(msg.clone())),
+                StateInput::Event(Some(event)) => Some(Entry::Event(event.clone())),
+                StateInput::Resp(Some(resp)) => Some(Entry::resp(resp)),
+                StateInput::Verified(Some((_, msg))) => Some(Entry::Verified(msg.clone())),
+                StateInput::Retry(Some(call)) => Some(Entry::Retry(call.clone())),
+                _ => None,
+            });
+            if !self.handle_input(input).await {
+                break;
+            }
+        }
+    }
 
-                evt = event.next() => {
-                    if self.stopped {
-                        break;
-                    }
+    /// Handle an input of the state and return whether to keep running.
+    pub(crate) async fn handle_input(&mut self, input: StateInput<T>) -> bool {
+        match input {
+            StateInput::Raw(raw) => {
+                let (ctx, msg) = raw.expect("Overlord message handler dropped");
 
-                    if !self.consensus_power {
-                        continue;
-                    }
+                if msg.is_rich_status() || matches!(msg, OverlordMsg::Stop) {
+                    let _ = self.verify_sig_tx.unbounded_send((ctx, msg));
+                } else {
+                    match self.height.cmp(&msg.get_height()) {
+                        Ordering::Less => self.verify_future_msg(ctx, msg).await,
+                        Ordering::Equal => {
+                            parallel_verify(
+                                ctx,
+                                msg,
+                                Arc::clone(&self.util),
+                                Arc::clone(&self.verify_queue),
+                                self.authority.clone(),
+                                self.config.sign_domain.clone(),
+                                self.verify_sig_tx.clone(),
+                            )
+                            .await;
+                        }
+                        Ordering::Greater => (),
+                    };
+                }
+            }
 
-                    if let Err(e) = self.handle_event(evt).await{
-                        log::error!("Overlord: state {:?} error", e);
-                    }
+            StateInput::Event(evt) => {
+                if self.stopped {
+                    return false;
                 }
 
-                res = verify_resp.next() => {
-                    if !self.consensus_power {
-                        continue;
-                    }
+                if !self.consensus_power {
+                    return true;
+                }
 
-                    if let Err(e) = self.handle_resp(res) {
-                        log::error!("Overlord: state {:?} error", e);
-                    }
+                if let Err(e) = self.handle_event(evt).await {
+                    log::error!("Overlord: state {:?} error", e);
+                }
+            }
+
+            StateInput::Resp(res) => {
+                if !self.consensus_power {
+                    return true;
                 }
 
-                verified_msg = verify_sig.next() => {
-                    let (ctx, msg) = verified_msg.expect("Overlord message handler dropped");
-                    if let Err(e) = self.handle_msg(ctx.clone(), msg).await {
-                        self.report_error(ctx, e.clone());
-                        log::error!("Overlord: state {:?} error", e);
-                    }
+                if let Err(e) = self.handle_resp(res) {
+                    log::error!("Overlord: state {:?} error", e);
+                }
+            }
+
+            StateInput::Verified(verified_msg) => {
+                let (ctx, msg) = verified_msg.expect("Overlord message handler dropped");
+                if let Err(e) = self.handle_msg(ctx.clone(), msg).await {
+                    log::error!("Overlord: state {:?} error", e);
+                    self.report_error(ctx, e);
+                }
+            }
+
+            StateInput::Retry(call) => {
+                if !self.consensus_power {
+                    return true;
+                }
+
+                if let Err(e) = self.handle_retry(call).await {
+                    log::error!("Overlord: state {:?} error", e);
                 }
             }
         }
+        true
+    }
+
+    /// Write an entry to the recorder if any.
+    fn record<E: FnOnce() -> Option<Entry<T>>>(&self, entry: E) {
+        if let Some(recorder) = self.recorder.as_ref() {
+            if let Some(entry) = entry() {
+                recorder.record(self.clock.now(), entry);
+            }
+        }
+    }
+
+    pub(crate) fn height(&self) -> u64 {
+        self.height
+    }
+
+    pub(crate) fn round(&self) -> u64 {
+        self.round
     }
 
     /// A function to handle message from the network. Public this in the crate to do unit tests.
-    #[tracing_span(kind = "overlord")]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "overlord.handle_msg",
+            skip_all,
+            parent = crate::utils::trace::parent_span(&ctx),
+            fields(height = self.height, round = self.round)
+        )
+    )]
+    #[cfg_attr(feature = "apm", tracing_span(kind = "overlord"))]
     pub(crate) async fn handle_msg(
         &mut self,
         ctx: Context,
         raw: OverlordMsg<T>,
     ) -> ConsensusResult<()> {
         if !self.consensus_power && !raw.is_rich_status() {
+            if self.config.follower_mode {
+                if let Err(e) = self.handle_observed_msg(ctx, raw).await {
+                    log::error!("Overlord: state handle observed message error {:?}", e);
+                }
+            }
             return Ok(());
         }
 
@@ -230,6 +405,10 @@ where
             }
 
             OverlordMsg::RichStatus(rs) => {
+                // The lists returned by the commits that self has not made are got again.
+                if rs.height > self.height {
+                    self.rebuild_schedule(ctx.clone(), rs.height).await;
+                }
                 if let Err(e) = self.goto_new_height(ctx.clone(), rs).await {
                     log::error!("Overlord: state handle rich status error {:?}", e);
                 }
@@ -304,7 +483,7 @@ where
             }
 
             SMREvent::Commit(hash) => {
-                if let Err(e) = self.handle_commit(hash).await {
+                if let Err(e) = self.handle_commit(hash, 1).await {
                     log::error!("Overlord: state handle commit error {:?}", e);
                 }
                 Ok(())
@@ -329,7 +508,7 @@ where
         }
     }
 
-    fn handle_resp(&mut self, msg: Option<VerifyResp>) -> ConsensusResult<()> {
+    fn handle_resp(&mut self, msg: Option<VerifyResp<T>>) -> ConsensusResult<()> {
         let resp = msg.ok_or_else(|| ConsensusError::Other("Event sender dropped".to_string()))?;
         if resp.height != self.height {
             return Ok(());
@@ -337,12 +516,36 @@ where
 
         let block_hash = resp.block_hash.clone();
         log::debug!(
-            "Overlord: state receive a verify response true, height {}, round {}, hash {:?}",
+            "Overlord: state receive a verify response {}, height {}, round {}, hash {:?}",
+            resp.is_pass,
             resp.height,
             resp.round,
             hex_encode(block_hash.clone())
         );
 
+        // The block of a failed check is kept unchecked, so the quorum certificates of it are
+        // not handed to SMR and the round will time out.
+        if resp.is_timeout {
+            self.check_timeouts.insert(block_hash);
+            return Ok(());
+        } else if let Some((signed_proposal, error)) = resp.invalid {
+            // A transient failure says nothing about the proposal, so the proposer is not blamed.
+            if TransientError::is_transient(&error) {
+                self.report_error(
+                    Context::new(),
+                    ConsensusError::CheckBlockErr {
+                        height: resp.height,
+                        round: resp.round,
+                        source: error,
+                    },
+                );
+            } else {
+                self.report_invalid_proposal(signed_proposal, error);
+            }
+            return Ok(());
+        }
+
+        self.check_timeouts.remove(&block_hash);
         self.is_full_transaction
             .insert(block_hash.clone(), resp.is_pass);
 
@@ -382,11 +585,11 @@ where
     /// of the `commit()` interface, or latest status after the synchronization is completed send
     /// by the overlord handler.
     ///
-    /// If the difference between the status height and current's over one, get the last authority
-    /// list of the status height firstly. Then update the height, authority_list and the block
-    /// interval. Since it is possible to have received and cached the current height's proposals,
-    /// votes and quorum certificates before, these should be re-checked as goto new height.
-    /// Finally, trigger SMR to goto new height.
+    /// The authority list of the status is scheduled to take effect after the authority delay, and
+    /// the authority of the new height is taken from the schedule. Then update the height and the
+    /// block interval. Since it is possible to have received and cached the current height's
+    /// proposals, votes and quorum certificates before, these should be re-checked as goto new
+    /// height. Finally, trigger SMR to goto new height.
     async fn goto_new_height(&mut self, _ctx: Context, status: Status) -> ConsensusResult<()> {
         if status.height <= self.height {
             log::warn!(
@@ -401,8 +604,63 @@ where
         self.height = new_height;
         self.round = INIT_ROUND;
 
+        // Update the authority schedule and the authority list of the new height. The list of the
+        // last height is kept to look up past messages.
+        let mut auth_list = status.authority_list.clone();
+        self.authority_schedule.schedule(new_height, &mut auth_list);
+        self.authority = self
+            .authority_schedule
+            .get(new_height)
+            .cloned()
+            .unwrap_or_else(|| self.authority.clone());
+        let is_cache_valid = match self.authority_schedule.get_cached(new_height) {
+            Some(auth) => auth == &self.authority,
+            None => true,
+        };
+        self.authority_schedule.flush(new_height - 1);
+        let unverified_msgs = self.take_unverified_msgs(new_height);
+
         // Check the consensus power.
-        self.consensus_power = status.is_consensus_node(&self.address);
+        self.consensus_power = self.authority.contains(&self.address);
+
+        // Clear outdated proposals and votes.
+        self.proposals.flush(new_height - 1);
+        self.votes.flush(new_height - 1);
+        self.hash_with_block.clear();
+        self.chokes.clear();
+        self.locked = None;
+        self.check_timeouts.clear();
+        for token in self.check_tokens.drain(..) {
+            token.cancel();
+        }
+
+        // Re-handle proposals, votes and quorum certificates that have been cached of the current
+        // height. These have been verified when received, unless the authority list of the current
+        // height differs from the one verified with.
+        let mut cached_msgs = Vec::new();
+        if let Some(proposals) = self.proposals.get_height_proposals(self.height) {
+            cached_msgs.extend(
+                proposals
+                    .into_iter()
+                    .map(|(sp, ctx)| (ctx, OverlordMsg::SignedProposal(sp))),
+            );
+        }
+        if let Some((votes, qcs)) = self.votes.get_height_votes(new_height) {
+            cached_msgs.extend(
+                votes
+                    .into_iter()
+                    .map(|(sv, ctx)| (ctx, OverlordMsg::SignedVote(sv))),
+            );
+            cached_msgs.extend(
+                qcs.into_iter()
+                    .map(|qc| (Context::new(), OverlordMsg::AggregatedVote(qc))),
+            );
+        }
+        if self.consensus_power || self.config.follower_mode {
+            self.re_handle_msgs(cached_msgs, is_cache_valid).await;
+            self.re_handle_msgs(unverified_msgs, false).await;
+        }
+
         if !self.consensus_power {
             log::info!(
                 "Overlord: self does not have consensus power height {}",
@@ -415,32 +673,12 @@ where
 
         self.save_wal(Step::Propose, None).await?;
 
-        // Update height and authority list.
-        self.height_start = Instant::now();
-        let mut auth_list = status.authority_list.clone();
-        self.authority.update(&mut auth_list);
+        self.height_start = self.clock.now();
 
         if let Some(interval) = status.interval {
             self.block_interval = interval;
         }
 
-        // Clear outdated proposals and votes.
-        self.proposals.flush(new_height - 1);
-        self.votes.flush(new_height - 1);
-        self.hash_with_block.clear();
-        self.chokes.clear();
-
-        // Re-check proposals that have been in the proposal collector, of the current height.
-        if let Some(proposals) = self.proposals.get_height_proposals(self.height) {
-            self.re_check_proposals(proposals).await?;
-        }
-
-        // Re-check votes and quorum certificates in the vote collector, of the current height.
-        if let Some((votes, qcs)) = self.votes.get_height_votes(new_height) {
-            self.re_check_votes(votes).await?;
-            self.re_check_qcs(qcs).await?;
-        }
-
         self.state_machine.new_height_status(status.into())?;
         Ok(())
     }
@@ -448,7 +686,8 @@ where
     /// Handle `NewRoundInfo` event from SMR. Firstly, goto new round and check the `XOR`
     /// relationship between the lock round type and the lock proposal type. Secondly, check if self
     /// is a proposer. If is not a proposer, return `Ok(())` and wait for a signed proposal from the
-    /// network. Otherwise, make up a proposal, broadcast it and touch off SMR trigger.
+    /// network. Otherwise, make up a proposal, broadcast it and touch off SMR trigger, unless the
+    /// application declines to propose a new block by `should_propose`.
     async fn handle_new_round(
         &mut self,
         new_round: u64,
@@ -473,6 +712,14 @@ where
             ));
         }
 
+        let lock_hash = lock_proposal.clone().unwrap_or_default();
+        self.update_lock(lock_round, &lock_hash);
+        self.events.publish(OverlordEvent::NewRound {
+            height: self.height,
+            round: self.round,
+            hash: lock_hash,
+        });
+
         self.set_update_from(from_where)?;
         self.save_wal_with_lock_round(Step::Propose, lock_round)
             .await?;
@@ -498,14 +745,81 @@ where
         // certificate form proposal collector and vote collector. Some necessary checks should be
         // done by doing this. These things constitute a Proposal. Then sign it and broadcast it to
         // other nodes.
-        self.is_leader = true;
         let ctx = Context::new();
-        let (block, hash, polc) = if lock_round.is_none() {
-            let (new_block, new_hash) = self
+        let elapsed = self
+            .clock
+            .now()
+            .saturating_duration_since(self.height_start);
+        // The next round starts as the propose step of this round times out, so skipping is allowed
+        // only if the next round still starts in the idle bound.
+        let next_round = elapsed + self.propose_timeout();
+        if lock_round.is_none()
+            && next_round <= Duration::from_millis(self.config.max_idle_interval)
+        {
+            let propose = self
                 .function
-                .get_block(ctx.clone(), self.height)
-                .await
-                .map_err(|err| ConsensusError::Other(format!("get block error {:?}", err)))?;
+                .should_propose(ctx.clone(), self.height, self.round, elapsed)
+                .await;
+            self.record(|| Some(Entry::ShouldPropose(propose)));
+            if !propose {
+                log::info!(
+                    "Overlord: state skip proposing height {}, round {}",
+                    self.height,
+                    self.round
+                );
+                return Ok(());
+            }
+        }
+
+        self.propose(ctx, lock_round, lock_proposal, 1).await
+    }
+
+    /// Make up a proposal of the current round, broadcast it and touch off SMR trigger. Without a
+    /// lock, the block is got by `get_block`, of which the given attempt is made.
+    async fn propose(
+        &mut self,
+        ctx: Context,
+        lock_round: Option<u64>,
+        lock_proposal: Option<Hash>,
+        attempt: u32,
+    ) -> ConsensusResult<()> {
+        self.is_leader = true;
+        let (block, hash, polc) = if lock_round.is_none() {
+            let res = self.function.get_block(ctx.clone(), self.height).await;
+            self.record(|| Some(Entry::GetBlock(recorded(&res))));
+
+            // If the retries are exhausted, give up proposing in this round. The propose timeout
+            // leads all the nodes to prevote nil and go to the next round.
+            let (new_block, new_hash) = match res {
+                Ok(res) => res,
+                Err(source) if self.should_retry(&source, attempt) => {
+                    self.schedule_retry(
+                        RetryCall::GetBlock {
+                            height: self.height,
+                            round: self.round,
+                            attempt: attempt + 1,
+                        },
+                        &source,
+                    );
+                    return Ok(());
+                }
+                Err(source) => {
+                    log::error!(
+                        "Overlord: state get block failed, give up proposing height {}, round {}",
+                        self.height,
+                        self.round
+                    );
+                    self.report_error(
+                        ctx,
+                        ConsensusError::GetBlockErr {
+                            height: self.height,
+                            round: self.round,
+                            source,
+                        },
+                    );
+                    return Ok(());
+                }
+            };
             (new_block, new_hash, None)
         } else {
             let round = lock_round.unwrap();
@@ -546,11 +860,17 @@ where
             hex_encode(hash.clone())
         );
 
+        let signed_proposal = self.sign_proposal(proposal)?;
         self.broadcast(
             Context::new(),
-            OverlordMsg::SignedProposal(self.sign_proposal(proposal)?),
+            OverlordMsg::SignedProposal(signed_proposal.clone()),
         )
         .await;
+        self.events.publish(OverlordEvent::ProposalBroadcast {
+            height: self.height,
+            round: self.round,
+            hash: hash.clone(),
+        });
 
         self.state_machine.trigger(SMRTrigger {
             trigger_type: TriggerType::Proposal,
@@ -562,22 +882,36 @@ where
             wal_info: None,
         })?;
 
-        self.check_block(ctx, hash, block).await;
+        self.check_block(ctx, signed_proposal).await;
         Ok(())
     }
 
     /// This function only handle signed proposals which height and round are equal to current.
     /// Others will be ignored or stored in the proposal collector.
-    #[tracing_span(
-        kind = "overlord",
-        tags = "{
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "overlord.handle_signed_proposal",
+            skip_all,
+            fields(
+                height = signed_proposal.proposal.height,
+                round = signed_proposal.proposal.round
+            )
+        )
+    )]
+    #[cfg_attr(
+        feature = "apm",
+        tracing_span(
+            kind = "overlord",
+            tags = "{
             'height': 'signed_proposal.proposal.height', 
             'round': 'signed_proposal.proposal.round'
         }",
-        logs = "{
+            logs = "{
             'proposal_hash': 'hex_encode(signed_proposal.proposal.block_hash.clone())',
             'proposer': 'hex_encode(signed_proposal.proposal.proposer.clone())'
         }"
+        )
     )]
     async fn handle_signed_proposal(
         &mut self,
@@ -602,7 +936,7 @@ where
             &signed_proposal.proposal.proposer,
         )?;
 
-        self.height_start = Instant::now();
+        self.height_start = self.clock.now();
 
         if self.filter_signed_proposal(
             ctx.clone(),
@@ -614,7 +948,6 @@ where
         }
 
         let proposal = signed_proposal.proposal.clone();
-        let signature = signed_proposal.signature.clone();
 
         // If the signed proposal is with a lock, check the lock round and the QC then trigger it to
         // SMR. Otherwise, touch off SMR directly.
@@ -626,7 +959,11 @@ where
         };
 
         let hash = proposal.block_hash.clone();
-        let block = proposal.content.clone();
+        self.events.publish(OverlordEvent::ProposalReceived {
+            height: proposal_height,
+            round: proposal_round,
+            hash: hash.clone(),
+        });
         self.hash_with_block.insert(hash.clone(), proposal.content);
         self.proposals.insert(
             ctx.clone(),
@@ -653,7 +990,7 @@ where
         })?;
 
         log::debug!("Overlord: state check the whole block");
-        self.check_block(ctx, hash, block).await;
+        self.check_block(ctx, signed_proposal).await;
         Ok(())
     }
 
@@ -680,6 +1017,13 @@ where
 
         self.save_wal_with_lock_round(vote_type.clone().into(), lock_round)
             .await?;
+        self.update_lock(lock_round, &hash);
+        self.events.publish(OverlordEvent::VoteSent {
+            height: self.height,
+            round: self.round,
+            hash: hash.clone(),
+            vote_type: vote_type.clone(),
+        });
 
         if self.is_leader {
             self.votes.insert_vote(
@@ -720,8 +1064,14 @@ where
 
         let signature = self
             .util
-            .sign(self.util.hash(Bytes::from(rlp::encode(&choke.to_hash()))))
-            .map_err(|err| ConsensusError::CryptoErr(format!("sign choke error {:?}", err)))?;
+            .sign(
+                self.util.hash(
+                    self.config
+                        .sign_domain
+                        .choke_payload(choke.height, choke.round),
+                ),
+            )
+            .map_err(|source| self.crypto_err(source))?;
         let signed_choke = SignedChoke {
             signature,
             choke,
@@ -739,11 +1089,17 @@ where
             .await?;
         self.broadcast(Context::new(), OverlordMsg::SignedChoke(signed_choke))
             .await;
+        self.events.publish(OverlordEvent::ChokeSent {
+            height: self.height,
+            round: self.round,
+            hash: Hash::new(),
+        });
         self.check_choke_above_threshold()?;
         Ok(())
     }
 
-    async fn handle_commit(&mut self, hash: Hash) -> ConsensusResult<()> {
+    /// Commit the block of the hash, of which the given attempt of `commit` is made.
+    async fn handle_commit(&mut self, hash: Hash, attempt: u32) -> ConsensusResult<()> {
         log::debug!(
             "Overlord: state receive commit event height {}, round {}, hash {:?}",
             self.height,
@@ -793,15 +1149,55 @@ where
         };
 
         let ctx = Context::new();
-        let status = self
-            .function
-            .commit(ctx.clone(), height, commit)
-            .await
-            .map_err(|err| ConsensusError::Other(format!("commit error {:?}", err)))?;
+        let proof = commit.proof.clone();
+        let res = self.function.commit(ctx.clone(), height, commit).await;
+        self.record(|| Some(Entry::Commit(recorded(&res))));
+
+        // If the retries are exhausted, stay in the commit step and request the application to
+        // synchronize the height by the proof, which goes on with a rich status as the block is
+        // synchronized.
+        let status = match res {
+            Ok(status) => status,
+            Err(source) if self.should_retry(&source, attempt) => {
+                self.schedule_retry(
+                    RetryCall::Commit {
+                        height,
+                        hash,
+                        attempt: attempt + 1,
+                    },
+                    &source,
+                );
+                return Ok(());
+            }
+            Err(source) => {
+                log::error!(
+                    "Overlord: state commit failed, request sync to height {}",
+                    height
+                );
+                self.report_error(
+                    ctx.clone(),
+                    ConsensusError::CommitErr {
+                        height,
+                        round: self.round,
+                        source,
+                    },
+                );
+                self.request_sync(ctx, proof).await;
+                return Ok(());
+            }
+        };
+        self.events.publish(OverlordEvent::Committed {
+            height,
+            round: qc.round,
+            hash,
+        });
 
-        let mut auth_list = status.authority_list.clone();
-        self.authority.update(&mut auth_list);
-        let cost = Instant::now() - self.height_start;
+        let cost = self
+            .clock
+            .now()
+            .saturating_duration_since(self.height_start);
+        self.metrics.observe_height_rounds(height, self.round + 1);
+        self.metrics.observe_commit_latency(height, cost);
 
         log::info!(
             "Overlord: achieve consensus in height {}, costs {} round {:?} time",
@@ -810,10 +1206,12 @@ where
             cost
         );
 
-        if self.next_proposer(status.height, INIT_ROUND)?
+        if self.next_proposer(&status, INIT_ROUND)?
             && cost < Duration::from_millis(self.block_interval)
         {
-            sleep(Duration::from_millis(self.block_interval) - cost).await;
+            self.clock
+                .sleep(Duration::from_millis(self.block_interval) - cost)
+                .await;
         }
 
         self.goto_new_height(ctx, status).await?;
@@ -827,17 +1225,32 @@ where
     /// will be done by the leader. For the higher votes, check the signature and save them in
     /// the vote collector. Whenever the current vote is received, a statistic is made to check
     /// if the sum of the voting weights corresponding to the hash exceeds the threshold.
-    #[tracing_span(
-        kind = "overlord",
-        tags = "{
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "overlord.handle_signed_vote",
+            skip_all,
+            fields(
+                height = signed_vote.vote.height,
+                round = signed_vote.vote.round,
+                vote_type = %signed_vote.vote.vote_type
+            )
+        )
+    )]
+    #[cfg_attr(
+        feature = "apm",
+        tracing_span(
+            kind = "overlord",
+            tags = "{
             'height': 'signed_vote.vote.height', 
             'round': 'signed_vote.vote.round', 
             'vote_type': 'signed_vote.vote.vote_type'
         }",
-        logs = "{
+            logs = "{
             'vote_hash': 'hex_encode(signed_vote.vote.block_hash.clone())',
             'voter': 'hex_encode(signed_vote.voter.clone())'
         }"
+        )
     )]
     async fn handle_signed_vote(
         &mut self,
@@ -861,18 +1274,16 @@ where
             hex_encode(signed_vote.vote.block_hash.clone())
         );
 
+        self.metrics.inc_vote(vote_type.clone());
+
         if self.filter_message(height, round) {
             return Ok(());
         }
 
-        let tmp_type: String = vote_type.to_string();
-
         // All the votes must pass the verification of signature and address before be saved into
         // vote collector.
-        let signature = signed_vote.signature.clone();
         let voter = signed_vote.voter.clone();
-        let vote = signed_vote.vote.clone();
-        self.verify_address(&voter)?;
+        self.verify_address(height, &voter)?;
 
         // Check if the quorum certificate has generated before check whether there is a hash that
         // vote weight is above the threshold. If no hash achieved this, return directly.
@@ -884,6 +1295,11 @@ where
             return Ok(());
         }
 
+        if self.is_future_votes_full(height) {
+            log::debug!("Overlord: state cached future votes are full");
+            return Ok(());
+        }
+
         self.votes.insert_vote(
             ctx.clone(),
             signed_vote.get_hash(),
@@ -913,6 +1329,7 @@ where
         );
 
         self.votes.set_qc(qc.clone());
+        self.publish_qc(&qc);
 
         log::debug!(
             "Overlord: state broadcast a {:?} QC, height {}, round {}, hash {:?}",
@@ -952,28 +1369,43 @@ where
     /// The main process to handle aggregate votes contains four cases.
     ///
     /// 1. The QC is later than current which means the QC's height is higher than current or is
-    /// equal to the current and the round is higher than current. In this cases, check the
-    /// aggregate signature subject to availability, and save it.
+    ///    equal to the current and the round is higher than current. In this cases, check the
+    ///    aggregate signature subject to availability, and save it.
     ///
     /// 2. The QC is equal to the current height and round. In this case, check the aggregate
-    /// signature, then save it, and touch off SMR trigger.
+    ///    signature, then save it, and touch off SMR trigger.
     ///
     /// 3. The QC is equal to the `current height - 1` and the round is higher than the last
-    /// commit round. In this case, check the aggregate signature firstly. If the type of the QC
-    /// is precommit, ignore it. Otherwise, retransmit precommit QC.
+    ///    commit round. In this case, check the aggregate signature firstly. If the type of the
+    ///    QC is precommit, ignore it. Otherwise, retransmit precommit QC.
     ///
     /// 4. Other cases, return `Ok(())` directly.
-    #[tracing_span(
-        kind = "overlord",
-        tags = "{
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "overlord.handle_aggregated_vote",
+            skip_all,
+            fields(
+                height = aggregated_vote.height,
+                round = aggregated_vote.round,
+                qc_type = %aggregated_vote.vote_type
+            )
+        )
+    )]
+    #[cfg_attr(
+        feature = "apm",
+        tracing_span(
+            kind = "overlord",
+            tags = "{
             'height': 'aggregated_vote.height', 
             'round': 'aggregated_vote.round', 
             'qc_type': 'aggregated_vote.vote_type'
         }",
-        logs = "{
+            logs = "{
             'qc_hash': 'hex_encode(aggregated_vote.block_hash.clone())',
             'leader': 'hex_encode(aggregated_vote.leader.clone())'
         }"
+        )
     )]
     async fn handle_aggregated_vote(
         &mut self,
@@ -1010,15 +1442,21 @@ where
             }
 
             Ordering::Greater => {
-                if self.height + FUTURE_HEIGHT_GAP > vote_height && vote_round < FUTURE_ROUND_GAP {
+                self.try_request_sync(ctx.clone(), &aggregated_vote).await;
+
+                if self.height + self.config.future_height_gap <= vote_height
+                    || vote_round >= self.config.future_round_gap
+                {
+                    log::warn!("Overlord: state receive a much higher aggregated vote");
+                } else if self.is_future_votes_full(vote_height) {
+                    log::debug!("Overlord: state cached future votes are full");
+                } else {
                     log::debug!(
                         "Overlord: state receive a future QC, height {}, round {}",
                         vote_height,
                         vote_round,
                     );
                     self.votes.set_qc(aggregated_vote);
-                } else {
-                    log::warn!("Overlord: state receive a much higher aggregated vote");
                 }
                 return Ok(());
             }
@@ -1039,6 +1477,7 @@ where
 
         // Check if the block hash has been verified.
         let qc_hash = aggregated_vote.block_hash.clone();
+        self.publish_qc(&aggregated_vote);
         self.votes.set_qc(aggregated_vote);
 
         if !qc_hash.is_empty() && !self.try_get_full_txs(&qc_hash) {
@@ -1104,6 +1543,7 @@ where
         } else if let Some(block_hash) = self.counting_vote(vote_type.clone())? {
             let qc = self.generate_qc(block_hash.clone(), vote_type.clone())?;
             self.votes.set_qc(qc.clone());
+            self.publish_qc(&qc);
 
             log::debug!(
                 "Overlord: state broadcast a {:?} QC, height {}, round {}, hash {:?}",
@@ -1169,13 +1609,24 @@ where
         Ok(None)
     }
 
-    #[tracing_span(
-        kind = "overlord",
-        tags = "{
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "overlord.handle_signed_choke",
+            skip_all,
+            fields(height = signed_choke.choke.height, round = signed_choke.choke.round)
+        )
+    )]
+    #[cfg_attr(
+        feature = "apm",
+        tracing_span(
+            kind = "overlord",
+            tags = "{
             'height': 'signed_choke.choke.height',
             'round': 'signed_choke.choke.round'
         }",
-        logs = "{'choke_from': 'hex_encode(signed_choke.address.clone())'}"
+            logs = "{'choke_from': 'hex_encode(signed_choke.address.clone())'}"
+        )
     )]
     async fn handle_signed_choke(
         &mut self,
@@ -1185,6 +1636,7 @@ where
         let choke = signed_choke.choke.clone();
         let choke_height = choke.height;
         let choke_round = choke.round;
+        self.metrics.inc_choke(choke_height, choke_round);
 
         // filter choke height ne self.height
         if choke_height != self.height {
@@ -1291,63 +1743,414 @@ where
         Ok(qc)
     }
 
-    async fn re_check_proposals(
+    /// Handle the messages as a follower which does not have consensus power. A follower only
+    /// collects signed proposals and precommit quorum certificates, and tries to assemble a commit
+    /// of the current height from them. Messages of a higher height are cached until state goto
+    /// that height.
+    async fn handle_observed_msg(
         &mut self,
-        proposals_and_ctxs: Vec<(SignedProposal<T>, Context)>,
+        ctx: Context,
+        raw: OverlordMsg<T>,
     ) -> ConsensusResult<()> {
-        log::debug!("Overlord: state re-check future signed proposals");
+        let height = raw.get_height();
+        if height < self.height {
+            return Ok(());
+        } else if self.height + self.config.future_height_gap < height {
+            if let OverlordMsg::AggregatedVote(qc) = raw {
+                self.try_request_sync(ctx, &qc).await;
+            }
+            return Ok(());
+        }
 
-        for item in proposals_and_ctxs.into_iter() {
-            parallel_verify(
-                item.1,
-                OverlordMsg::SignedProposal(item.0),
-                Arc::clone(&self.util),
-                self.authority.clone(),
-                self.verify_sig_tx.clone(),
-            )
-            .await;
+        let (qc, signed_proposal) = match raw {
+            OverlordMsg::SignedProposal(sp) => {
+                let round = sp.proposal.round;
+                self.verify_proposer(height, round, &sp.proposal.proposer)?;
+                if self.is_future_proposals_full(height) {
+                    return Ok(());
+                }
+                self.proposals
+                    .insert(ctx.clone(), height, round, sp.clone())?;
+
+                let qc = self.votes.get_qc_by_hash(
+                    height,
+                    sp.proposal.block_hash.clone(),
+                    VoteType::Precommit,
+                );
+                (qc, Some(sp))
+            }
+
+            OverlordMsg::AggregatedVote(qc) => {
+                if qc.is_prevote_qc() || qc.block_hash.is_empty() {
+                    return Ok(());
+                }
+                if height > self.height {
+                    self.try_request_sync(ctx.clone(), &qc).await;
+                }
+                if self.is_future_votes_full(height) {
+                    return Ok(());
+                }
+                self.votes.set_qc(qc.clone());
+
+                let sp = self
+                    .proposals
+                    .get(height, qc.round)
+                    .ok()
+                    .map(|(sp, _)| sp)
+                    .filter(|sp| sp.proposal.block_hash == qc.block_hash);
+                (Some(qc), sp)
+            }
+
+            _ => return Ok(()),
+        };
+
+        if height != self.height || self.last_observed == Some(height) {
+            return Ok(());
         }
 
+        if let (Some(qc), Some(sp)) = (qc, signed_proposal) {
+            self.observe_commit(ctx, qc, sp.proposal.content).await?;
+        }
         Ok(())
     }
 
-    async fn re_check_votes(
+    /// Hand the observed commit of the current height to `on_observed_commit`. If a rich status is
+    /// returned, goto the new height.
+    async fn observe_commit(
         &mut self,
-        votes_and_ctxs: Vec<(SignedVote, Context)>,
+        ctx: Context,
+        qc: AggregatedVote,
+        content: T,
     ) -> ConsensusResult<()> {
-        log::debug!("Overlord: state re-check future signed votes");
+        let height = self.height;
+        let round = qc.round;
+        log::info!(
+            "Overlord: state observe a commit height {}, round {}, hash {:?}",
+            height,
+            round,
+            hex_encode(qc.block_hash.clone())
+        );
 
-        for item in votes_and_ctxs.into_iter() {
-            parallel_verify(
-                item.1,
-                OverlordMsg::SignedVote(item.0),
-                Arc::clone(&self.util),
-                self.authority.clone(),
-                self.verify_sig_tx.clone(),
-            )
+        let commit = Commit {
+            height,
+            content,
+            proof: Proof {
+                height,
+                round: qc.round,
+                block_hash: qc.block_hash,
+                signature: qc.signature,
+            },
+        };
+
+        let res = self
+            .function
+            .on_observed_commit(ctx.clone(), height, commit)
             .await;
-        }
+        self.record(|| Some(Entry::ObservedCommit(recorded(&res))));
+        let status = res.map_err(|source| ConsensusError::ObservedCommitErr {
+            height,
+            round,
+            source,
+        })?;
+        self.last_observed = Some(height);
 
+        if let Some(status) = status {
+            self.goto_new_height(ctx, status).await?;
+        }
         Ok(())
     }
 
-    async fn re_check_qcs(&mut self, qcs: Vec<AggregatedVote>) -> ConsensusResult<()> {
-        log::debug!("Overlord: state re-check future QCs");
+    /// Re-handle the cached messages of the current height. The messages that are not verified
+    /// yet will be verified with the current authority list firstly.
+    async fn re_handle_msgs(&self, msgs: Vec<(Context, OverlordMsg<T>)>, verified: bool) {
+        log::debug!("Overlord: state re-handle {} cached messages", msgs.len());
+
+        for (ctx, msg) in msgs.into_iter() {
+            if verified {
+                let _ = self.verify_sig_tx.unbounded_send((ctx, msg));
+            } else {
+                parallel_verify(
+                    ctx,
+                    msg,
+                    Arc::clone(&self.util),
+                    Arc::clone(&self.verify_queue),
+                    self.authority.clone(),
+                    self.config.sign_domain.clone(),
+                    self.verify_sig_tx.clone(),
+                )
+                .await;
+            }
+        }
+    }
+
+    /// Verify a message of a higher height with the authority list of that height. If the list is
+    /// unknown, save the message until state goto that height.
+    async fn verify_future_msg(&mut self, ctx: Context, msg: OverlordMsg<T>) {
+        let height = msg.get_height();
+        let is_far = self.height + self.config.future_height_gap < height;
+        if is_far && !(self.is_sync_needed(height) && is_commit_qc(&msg)) {
+            log::debug!(
+                "Overlord: state receive a future message height {}, self height {}",
+                height,
+                self.height
+            );
+            return;
+        }
 
-        for item in qcs.into_iter() {
+        if let Some(auth) = self.get_authority(ctx.clone(), height).await {
             parallel_verify(
-                Context::new(),
-                OverlordMsg::AggregatedVote(item),
+                ctx,
+                msg,
                 Arc::clone(&self.util),
-                self.authority.clone(),
+                Arc::clone(&self.verify_queue),
+                auth,
+                self.config.sign_domain.clone(),
                 self.verify_sig_tx.clone(),
             )
             .await;
+        } else if !is_far && !self.is_unverified_msgs_full() {
+            self.unverified_msgs
+                .entry(height)
+                .or_default()
+                .push((ctx, msg));
+        }
+    }
+
+    /// Request the consensus to synchronize to the height of the given quorum certificate, if it
+    /// is a precommit quorum certificate of a block which means that the height has been committed
+    /// by others. The target is saved only if the request succeeds, so a failed request is sent
+    /// again on the next quorum certificate.
+    async fn try_request_sync(&mut self, ctx: Context, qc: &AggregatedVote) {
+        if qc.is_prevote_qc() || qc.block_hash.is_empty() || !self.is_sync_needed(qc.height) {
+            return;
+        }
+
+        log::info!(
+            "Overlord: state request sync to height {}, self height {}",
+            qc.height,
+            self.height
+        );
+
+        let proof = Proof {
+            height: qc.height,
+            round: qc.round,
+            block_hash: qc.block_hash.clone(),
+            signature: qc.signature.clone(),
+        };
+        self.request_sync(ctx, proof).await;
+    }
+
+    /// Request the consensus to synchronize to the height of the proof.
+    async fn request_sync(&mut self, ctx: Context, proof: Proof) {
+        let (height, round) = (proof.height, proof.round);
+        match self.function.request_sync(ctx.clone(), height, proof).await {
+            Ok(()) => self.sync_target = Some((height, self.clock.now())),
+            Err(source) => {
+                log::error!("Overlord: state request sync error {:?}", source);
+                self.sync_target = None;
+                self.report_error(
+                    ctx,
+                    ConsensusError::RequestSyncErr {
+                        height,
+                        round,
+                        source,
+                    },
+                );
+            }
         }
+    }
 
+    /// Whether to make another attempt of an application call that fails with the error.
+    fn should_retry(&self, error: &ErrorSource, attempt: u32) -> bool {
+        TransientError::is_transient(error) && attempt < self.config.retry.max_attempts
+    }
+
+    /// Send the call to the state to make it again after the backoff of the retry policy.
+    fn schedule_retry(&self, call: RetryCall, error: &ErrorSource) {
+        let attempt = match &call {
+            RetryCall::GetBlock { attempt, .. } | RetryCall::Commit { attempt, .. } => *attempt,
+        };
+        let backoff = self.config.retry.backoff(attempt - 1);
+        log::warn!(
+            "Overlord: state {:?} error {:?}, retry after {:?}",
+            call,
+            error,
+            backoff
+        );
+        let sleep = self.clock.sleep(backoff);
+        let retry_tx = self.retry_tx.clone();
+        self.spawner.spawn(Box::pin(async move {
+            sleep.await;
+            let _ = retry_tx.unbounded_send(call);
+        }));
+    }
+
+    /// Make a call again if it is still needed, which is when self stays in the height and round
+    /// of the call to propose or has not gone to the next height after the failed commit.
+    async fn handle_retry(&mut self, call: Option<RetryCall>) -> ConsensusResult<()> {
+        match call.ok_or_else(|| ConsensusError::Other("Retry sender dropped".to_string()))? {
+            RetryCall::GetBlock {
+                height,
+                round,
+                attempt,
+            } => {
+                if height == self.height && round == self.round && self.is_leader {
+                    self.propose(Context::new(), None, None, attempt).await?;
+                }
+            }
+            RetryCall::Commit {
+                height,
+                hash,
+                attempt,
+            } => {
+                if height == self.height {
+                    self.handle_commit(hash, attempt).await?;
+                }
+            }
+        }
         Ok(())
     }
 
+    /// Whether to request sync to the given height. While self has not reached the last requested
+    /// height, a request is only needed for a higher height or when the last request does not
+    /// complete in `SYNC_RETRY_INTERVALS` height intervals.
+    fn is_sync_needed(&self, height: u64) -> bool {
+        if height <= self.height {
+            return false;
+        }
+        match self.sync_target {
+            Some((target, requested_at)) if target >= self.height => {
+                let timeout = Duration::from_millis(self.block_interval * SYNC_RETRY_INTERVALS);
+                height > target
+                    || self.clock.now().saturating_duration_since(requested_at) >= timeout
+            }
+            _ => true,
+        }
+    }
+
+    /// Whether the cached proposals of the future heights reach the limit.
+    fn is_future_proposals_full(&self, height: u64) -> bool {
+        height > self.height
+            && self.proposals.count_above(self.height) >= self.config.max_future_proposals
+    }
+
+    /// Whether the cached votes and quorum certificates of the future heights reach the limit.
+    fn is_future_votes_full(&self, height: u64) -> bool {
+        height > self.height && self.votes.count_above(self.height) >= self.config.max_future_votes
+    }
+
+    /// Whether the cached unverified messages reach the limit.
+    fn is_unverified_msgs_full(&self) -> bool {
+        let count = self.unverified_msgs.values().map(Vec::len).sum::<usize>();
+        if count >= self.config.max_unverified_msgs {
+            log::debug!("Overlord: state cached unverified messages are full");
+            return true;
+        }
+        false
+    }
+
+    /// Take the unverified messages of the given height, and remove the ones lower than it.
+    fn take_unverified_msgs(&mut self, height: u64) -> Vec<(Context, OverlordMsg<T>)> {
+        let higher = self.unverified_msgs.split_off(&(height + 1));
+        let msgs = self.unverified_msgs.remove(&height).unwrap_or_default();
+        self.unverified_msgs = higher;
+        msgs
+    }
+
+    /// Get the authority lists from the given height to the height where the list of the status of
+    /// the given height takes effect, which are returned by the commits before the given height.
+    /// These are only known if self has made the commits, so they are got by `get_authority_list`
+    /// when self starts from a wal or goes to a height by a rich status. A list that fails to be
+    /// got is left to the earlier one.
+    async fn rebuild_schedule(&mut self, ctx: Context, height: u64) {
+        for h in height..height + self.config.authority_delay.saturating_sub(1) {
+            let res = self.function.get_authority_list(ctx.clone(), h).await;
+            self.record(|| Some(Entry::AuthorityList(recorded(&res))));
+            match res {
+                Ok(mut authority_list) if !authority_list.is_empty() => {
+                    self.authority_schedule.insert(h, &mut authority_list);
+                }
+                Ok(_) => log::warn!("Overlord: state get an empty authority list height {}", h),
+                Err(source) => self.report_error(
+                    ctx.clone(),
+                    ConsensusError::GetAuthorityListErr {
+                        height: h,
+                        round: self.round,
+                        source,
+                    },
+                ),
+            }
+        }
+    }
+
+    /// Get the authority manage of the given height. If the authority list of that height is not
+    /// determined or cached, get it by `get_authority_list` and cache it.
+    async fn get_authority(&mut self, ctx: Context, height: u64) -> Option<AuthorityManage> {
+        if let Some(auth) = self.authority_schedule.lookup(height, self.height) {
+            return Some(auth.clone());
+        }
+        // A failed lookup is not repeated in a height interval.
+        let now = self.clock.now();
+        if let Some(at) = self.authority_schedule.last_miss(height) {
+            if now.saturating_duration_since(at) < Duration::from_millis(self.block_interval) {
+                return None;
+            }
+        }
+
+        let res = self.function.get_authority_list(ctx.clone(), height).await;
+        self.record(|| Some(Entry::AuthorityList(recorded(&res))));
+        match res {
+            Ok(mut authority_list) if !authority_list.is_empty() => {
+                // Only the lists of the heights that may be handled later are cached.
+                if height > self.height + self.config.future_height_gap {
+                    let mut auth = AuthorityManage::new();
+                    auth.update(&mut authority_list);
+                    return Some(auth);
+                }
+                self.authority_schedule.cache(height, &mut authority_list);
+                self.authority_schedule.get_cached(height).cloned()
+            }
+            Ok(_) => {
+                log::warn!(
+                    "Overlord: state get an empty authority list height {}",
+                    height
+                );
+                self.authority_schedule.miss(height, now);
+                None
+            }
+            Err(source) => {
+                log::warn!(
+                    "Overlord: state get authority list height {} error {:?}",
+                    height,
+                    source
+                );
+                self.authority_schedule.miss(height, now);
+                self.report_error(
+                    ctx,
+                    ConsensusError::GetAuthorityListErr {
+                        height,
+                        round: self.round,
+                        source,
+                    },
+                );
+                None
+            }
+        }
+    }
+
+    /// The propose timeout of the current round, as the timer sets it.
+    fn propose_timeout(&self) -> Duration {
+        let timeout = self.timeouts.lock().get_propose_timeout();
+        round_timeout(timeout, self.round, self.config.max_timeout_coef)
+    }
+
+    /// The deadline of checking a block in the current round, which is the prevote and the
+    /// precommit timeouts that the timer sets in the round.
+    fn check_block_timeout(&self) -> Duration {
+        let timeout = self.timeouts.lock().get_check_block_timeout();
+        round_timeout(timeout, self.round, self.config.max_timeout_coef)
+    }
+
     /// If self is not the proposer of the height and round, set leader address as the proposer
     /// address.
     fn is_proposer(&mut self) -> ConsensusResult<bool> {
@@ -1375,8 +2178,18 @@ where
         Ok(false)
     }
 
-    fn next_proposer(&self, height: u64, round: u64) -> ConsensusResult<bool> {
-        let proposer = self.authority.get_proposer(height, round)?;
+    /// Check whether self is the proposer of the height of the status returned by the commit,
+    /// before the authority list of the status is scheduled as self goes to the height.
+    fn next_proposer(&self, status: &Status, round: u64) -> ConsensusResult<bool> {
+        let height = status.height;
+        let mut auth_list = status.authority_list.clone();
+        let proposer = self
+            .authority_schedule
+            .peek(height, height, &mut auth_list)
+            .ok_or_else(|| {
+                ConsensusError::Other(format!("Unknown authority list height {}", height))
+            })?
+            .get_proposer(height, round)?;
         Ok(self.address == proposer)
     }
 
@@ -1384,8 +2197,11 @@ where
         log::debug!("Overlord: state sign a proposal");
         let signature = self
             .util
-            .sign(self.util.hash(Bytes::from(rlp::encode(&proposal))))
-            .map_err(|err| ConsensusError::CryptoErr(format!("{:?}", err)))?;
+            .sign(
+                self.util
+                    .hash(self.config.sign_domain.proposal_payload(&proposal)),
+            )
+            .map_err(|source| self.crypto_err(source))?;
 
         Ok(SignedProposal {
             signature,
@@ -1397,8 +2213,8 @@ where
         log::debug!("Overlord: state sign a vote");
         let signature = self
             .util
-            .sign(self.util.hash(Bytes::from(rlp::encode(&vote))))
-            .map_err(|err| ConsensusError::CryptoErr(format!("{:?}", err)))?;
+            .sign(self.util.hash(self.config.sign_domain.vote_payload(&vote)))
+            .map_err(|source| self.crypto_err(source))?;
 
         Ok(SignedVote {
             voter: self.address.clone(),
@@ -1427,27 +2243,40 @@ where
         let signature = self
             .util
             .aggregate_signatures(signatures, voters)
-            .map_err(|err| ConsensusError::CryptoErr(format!("{:?}", err)))?;
+            .map_err(|source| self.crypto_err(source))?;
         Ok(signature)
     }
 
+    /// Verify the proposer with the authority list of the given height.
     fn verify_proposer(&self, height: u64, round: u64, address: &Address) -> ConsensusResult<()> {
         log::debug!("Overlord: state verify a proposer");
-        self.verify_address(address)?;
-        if address != &self.authority.get_proposer(height, round)? {
+        self.verify_address(height, address)?;
+        if address != &self.authority_at(height)?.get_proposer(height, round)? {
             return Err(ConsensusError::ProposalErr("Invalid proposer".to_string()));
         }
         Ok(())
     }
 
-    /// Check whether the given address is included in the corresponding authority list.
-    fn verify_address(&self, address: &Address) -> ConsensusResult<()> {
-        if !self.authority.contains(address) {
+    /// Check whether the given address is included in the authority list of the given height.
+    fn verify_address(&self, height: u64, address: &Address) -> ConsensusResult<()> {
+        if !self.authority_at(height)?.contains(address) {
             return Err(ConsensusError::InvalidAddress);
         }
         Ok(())
     }
 
+    /// Get the authority manage of the given height which has been determined or cached.
+    fn authority_at(&self, height: u64) -> ConsensusResult<&AuthorityManage> {
+        if height == self.height {
+            return Ok(&self.authority);
+        }
+        self.authority_schedule
+            .lookup(height, self.height)
+            .ok_or_else(|| {
+                ConsensusError::Other(format!("Unknown authority list height {}", height))
+            })
+    }
+
     async fn transmit(&self, ctx: Context, msg: OverlordMsg<T>) {
         log::debug!(
             "Overlord: state transmit a message to leader height {}, round {}",
@@ -1455,16 +2284,24 @@ where
             self.round
         );
 
-        let _ = self
+        if let Err(source) = self
             .function
-            .transmit_to_relayer(ctx, self.leader_address.clone(), msg.clone())
+            .transmit_to_relayer(ctx.clone(), self.leader_address.clone(), msg)
             .await
-            .map_err(|err| {
-                log::error!(
-                    "Overlord: state transmit message to leader failed {:?}",
-                    err
-                );
-            });
+        {
+            log::error!(
+                "Overlord: state transmit message to leader failed {:?}",
+                source
+            );
+            self.report_error(
+                ctx,
+                ConsensusError::TransmitErr {
+                    height: self.height,
+                    round: self.round,
+                    source,
+                },
+            );
+        }
     }
 
     async fn broadcast(&self, ctx: Context, msg: OverlordMsg<T>) {
@@ -1474,19 +2311,84 @@ where
             self.round
         );
 
-        let _ = self
-            .function
-            .broadcast_to_other(ctx, msg.clone())
-            .await
-            .map_err(|err| {
-                log::error!("Overlord: state broadcast message failed {:?}", err);
-            });
+        if let Err(source) = self.function.broadcast_to_other(ctx.clone(), msg).await {
+            log::error!("Overlord: state broadcast message failed {:?}", source);
+            self.report_error(
+                ctx,
+                ConsensusError::BroadcastErr {
+                    height: self.height,
+                    round: self.round,
+                    source,
+                },
+            );
+        }
     }
 
     fn report_error(&self, ctx: Context, err: ConsensusError) {
         self.function.report_error(ctx, err);
     }
 
+    fn crypto_err(&self, source: ErrorSource) -> ConsensusError {
+        ConsensusError::CryptoErr {
+            height: self.height,
+            round: self.round,
+            source,
+        }
+    }
+
+    /// Publish the lock changes reported by SMR. Relocking on a higher round is published as a
+    /// new lock.
+    fn update_lock(&mut self, lock_round: Option<u64>, hash: &Hash) {
+        match (self.locked.take(), lock_round) {
+            (None, Some(round)) => self.lock_on(round, hash),
+            (Some((last_round, _)), Some(round)) if round != last_round => {
+                self.lock_on(round, hash)
+            }
+            (Some((_, last_hash)), None) => {
+                self.events.publish(OverlordEvent::Unlocked {
+                    height: self.height,
+                    round: self.round,
+                    hash: last_hash,
+                });
+            }
+            (last, _) => self.locked = last,
+        }
+    }
+
+    fn lock_on(&mut self, round: u64, hash: &Hash) {
+        self.locked = Some((round, hash.clone()));
+        self.events.publish(OverlordEvent::Locked {
+            height: self.height,
+            round,
+            hash: hash.clone(),
+        });
+    }
+
+    fn publish_qc(&self, qc: &AggregatedVote) {
+        self.events.publish(OverlordEvent::QCFormed {
+            height: qc.height,
+            round: qc.round,
+            hash: qc.block_hash.clone(),
+            vote_type: qc.vote_type.clone(),
+        });
+    }
+
+    fn report_invalid_proposal(&mut self, signed_proposal: SignedProposal<T>, error: ErrorSource) {
+        let count = self
+            .invalid_proposals
+            .entry(signed_proposal.proposal.proposer.clone())
+            .or_default();
+        *count += 1;
+
+        let invalid = InvalidProposal {
+            signed_proposal,
+            error,
+            count: *count,
+        };
+        self.function
+            .report_invalid_proposal(Context::new(), invalid);
+    }
+
     fn report_view_change(&self, round: u64, reason: ViewChangeReason) {
         self.function
             .report_view_change(Context::new(), self.height, round, reason)
@@ -1518,11 +2420,11 @@ where
 
         // Check block failed case.
         let proposal = proposal.unwrap().0;
-        if self
-            .is_full_transaction
-            .get(&proposal.proposal.block_hash)
-            .is_none()
-        {
+        let hash = &proposal.proposal.block_hash;
+        if !self.is_full_transaction.contains_key(hash) {
+            if self.check_timeouts.contains(hash) {
+                return ViewChangeReason::CheckBlockTimeout;
+            }
             return ViewChangeReason::CheckBlockNotPass;
         }
 
@@ -1590,24 +2492,91 @@ where
         Ok(())
     }
 
-    #[tracing_span(
-        kind = "overlord",
-        tags = "{'height': 'self.height', 'round': 'self.round'}"
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "overlord.check_block",
+            skip_all,
+            fields(height = self.height, round = self.round)
+        )
+    )]
+    #[cfg_attr(
+        feature = "apm",
+        tracing_span(
+            kind = "overlord",
+            tags = "{'height': 'self.height', 'round': 'self.round'}"
+        )
     )]
-    async fn check_block(&mut self, ctx: Context, hash: Hash, block: T) {
+    async fn check_block(&mut self, ctx: Context, signed_proposal: SignedProposal<T>) {
+        let hash = signed_proposal.proposal.block_hash.clone();
+        let block = signed_proposal.proposal.content.clone();
         let height = self.height;
         let round = self.round;
         let function = Arc::clone(&self.function);
         let resp_tx = self.resp_tx.clone();
+        let policy = self.config.retry.clone();
+        let clock = Arc::clone(&self.clock);
+        let deadline = self.check_block_timeout();
+        let token = CancelToken::new();
+        let ctx = token.attach(ctx);
+        self.check_tokens.push(token.clone());
+
+        let task = async move {
+            let check_hash = hash.clone();
+            let check = retry(&policy, clock.as_ref(), "check block", move || {
+                check_current_block(
+                    ctx.clone(),
+                    Arc::clone(&function),
+                    height,
+                    round,
+                    check_hash.clone(),
+                    block.clone(),
+                )
+            });
+            let res = tokio::select! {
+                biased;
+                res = check => Some(res),
+                _ = clock.sleep(deadline) => None,
+                // The height has been committed, drop the stale check.
+                _ = token.cancelled() => return,
+            };
 
-        tokio::spawn(async move {
-            if let Err(e) =
-                check_current_block(ctx, function, height, round, hash.clone(), block, resp_tx)
-                    .await
-            {
-                log::error!("Overlord: state check block failed: {:?}", e);
+            let mut resp = VerifyResp {
+                height,
+                round,
+                block_hash: hash.clone(),
+                is_pass: false,
+                is_timeout: false,
+                invalid: None,
+            };
+            match res {
+                Some(Ok(())) => resp.is_pass = true,
+                Some(Err(source)) => {
+                    log::error!(
+                        "Overlord: state check block failed, height {}, round {}, \
+                         hash {:?}, error {:?}",
+                        height,
+                        round,
+                        hex_encode(hash),
+                        source
+                    );
+                    resp.invalid = Some((signed_proposal, source));
+                }
+                None => {
+                    // Cancel the token to tell the application to stop the check.
+                    log::warn!(
+                        "Overlord: state check block timeout, height {}, round {}, hash {:?}",
+                        height,
+                        round,
+                        hex_encode(hash)
+                    );
+                    token.cancel();
+                    resp.is_timeout = true;
+                }
             }
-        });
+            let _ = resp_tx.unbounded_send(resp);
+        };
+        self.spawner.spawn(task.boxed());
     }
 
     async fn save_wal(&mut self, step: Step, lock: Option<WalLock<T>>) -> ConsensusResult<()> {
@@ -1619,17 +2588,19 @@ where
             lock,
         };
 
-        self.wal
-            .save(Bytes::from(rlp::encode(&wal_info)))
-            .await
-            .map_err(|e| {
-                log::error!("Overlord: state save wal error {:?}", e);
-                ConsensusError::SaveWalErr {
-                    height: self.height,
-                    round: self.round,
-                    step: step.to_string(),
-                }
-            })?;
+        let start = self.clock.now();
+        let res = self.wal.save(Bytes::from(rlp::encode(&wal_info))).await;
+        let cost = self.clock.now().saturating_duration_since(start);
+        self.metrics.observe_wal_save(cost);
+        res.map_err(|source| {
+            log::error!("Overlord: state save wal error {:?}", source);
+            ConsensusError::SaveWalErr {
+                height: self.height,
+                round: self.round,
+                step: step.to_string(),
+                source,
+            }
+        })?;
         Ok(())
     }
 
@@ -1683,7 +2654,7 @@ where
         })
     }
 
-    async fn start_with_wal(&mut self) -> ConsensusResult<()> {
+    pub(crate) async fn start_with_wal(&mut self) -> ConsensusResult<()> {
         if !self.consensus_power {
             return Ok(());
         }
@@ -1703,6 +2674,10 @@ where
         // recover basic state
         self.height = wal_info.height;
         self.round = wal_info.round;
+        self.rebuild_schedule(Context::new(), self.height).await;
+        if let Some(auth) = self.authority_schedule.get(self.height) {
+            self.authority = auth.clone();
+        }
         self.is_leader = self.is_proposer()?;
         self.update_from_where = wal_info.from.clone();
 
@@ -1715,11 +2690,12 @@ where
         }
 
         if wal_info.step == Step::Commit {
-            let qc = wal_info
-                .lock
-                .clone()
-                .ok_or_else(|| ConsensusError::LoadWalErr("no lock in commit step".to_string()))?;
-            return self.handle_commit(qc.lock_votes.block_hash.clone()).await;
+            let qc = wal_info.lock.clone().ok_or_else(|| {
+                ConsensusError::CorrectnessErr("no lock in commit step".to_string())
+            })?;
+            return self
+                .handle_commit(qc.lock_votes.block_hash.clone(), 1)
+                .await;
         }
 
         if wal_info.step == Step::Brake {
@@ -1740,18 +2716,18 @@ where
     }
 
     async fn load_wal(&mut self) -> ConsensusResult<Option<WalInfo<T>>> {
-        let tmp = self
-            .wal
-            .load()
-            .await
-            .map_err(|e| ConsensusError::LoadWalErr(e.to_string()))?;
+        let res = self.wal.load().await;
+        self.record(|| Some(Entry::WalLoad(recorded(&res))));
+        let tmp = res.map_err(|source| ConsensusError::LoadWalErr { source })?;
 
         if tmp.is_none() {
             return Ok(None);
         }
 
-        let info: WalInfo<T> = rlp::decode(tmp.unwrap().as_ref())
-            .map_err(|e| ConsensusError::LoadWalErr(e.to_string()))?;
+        let info: WalInfo<T> =
+            rlp::decode(tmp.unwrap().as_ref()).map_err(|e| ConsensusError::LoadWalErr {
+                source: Box::new(e),
+            })?;
         Ok(Some(info))
     }
 
@@ -1801,8 +2777,9 @@ where
 
     /// Filter the proposals that do not need to be handed.
     /// 1. Outdated proposals
-    /// 2. A much higher height which is larger than the FUTURE_HEIGHT_GAP
-    /// 3. A much higher round which is larger than the FUTURE_ROUND_GAP
+    /// 2. A much higher height which is larger than the future height gap
+    /// 3. A much higher round which is larger than the future round gap
+    /// 4. A future height proposal when the cached future proposals are full
     fn filter_signed_proposal(
         &mut self,
         ctx: Context,
@@ -1823,6 +2800,10 @@ where
                 height,
                 round,
             );
+            if self.is_future_proposals_full(height) {
+                log::debug!("Overlord: state cached future proposals are full");
+                return Ok(true);
+            }
             self.proposals
                 .insert(ctx, height, round, signed_proposal.clone())?;
             return Ok(true);
@@ -1838,15 +2819,15 @@ where
                 self.height
             );
             return true;
-        } else if self.height + FUTURE_HEIGHT_GAP < height {
+        } else if self.height + self.config.future_height_gap < height {
             log::debug!(
                 "Overlord: state receive a future message height {}, self height {}",
                 height,
                 self.height
             );
             return true;
-        } else if (height == self.height && self.round + FUTURE_ROUND_GAP < round)
-            || (height > self.height && round > FUTURE_ROUND_GAP)
+        } else if (height == self.height && self.round + self.config.future_round_gap < round)
+            || (height > self.height && round > self.config.future_round_gap)
         {
             log::debug!("Overlord: state receive a much higher round message");
             return true;
@@ -1856,7 +2837,17 @@ where
     }
 }
 
-#[tracing_span(kind = "overlord", tags = "{'height': 'height', 'round': 'round'}")]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "overlord.check_current_block",
+        skip(ctx, function, hash, block)
+    )
+)]
+#[cfg_attr(
+    feature = "apm",
+    tracing_span(kind = "overlord", tags = "{'height': 'height', 'round': 'round'}")
+)]
 async fn check_current_block<U: Consensus<T>, T: Codec>(
     ctx: Context,
     function: Arc<U>,
@@ -1864,21 +2855,54 @@ async fn check_current_block<U: Consensus<T>, T: Codec>(
     round: u64,
     hash: Hash,
     block: T,
-    tx: UnboundedSender<VerifyResp>,
-) -> ConsensusResult<()> {
-    function
-        .check_block(ctx, height, hash.clone(), block)
-        .await
-        .map_err(|err| ConsensusError::Other(format!("check {} block error {:?}", height, err)))?;
-
-    log::debug!("Overlord: state check block {}", true);
-    tx.unbounded_send(VerifyResp {
+) -> Result<(), ErrorSource> {
+    function.check_block(ctx, height, hash, block).await?;
+
+    log::debug!(
+        "Overlord: state check block pass, height {}, round {}",
         height,
-        round,
-        block_hash: hash,
-        is_pass: true,
-    })
-    .map_err(|e| ConsensusError::ChannelErr(e.to_string()))
+        round
+    );
+    Ok(())
+}
+
+/// An input of the state.
+pub(crate) enum StateInput<T: Codec> {
+    Raw(Option<(Context, OverlordMsg<T>)>),
+    Event(Option<SMREvent>),
+    Resp(Option<VerifyResp<T>>),
+    Verified(Option<(Context, OverlordMsg<T>)>),
+    Retry(Option<RetryCall>),
+}
+
+/// An application call to make again after the backoff of the retry policy, since its last
+/// attempt fails with a transient error. The backoff is slept out of the state, which keeps
+/// handling the other inputs in the meantime.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum RetryCall {
+    /// Get a block to propose in the round.
+    GetBlock {
+        height: u64,
+        round: u64,
+        attempt: u32,
+    },
+    /// Commit the block of the hash.
+    Commit {
+        height: u64,
+        hash: Hash,
+        attempt: u32,
+    },
+}
+
+/// Poll a stream of the state inputs. A terminated stream is skipped.
+fn poll_input<S: FusedStream + Unpin>(
+    stream: &mut S,
+    cx: &mut TaskContext<'_>,
+) -> Poll<Option<S::Item>> {
+    if stream.is_terminated() {
+        return Poll::Pending;
+    }
+    stream.poll_next_unpin(cx)
 }
 
 fn mock_init_qc() -> AggregatedVote {
@@ -1896,3 +2920,300 @@ fn mock_init_qc() -> AggregatedVote {
         leader: Address::default(),
     }
 }
+
+fn is_commit_qc<T: Codec>(msg: &OverlordMsg<T>) -> bool {
+    if let OverlordMsg::AggregatedVote(qc) = msg {
+        return !qc.is_prevote_qc() && !qc.block_hash.is_empty();
+    }
+    false
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+
+    use crate::error::{ConsensusError, ErrorSource, TransientError};
+    use crate::sim::{sim_address, SimError, SimWal};
+    use crate::smr::smr_types::Step;
+    use crate::state::harness::Harness;
+    use crate::types::{Node, OverlordMsg, UpdateFrom, VoteType};
+    use crate::wal::WalInfo;
+    use crate::{get_leader, OverlordConfig, RetryPolicy, Wal};
+
+    use super::{mock_init_qc, SYNC_RETRY_INTERVALS};
+
+    fn send_commit_qc(node: &Harness, height: u64) {
+        let qc = node.gen_qc(3, VoteType::Precommit, height, 0, Bytes::from("block"));
+        node.send(OverlordMsg::AggregatedVote(qc));
+    }
+
+    fn commit_heights(node: &Harness) -> Vec<u64> {
+        node.app.commits.lock().iter().map(|(h, _)| *h).collect()
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_request_sync() {
+        let config = OverlordConfig::default();
+        let retry = Duration::from_millis(config.interval * SYNC_RETRY_INTERVALS);
+        let node = Harness::start(0, 4, config, SimWal::default());
+        node.run_timers(0).await;
+
+        send_commit_qc(&node, 7);
+        node.run_timers(0).await;
+        assert_eq!(*node.app.syncs.lock(), vec![7]);
+
+        // The request is not sent again until it times out or a higher height is committed.
+        send_commit_qc(&node, 7);
+        node.run_timers(0).await;
+        assert_eq!(*node.app.syncs.lock(), vec![7]);
+        send_commit_qc(&node, 8);
+        node.run_timers(0).await;
+        assert_eq!(*node.app.syncs.lock(), vec![7, 8]);
+
+        node.clock.advance(retry);
+        send_commit_qc(&node, 8);
+        node.run_timers(0).await;
+        assert_eq!(*node.app.syncs.lock(), vec![7, 8, 8]);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_request_sync_failed() {
+        let node = Harness::start(0, 4, OverlordConfig::default(), SimWal::default());
+        *node.app.fail_sync.lock() = true;
+        node.run_timers(0).await;
+
+        // A failed request is sent again on the next quorum certificate.
+        send_commit_qc(&node, 7);
+        node.run_timers(0).await;
+        send_commit_qc(&node, 7);
+        node.run_timers(0).await;
+        assert_eq!(*node.app.syncs.lock(), vec![7, 7]);
+
+        *node.app.fail_sync.lock() = false;
+        send_commit_qc(&node, 7);
+        node.run_timers(0).await;
+        send_commit_qc(&node, 7);
+        node.run_timers(0).await;
+        assert_eq!(*node.app.syncs.lock(), vec![7, 7, 7]);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_retry_transient_commit() {
+        let node = Harness::start_among(1, 0, 4, OverlordConfig::default(), SimWal::default());
+        for _ in 0..2 {
+            let e = TransientError::boxed(SimError::InvalidBlock(5));
+            node.app.commit_errors.lock().push_back(e);
+        }
+        node.run_timers(0).await;
+        assert_eq!(commit_heights(&node), vec![5]);
+
+        // The state keeps handling messages during the backoff.
+        send_commit_qc(&node, 7);
+        node.run_timers(0).await;
+        assert_eq!(*node.app.syncs.lock(), vec![7]);
+
+        node.run_timers(1).await;
+        assert_eq!(commit_heights(&node), vec![5, 5]);
+        node.run_timers(1).await;
+        assert_eq!(commit_heights(&node)[..3], [5, 5, 5]);
+        assert_eq!(*node.app.syncs.lock(), vec![7]);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_failed_commit_requests_sync() {
+        let node = Harness::start_among(1, 0, 4, OverlordConfig::default(), SimWal::default());
+        let e = Box::new(SimError::InvalidBlock(5));
+        node.app.commit_errors.lock().push_back(e);
+        node.run_timers(0).await;
+
+        // An error which is not transient is not retried.
+        assert_eq!(commit_heights(&node), vec![5]);
+        assert_eq!(*node.app.syncs.lock(), vec![5]);
+        node.run_timers(3).await;
+        assert_eq!(commit_heights(&node), vec![5]);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_authority_lookup_failed() {
+        let config = OverlordConfig::default();
+        let interval = Duration::from_millis(config.interval);
+        let node = Harness::start(0, 4, config, SimWal::default());
+        *node.app.fail_authority.lock() = true;
+        node.run_timers(0).await;
+        node.app.authority_calls.lock().clear();
+
+        // A failed lookup is not repeated for every message of the height.
+        send_commit_qc(&node, 6);
+        send_commit_qc(&node, 6);
+        node.run_timers(0).await;
+        assert_eq!(*node.app.authority_calls.lock(), vec![6]);
+
+        node.clock.advance(interval);
+        send_commit_qc(&node, 6);
+        node.run_timers(0).await;
+        assert_eq!(*node.app.authority_calls.lock(), vec![6, 6]);
+    }
+
+    /// Start a node with an authority delay of three, of which the authority list changes at
+    /// height 6 by a commit that the node has not made, and check that a commit QC of height 6
+    /// signed by the changed list is verified.
+    async fn check_rebuilt_schedule(wal: SimWal) {
+        let config = OverlordConfig {
+            authority_delay: 3,
+            ..Default::default()
+        };
+        let node = Harness::start(0, 4, config, wal);
+        // Replace the first node, so that the voters of the QC differ from the original list.
+        let mut changed = node.authority_list[1..].to_vec();
+        changed.push(Node::new(sim_address(4)));
+        changed.sort();
+        node.app.authority_lists.lock().insert(6, changed.clone());
+        node.run_timers(0).await;
+
+        let qc = node.gen_qc_of(&changed, 3, VoteType::Precommit, 6, 0, Bytes::from("block"));
+        node.send(OverlordMsg::AggregatedVote(qc));
+        node.run_timers(0).await;
+        assert_eq!(*node.app.syncs.lock(), vec![6]);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_rebuild_schedule_by_status() {
+        check_rebuilt_schedule(SimWal::default()).await;
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_rebuild_schedule_from_wal() {
+        let wal = SimWal::default();
+        let info = WalInfo::<crate::types::RawBlock> {
+            height: 5,
+            round: 0,
+            step: Step::Propose,
+            lock: None,
+            from: UpdateFrom::PrecommitQC(mock_init_qc()),
+        };
+        wal.save(info.to_bytes()).await.unwrap();
+        check_rebuilt_schedule(wal).await;
+    }
+
+    /// Start a follower which is not in the authority list of four nodes.
+    fn start_follower() -> Harness {
+        let config = OverlordConfig {
+            follower_mode: true,
+            ..Default::default()
+        };
+        Harness::start_among(4, 4, 4, config, SimWal::default())
+    }
+
+    fn send_observed(node: &Harness, height: u64, proposal: &str, qc: &str, voters: usize) {
+        let sp = node.gen_proposal(height, 0, Bytes::from(proposal.to_string()));
+        node.send(OverlordMsg::SignedProposal(sp));
+        let qc = node.gen_qc(
+            voters,
+            VoteType::Precommit,
+            height,
+            0,
+            Bytes::from(qc.to_string()),
+        );
+        node.send(OverlordMsg::AggregatedVote(qc));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_follower_observe_commit() {
+        let node = start_follower();
+        node.run_timers(0).await;
+
+        send_observed(&node, 5, "block", "block", 3);
+        node.run_timers(0).await;
+        assert_eq!(*node.app.observed.lock(), vec![(5, Bytes::from("block"))]);
+
+        // Without a rich status, the follower stays in the height.
+        send_observed(&node, 6, "block", "block", 3);
+        node.run_timers(0).await;
+        assert_eq!(node.app.observed.lock().len(), 1);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_follower_ignore_mismatched_qc() {
+        let node = start_follower();
+        node.run_timers(0).await;
+
+        send_observed(&node, 5, "block", "other", 3);
+        node.run_timers(0).await;
+        assert!(node.app.observed.lock().is_empty());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_follower_ignore_minority_qc() {
+        let node = start_follower();
+        node.run_timers(0).await;
+
+        send_observed(&node, 5, "block", "block", 2);
+        node.run_timers(0).await;
+        assert!(node.app.observed.lock().is_empty());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_follower_advance_by_status() {
+        let node = start_follower();
+        *node.app.follow_status.lock() = true;
+        node.run_timers(0).await;
+
+        send_observed(&node, 5, "block", "block", 3);
+        node.run_timers(0).await;
+        send_observed(&node, 6, "next", "next", 3);
+        node.run_timers(0).await;
+        assert_eq!(
+            *node.app.observed.lock(),
+            vec![(5, Bytes::from("block")), (6, Bytes::from("next"))]
+        );
+    }
+
+    /// Start a node which is not the leader of height 5, round 0, and send it a proposal of the
+    /// round after the block check fails with the given error.
+    async fn check_failed_proposal(error: ErrorSource) -> Harness {
+        let mut authority_list = (0..4)
+            .map(|i| Node::new(sim_address(i)))
+            .collect::<Vec<_>>();
+        authority_list.sort();
+        let leader = get_leader(5, 0, authority_list);
+        let index = (0..4).find(|i| sim_address(*i) != leader).unwrap();
+        let config = OverlordConfig {
+            retry: RetryPolicy {
+                max_attempts: 1,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let node = Harness::start(index, 4, config, SimWal::default());
+        node.run_timers(0).await;
+
+        node.app.check_errors.lock().push_back(error);
+        let sp = node.gen_proposal(5, 0, Bytes::from("block"));
+        node.send(OverlordMsg::SignedProposal(sp));
+        node.run_timers(0).await;
+        node
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_check_block_rejected() {
+        let node = check_failed_proposal(Box::new(SimError::InvalidBlock(5))).await;
+        assert_eq!(*node.app.invalid.lock(), vec![Bytes::from("block")]);
+        assert!(node.app.errors.lock().is_empty());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_check_block_transient_failure() {
+        let node = check_failed_proposal(TransientError::boxed(SimError::InvalidBlock(5))).await;
+        // The proposer is not blamed for a failure of the application.
+        assert!(node.app.invalid.lock().is_empty());
+        let code = ConsensusError::CheckBlockErr {
+            height: 5,
+            round: 0,
+            source: Box::new(SimError::InvalidBlock(5)),
+        }
+        .code();
+        assert_eq!(*node.app.errors.lock(), vec![code]);
+    }
+}
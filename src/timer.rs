@@ -1,32 +1,43 @@
 use std::task::{Context, Poll};
-use std::time::Duration;
-use std::{future::Future, pin::Pin};
+use std::time::Instant;
+use std::{future::Future, pin::Pin, sync::Arc};
 
 use derive_more::Display;
 use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use futures::future::{BoxFuture, FutureExt};
 use futures::stream::{Stream, StreamExt};
-use futures::SinkExt;
-use tokio::time::{sleep, Sleep};
+use parking_lot::Mutex;
 
-use crate::smr::smr_types::{SMREvent, SMRTrigger, TriggerSource, TriggerType};
+use crate::record::Recorder;
+use crate::smr::smr_types::{SMREvent, SMRTrigger, Step, TriggerSource, TriggerType};
 use crate::smr::{Event, SMRHandler};
-use crate::DurationConfig;
-use crate::{error::ConsensusError, ConsensusResult, INIT_HEIGHT, INIT_ROUND};
-use crate::{types::Hash, utils::timer_config::TimerConfig};
-
-const MAX_TIMEOUT_COEF: u32 = 5;
-
-/// Overlord timer used futures timer which is powered by a timer heap. When monitor a SMR event,
-/// timer will get timeout interval from timer config, then set a delay. When the timeout expires,
+use crate::types::Hash;
+use crate::utils::timer_config::{round_timeout, TimerConfig};
+use crate::{error::ConsensusError, Clock, ConsensusResult, Metrics, OverlordConfig, Spawner};
+use crate::{INIT_HEIGHT, INIT_ROUND};
+
+/// Overlord timer used the sleeps of the clock. When monitor a SMR event, timer will get timeout
+/// interval from timer config, then set a delay. When the timeout expires,
+/// timer will touch off a SMR trigger. Timer also reports the time spent in each step to the
+/// metrics, which is measured from the SMR events. In the adaptive timing mode, the step durations
+/// also tune the step timeouts. The timer config is shared with the state, which derives the
+/// deadline of checking a block from it. The firings of the timeouts are written to the `recorder`
+/// if any.
 #[derive(Debug)]
 pub struct Timer {
-    config: TimerConfig,
+    config: Arc<Mutex<TimerConfig>>,
+    max_timeout_coef: u32,
     event: Event,
     sender: UnboundedSender<SMREvent>,
     notify: UnboundedReceiver<SMREvent>,
     state_machine: SMRHandler,
     height: u64,
     round: u64,
+    step_start: Option<(Step, Instant)>,
+    metrics: Arc<dyn Metrics>,
+    clock: Arc<dyn Clock>,
+    spawner: Arc<dyn Spawner>,
+    recorder: Option<Arc<Recorder>>,
 }
 
 ///
@@ -40,9 +51,10 @@ impl Stream for Timer {
 
                 Poll::Ready(event) => {
                     if event.is_none() {
-                        return Poll::Ready(Some(ConsensusError::TimerErr(
-                            "Channel dropped".to_string(),
-                        )));
+                        // The SMR is gone without a stop event, such as when the state stops
+                        // first. Stop as well rather than reporting the channel on every poll.
+                        log::error!("Overlord: timer stop as the SMR event channel is dropped");
+                        return Poll::Ready(None);
                     }
 
                     let event = event.unwrap();
@@ -84,35 +96,50 @@ impl Timer {
     pub fn new(
         event: Event,
         state_machine: SMRHandler,
-        interval: u64,
-        config: Option<DurationConfig>,
+        config: &OverlordConfig,
+        metrics: Arc<dyn Metrics>,
+        clock: Arc<dyn Clock>,
+        spawner: Arc<dyn Spawner>,
+        recorder: Option<Arc<Recorder>>,
     ) -> Self {
         let (tx, rx) = unbounded();
-        let mut timer_config = TimerConfig::new(interval);
-        if let Some(tmp) = config {
-            timer_config.update(tmp);
-        }
-
         Timer {
-            config: timer_config,
+            config: Arc::new(Mutex::new(TimerConfig::from_config(config))),
+            max_timeout_coef: config.max_timeout_coef,
             height: INIT_HEIGHT,
             round: INIT_ROUND,
             sender: tx,
             notify: rx,
             event,
             state_machine,
+            step_start: None,
+            metrics,
+            clock,
+            spawner,
+            recorder,
         }
     }
 
+    /// Get the timer config which is tuned and updated by the timer.
+    pub fn timer_config(&self) -> Arc<Mutex<TimerConfig>> {
+        Arc::clone(&self.config)
+    }
+
     pub fn run(mut self) {
-        tokio::spawn(async move {
-            while let Some(err) = self.next().await {
-                log::error!("Overlord: timer error {:?}", err);
+        let spawner = Arc::clone(&self.spawner);
+        spawner.spawn(
+            async move {
+                while let Some(err) = self.next().await {
+                    log::error!("Overlord: timer error {:?}", err);
+                }
             }
-        });
+            .boxed(),
+        );
     }
 
     fn set_timer(&mut self, event: SMREvent) -> ConsensusResult<()> {
+        self.observe_step(&event);
+
         let mut is_brake_timer = false;
         match event.clone() {
             SMREvent::NewRoundInfo {
@@ -128,10 +155,10 @@ impl Timer {
                 self.round = round;
 
                 if let Some(interval) = new_interval {
-                    self.config.set_interval(interval);
+                    self.config.lock().set_interval(interval);
                 }
                 if let Some(config) = new_config {
-                    self.config.update(config);
+                    self.config.lock().update(config);
                 }
             }
             SMREvent::Brake { .. } => is_brake_timer = true,
@@ -139,24 +166,43 @@ impl Timer {
             _ => (),
         };
 
-        let mut interval = self.config.get_timeout(event.clone())?;
+        let mut interval = self.config.lock().get_timeout(event.clone())?;
         if !is_brake_timer {
-            let mut coef = self.round as u32;
-            if coef > MAX_TIMEOUT_COEF {
-                coef = MAX_TIMEOUT_COEF;
-            }
-            interval *= 2u32.pow(coef);
+            interval = round_timeout(interval, self.round, self.max_timeout_coef);
         }
 
         log::debug!("Overlord: timer set {} timer", event);
-        let smr_timer = TimeoutInfo::new(interval, event, self.sender.clone());
+        let smr_timer = TimeoutInfo::new(self.clock.sleep(interval), event, self.sender.clone());
 
-        tokio::spawn(async move {
-            smr_timer.await;
-        });
+        self.spawner.spawn(smr_timer.boxed());
         Ok(())
     }
 
+    /// Report the time spent in the last step when SMR goes to another step. The brake events
+    /// thrown repeatedly in the same brake step do not end the step.
+    fn observe_step(&mut self, event: &SMREvent) {
+        let step = match event {
+            SMREvent::NewRoundInfo { .. } => Step::Propose,
+            SMREvent::PrevoteVote { .. } => Step::Prevote,
+            SMREvent::PrecommitVote { .. } => Step::Precommit,
+            SMREvent::Brake { .. } => Step::Brake,
+            SMREvent::Commit(_) => Step::Commit,
+            _ => return,
+        };
+
+        if let Some((last_step, start)) = self.step_start.take() {
+            if last_step == Step::Brake && step == Step::Brake {
+                self.step_start = Some((last_step, start));
+                return;
+            }
+            let duration = self.clock.now().saturating_duration_since(start);
+            self.metrics
+                .observe_step_duration(last_step.clone(), duration);
+            self.config.lock().observe(last_step, duration);
+        }
+        self.step_start = Some((step, self.clock.now()));
+    }
+
     #[rustfmt::skip]
     fn trigger(&mut self, event: SMREvent) -> ConsensusResult<()> {
         let (trigger_type, round, height) = match event {
@@ -196,6 +242,9 @@ impl Timer {
         };
 
         log::debug!("Overlord: timer {:?} time out", event);
+        if let Some(recorder) = self.recorder.as_ref() {
+            recorder.record_timeout(self.clock.now(), event);
+        }
 
         self.state_machine.trigger(SMRTrigger {
             source: TriggerSource::Timer,
@@ -209,12 +258,12 @@ impl Timer {
     }
 }
 
-/// Timeout info which is a future consists of a `futures-timer Delay`, timeout info and a sender.
+/// Timeout info which is a future consists of a sleep of the clock, timeout info and a sender.
 /// When the timeout expires, future will send timeout info by sender.
-#[derive(Debug, Display)]
+#[derive(Display)]
 #[display(fmt = "{:?}", info)]
 struct TimeoutInfo {
-    timeout: Sleep,
+    timeout: BoxFuture<'static, ()>,
     info: SMREvent,
     sender: UnboundedSender<SMREvent>,
 }
@@ -222,22 +271,11 @@ struct TimeoutInfo {
 impl Future for TimeoutInfo {
     type Output = ();
 
-    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
-        let msg = self.info.clone();
-        let mut tx = self.sender.clone();
-
-        // Safety: we just poll it and didn't move it.
-        let fut = unsafe {
-            let this = self.get_unchecked_mut();
-            Pin::new_unchecked(&mut this.timeout)
-        };
-
-        match fut.poll(cx) {
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        match self.timeout.poll_unpin(cx) {
             Poll::Pending => Poll::Pending,
             Poll::Ready(_) => {
-                tokio::spawn(async move {
-                    let _ = tx.send(msg).await;
-                });
+                let _ = self.sender.unbounded_send(self.info.clone());
                 Poll::Ready(())
             }
         }
@@ -245,9 +283,13 @@ impl Future for TimeoutInfo {
 }
 
 impl TimeoutInfo {
-    fn new(interval: Duration, event: SMREvent, tx: UnboundedSender<SMREvent>) -> Self {
+    fn new(
+        timeout: BoxFuture<'static, ()>,
+        event: SMREvent,
+        tx: UnboundedSender<SMREvent>,
+    ) -> Self {
         TimeoutInfo {
-            timeout: sleep(interval),
+            timeout,
             info: event,
             sender: tx,
         }
@@ -256,12 +298,28 @@ impl TimeoutInfo {
 
 #[cfg(test)]
 mod test {
+    use std::sync::Arc;
+    use std::time::Duration;
+
     use futures::channel::mpsc::unbounded;
     use futures::stream::StreamExt;
+    use parking_lot::Mutex;
 
-    use crate::smr::smr_types::{FromWhere, SMREvent, SMRTrigger, TriggerSource, TriggerType};
+    use crate::smr::smr_types::{
+        FromWhere, SMREvent, SMRTrigger, Step, TriggerSource, TriggerType,
+    };
     use crate::smr::{Event, SMRHandler};
-    use crate::{timer::Timer, types::Hash};
+    use crate::{timer::Timer, types::Hash, ManualClock, Metrics, NoopMetrics};
+    use crate::{OverlordConfig, SystemClock, TokioSpawner};
+
+    #[derive(Debug, Default)]
+    struct StepMetrics(Mutex<Vec<Step>>);
+
+    impl Metrics for StepMetrics {
+        fn observe_step_duration(&self, step: Step, _duration: Duration) {
+            self.0.lock().push(step);
+        }
+    }
 
     async fn test_timer_trigger(input: SMREvent, output: SMRTrigger) {
         let (trigger_tx, mut trigger_rx) = unbounded();
@@ -269,7 +327,10 @@ mod test {
         let mut timer = Timer::new(
             Event::new(event_rx),
             SMRHandler::new(trigger_tx),
-            3000,
+            &OverlordConfig::default(),
+            Arc::new(NoopMetrics),
+            Arc::new(SystemClock),
+            Arc::new(TokioSpawner::new()),
             None,
         );
         event_tx.unbounded_send(input).unwrap();
@@ -289,6 +350,23 @@ mod test {
         }
     }
 
+    #[tokio::test]
+    async fn test_event_dropped() {
+        let (trigger_tx, _trigger_rx) = unbounded();
+        let (event_tx, event_rx) = unbounded::<SMREvent>();
+        let mut timer = Timer::new(
+            Event::new(event_rx),
+            SMRHandler::new(trigger_tx),
+            &OverlordConfig::default(),
+            Arc::new(NoopMetrics),
+            Arc::new(SystemClock),
+            Arc::new(TokioSpawner::new()),
+            None,
+        );
+        drop(event_tx);
+        assert!(timer.next().await.is_none());
+    }
+
     fn gen_output(trigger_type: TriggerType, round: u64, height: u64) -> SMRTrigger {
         SMRTrigger {
             source: TriggerSource::Timer,
@@ -343,6 +421,50 @@ mod test {
         .await;
     }
 
+    #[tokio::test]
+    async fn test_manual_clock() {
+        let (trigger_tx, mut trigger_rx) = unbounded();
+        let (event_tx, event_rx) = unbounded();
+        let clock = Arc::new(ManualClock::new());
+        let timer = Timer::new(
+            Event::new(event_rx),
+            SMRHandler::new(trigger_tx),
+            &OverlordConfig::default(),
+            Arc::new(NoopMetrics),
+            Arc::<ManualClock>::clone(&clock),
+            Arc::new(TokioSpawner::new()),
+            None,
+        );
+        timer.run();
+
+        event_tx
+            .unbounded_send(SMREvent::NewRoundInfo {
+                height: 1,
+                round: 0,
+                lock_round: None,
+                lock_proposal: None,
+                new_interval: None,
+                new_config: None,
+                from_where: FromWhere::PrecommitQC(0),
+            })
+            .unwrap();
+        while clock.pending() == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        // The default propose timeout is 7.2s.
+        clock.advance(Duration::from_millis(7100));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(trigger_rx.try_recv().is_err());
+
+        clock.advance(Duration::from_millis(100));
+        let trigger = tokio::time::timeout(Duration::from_secs(1), trigger_rx.next())
+            .await
+            .unwrap();
+        assert_eq!(trigger, Some(gen_output(TriggerType::Proposal, 0, 1)));
+        event_tx.unbounded_send(SMREvent::Stop).unwrap();
+    }
+
     #[tokio::test]
     async fn test_order() {
         let (trigger_tx, mut trigger_rx) = unbounded();
@@ -350,7 +472,10 @@ mod test {
         let mut timer = Timer::new(
             Event::new(event_rx),
             SMRHandler::new(trigger_tx),
-            3000,
+            &OverlordConfig::default(),
+            Arc::new(NoopMetrics),
+            Arc::new(SystemClock),
+            Arc::new(TokioSpawner::new()),
             None,
         );
 
@@ -410,4 +535,68 @@ mod test {
             }
         }
     }
+
+    #[tokio::test]
+    async fn test_step_metrics() {
+        let (trigger_tx, _trigger_rx) = unbounded();
+        let (_event_tx, event_rx) = unbounded();
+        let metrics = Arc::new(StepMetrics::default());
+        let mut timer = Timer::new(
+            Event::new(event_rx),
+            SMRHandler::new(trigger_tx),
+            &OverlordConfig::default(),
+            Arc::<StepMetrics>::clone(&metrics),
+            Arc::new(SystemClock),
+            Arc::new(TokioSpawner::new()),
+            None,
+        );
+
+        let new_round_event = |round| SMREvent::NewRoundInfo {
+            height: 1,
+            round,
+            lock_round: None,
+            lock_proposal: None,
+            new_interval: None,
+            new_config: None,
+            from_where: FromWhere::PrecommitQC(0),
+        };
+        let brake_event = SMREvent::Brake {
+            height: 1,
+            round: 0,
+            lock_round: None,
+        };
+
+        timer.set_timer(new_round_event(0)).unwrap();
+        timer
+            .set_timer(SMREvent::PrevoteVote {
+                height: 1,
+                round: 0,
+                block_hash: Hash::new(),
+                lock_round: None,
+            })
+            .unwrap();
+        timer
+            .set_timer(SMREvent::PrecommitVote {
+                height: 1,
+                round: 0,
+                block_hash: Hash::new(),
+                lock_round: None,
+            })
+            .unwrap();
+        timer.set_timer(brake_event.clone()).unwrap();
+        timer.set_timer(brake_event).unwrap();
+        timer.set_timer(new_round_event(1)).unwrap();
+        timer.set_timer(SMREvent::Commit(Hash::new())).unwrap();
+
+        assert_eq!(
+            *metrics.0.lock(),
+            vec![
+                Step::Propose,
+                Step::Prevote,
+                Step::Precommit,
+                Step::Brake,
+                Step::Propose
+            ]
+        );
+    }
 }
@@ -1,14 +1,17 @@
 use std::cmp::{Ord, Ordering, PartialOrd};
 use std::convert::TryFrom;
+use std::error::Error;
 
 use bytes::Bytes;
 use derive_more::Display;
 use serde::{Deserialize, Serialize};
 
-use crate::error::ConsensusError;
-use crate::smr::smr_types::{SMRStatus, Step, TriggerType};
+use crate::error::{ConsensusError, ErrorSource};
+use crate::smr::smr_types::{SMRStatus, TriggerType};
 use crate::{Codec, DurationConfig};
 
+pub use crate::smr::smr_types::Step;
+
 /// Address type.
 pub type Address = Bytes;
 /// Hash type.
@@ -142,6 +145,10 @@ pub enum ViewChangeReason {
     #[display(fmt = "Check the block not pass")]
     CheckBlockNotPass,
 
+    /// The check of the block does not finish before the deadline.
+    #[display(fmt = "Check the block timeout")]
+    CheckBlockTimeout,
+
     ///
     #[display(fmt = "Update from a higher round prevote QC from {} to {}", _0, _1)]
     UpdateFromHigherPrevoteQC(u64, u64),
@@ -163,6 +170,185 @@ pub enum ViewChangeReason {
     Others,
 }
 
+/// The events of the consensus process published to the subscribers of an overlord handler. Each
+/// event carries the height, the round and the block hash. The hash is empty if the event is not
+/// about a block.
+#[derive(Clone, Debug, Display, PartialEq, Eq)]
+pub enum OverlordEvent {
+    /// Goto a new round. The hash is the locked proposal hash if self is locked.
+    #[display(fmt = "New round height {}, round {}", height, round)]
+    NewRound {
+        /// The height.
+        height: u64,
+        /// The round.
+        round: u64,
+        /// The block hash.
+        hash: Hash,
+    },
+
+    /// Receive a signed proposal of the current height and round from the network.
+    #[display(fmt = "Proposal received height {}, round {}", height, round)]
+    ProposalReceived {
+        /// The height.
+        height: u64,
+        /// The round.
+        round: u64,
+        /// The block hash.
+        hash: Hash,
+    },
+
+    /// Broadcast a signed proposal as the leader.
+    #[display(fmt = "Proposal broadcast height {}, round {}", height, round)]
+    ProposalBroadcast {
+        /// The height.
+        height: u64,
+        /// The round.
+        round: u64,
+        /// The block hash.
+        hash: Hash,
+    },
+
+    /// Send a signed vote. The hash is empty if self votes for nil.
+    #[display(fmt = "{:?} vote sent height {}, round {}", vote_type, height, round)]
+    VoteSent {
+        /// The height.
+        height: u64,
+        /// The round.
+        round: u64,
+        /// The block hash.
+        hash: Hash,
+        /// The vote type.
+        vote_type: VoteType,
+    },
+
+    /// A quorum certificate of the current height is aggregated by self or received from the
+    /// leader.
+    #[display(fmt = "{:?} QC formed height {}, round {}", vote_type, height, round)]
+    QCFormed {
+        /// The height.
+        height: u64,
+        /// The round.
+        round: u64,
+        /// The block hash.
+        hash: Hash,
+        /// The vote type.
+        vote_type: VoteType,
+    },
+
+    /// Broadcast a signed choke. The hash is empty.
+    #[display(fmt = "Choke sent height {}, round {}", height, round)]
+    ChokeSent {
+        /// The height.
+        height: u64,
+        /// The round.
+        round: u64,
+        /// The block hash.
+        hash: Hash,
+    },
+
+    /// Lock a proposal by a prevote quorum certificate. The round is the lock round.
+    #[display(fmt = "Locked height {}, round {}", height, round)]
+    Locked {
+        /// The height.
+        height: u64,
+        /// The round.
+        round: u64,
+        /// The block hash.
+        hash: Hash,
+    },
+
+    /// Release the lock. The hash is the hash of the released proposal.
+    #[display(fmt = "Unlocked height {}, round {}", height, round)]
+    Unlocked {
+        /// The height.
+        height: u64,
+        /// The round.
+        round: u64,
+        /// The block hash.
+        hash: Hash,
+    },
+
+    /// Commit a block.
+    #[display(fmt = "Committed height {}, round {}", height, round)]
+    Committed {
+        /// The height.
+        height: u64,
+        /// The round.
+        round: u64,
+        /// The block hash.
+        hash: Hash,
+    },
+}
+
+impl OverlordEvent {
+    /// Get the height of the event.
+    pub fn get_height(&self) -> u64 {
+        self.fields().0
+    }
+
+    /// Get the round of the event.
+    pub fn get_round(&self) -> u64 {
+        self.fields().1
+    }
+
+    /// Get the block hash of the event.
+    pub fn get_hash(&self) -> Hash {
+        self.fields().2.clone()
+    }
+
+    fn fields(&self) -> (u64, u64, &Hash) {
+        match self {
+            OverlordEvent::NewRound {
+                height,
+                round,
+                hash,
+            }
+            | OverlordEvent::ProposalReceived {
+                height,
+                round,
+                hash,
+            }
+            | OverlordEvent::ProposalBroadcast {
+                height,
+                round,
+                hash,
+            }
+            | OverlordEvent::VoteSent {
+                height,
+                round,
+                hash,
+                ..
+            }
+            | OverlordEvent::QCFormed {
+                height,
+                round,
+                hash,
+                ..
+            }
+            | OverlordEvent::ChokeSent {
+                height,
+                round,
+                hash,
+            }
+            | OverlordEvent::Locked {
+                height,
+                round,
+                hash,
+            }
+            | OverlordEvent::Unlocked {
+                height,
+                round,
+                hash,
+            }
+            | OverlordEvent::Committed {
+                height,
+                round,
+                hash,
+            } => (*height, *round, hash),
+        }
+    }
+}
+
 /// A signed proposal.
 #[derive(Clone, Debug, Display, PartialEq, Eq)]
 #[display(fmt = "Signed Proposal {:?}", proposal)]
@@ -173,6 +359,19 @@ pub struct SignedProposal<T: Codec> {
     pub proposal: Proposal<T>,
 }
 
+/// A signed proposal of which the block does not pass the check of the application. The signed
+/// proposal is the evidence of the proposer, and `count` is the number of the invalid proposals
+/// received from the proposer since overlord started.
+#[derive(Debug)]
+pub struct InvalidProposal<T: Codec> {
+    /// The invalid signed proposal.
+    pub signed_proposal: SignedProposal<T>,
+    /// The error returned by `check_block`.
+    pub error: ErrorSource,
+    /// The number of the invalid proposals from the proposer.
+    pub count: u64,
+}
+
 /// A proposal
 #[derive(Clone, Debug, Display, PartialEq, Eq)]
 #[display(fmt = "Proposal height {}, round {}", height, round)]
@@ -368,14 +567,6 @@ impl From<Status> for SMRStatus {
     }
 }
 
-impl Status {
-    pub(crate) fn is_consensus_node(&self, address: &Address) -> bool {
-        self.authority_list
-            .iter()
-            .any(|node| node.address == address)
-    }
-}
-
 /// A node info.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct Node {
@@ -424,8 +615,8 @@ impl Node {
 }
 
 /// A verify response.
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub(crate) struct VerifyResp {
+#[derive(Debug)]
+pub(crate) struct VerifyResp<T: Codec> {
     /// The height of the verified block.
     pub(crate) height: u64,
     /// The round of the verified block.
@@ -434,6 +625,10 @@ pub(crate) struct VerifyResp {
     pub(crate) block_hash: Hash,
     /// The block is pass or not.
     pub(crate) is_pass: bool,
+    /// The check does not finish before the deadline.
+    pub(crate) is_timeout: bool,
+    /// The proposal and the error of the check if the block does not pass.
+    pub(crate) invalid: Option<(SignedProposal<T>, ErrorSource)>,
 }
 
 /// An aggregated choke.
@@ -487,50 +682,23 @@ pub struct Choke {
     pub from: UpdateFrom,
 }
 
-impl Choke {
-    pub(crate) fn to_hash(&self) -> HashChoke {
-        HashChoke {
-            height: self.height,
-            round: self.round,
-        }
-    }
-}
-
 #[derive(Clone, Debug)]
 pub(crate) struct HashChoke {
     pub(crate) height: u64,
     pub(crate) round: u64,
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use rand::random;
-
-    fn gen_address() -> Address {
-        Address::from((0..32).map(|_| random::<u8>()).collect::<Vec<_>>())
-    }
-
-    fn mock_node() -> Node {
-        Node::new(gen_address())
-    }
+/// A block kept as the bytes encoded by the application, which lets a trace be replayed or a
+/// message be encoded on the wire without the block type of the application.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RawBlock(#[serde(with = "super::serde_hex")] pub Bytes);
 
-    fn mock_status() -> Status {
-        Status {
-            height: random::<u64>(),
-            interval: None,
-            timer_config: None,
-            authority_list: vec![mock_node(), mock_node()],
-        }
+impl Codec for RawBlock {
+    fn encode(&self) -> Result<Bytes, Box<dyn Error + Send>> {
+        Ok(self.0.clone())
     }
 
-    #[test]
-    fn test_consensus_power() {
-        let status = mock_status();
-        let consensus_node = status.authority_list[0].address.clone();
-        let sync_node = gen_address();
-
-        assert!(status.is_consensus_node(&consensus_node));
-        assert!(!status.is_consensus_node(&sync_node));
+    fn decode(data: Bytes) -> Result<Self, Box<dyn Error + Send>> {
+        Ok(RawBlock(data))
     }
 }
@@ -1,4 +1,5 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Instant;
 
 use bit_vec::BitVec;
 use derive_more::Display;
@@ -141,6 +142,122 @@ impl AuthorityManage {
     }
 }
 
+/// Authority schedule keeps the authority lists that are effective at each height. An authority
+/// list returned along with the status of height `h` is the result of committing height `h - 1`,
+/// and it takes effect at height `h - 1 + delay`. A list keeps effective until a later list
+/// replaces it, so looking up a height returns the latest list activated at or before that height.
+///
+/// The lists of the heights that are not determined by commits yet can be cached by height, which
+/// are usually got from `Consensus::get_authority_list`. The time of the last failed lookup of a
+/// height is kept as well, so that the lookup is not repeated for every message of the height.
+#[derive(Clone, Debug)]
+pub struct AuthoritySchedule {
+    delay: u64,
+    lists: BTreeMap<u64, AuthorityManage>,
+    cache: BTreeMap<u64, AuthorityManage>,
+    misses: BTreeMap<u64, Instant>,
+}
+
+impl AuthoritySchedule {
+    /// Create a new authority schedule with the given activation delay. A delay of `0` is treated
+    /// as `1` which means that the list takes effect at the very next height.
+    pub fn new(delay: u64) -> Self {
+        AuthoritySchedule {
+            delay: delay.max(1),
+            lists: BTreeMap::new(),
+            cache: BTreeMap::new(),
+            misses: BTreeMap::new(),
+        }
+    }
+
+    /// Insert an authority list that takes effect from the given height.
+    pub fn insert(&mut self, height: u64, authority_list: &mut [Node]) {
+        let mut auth = AuthorityManage::new();
+        auth.update(authority_list);
+        self.lists.insert(height, auth);
+    }
+
+    /// Schedule an authority list returned along with the status of the given height.
+    pub fn schedule(&mut self, status_height: u64, authority_list: &mut [Node]) {
+        let activation = status_height.saturating_sub(1) + self.delay;
+        self.insert(activation, authority_list);
+    }
+
+    /// Get the authority manage that would be effective at the given height once the list
+    /// returned along with the status of the given height is scheduled, without scheduling it.
+    pub fn peek(
+        &self,
+        height: u64,
+        status_height: u64,
+        authority_list: &mut [Node],
+    ) -> Option<AuthorityManage> {
+        if status_height.saturating_sub(1) + self.delay <= height {
+            let mut auth = AuthorityManage::new();
+            auth.update(authority_list);
+            return Some(auth);
+        }
+        self.get(height).cloned()
+    }
+
+    /// Get the authority manage that is effective at the given height.
+    pub fn get(&self, height: u64) -> Option<&AuthorityManage> {
+        self.lists
+            .range(..=height)
+            .next_back()
+            .map(|(_, auth)| auth)
+    }
+
+    /// Cache the authority list of exactly the given height.
+    pub fn cache(&mut self, height: u64, authority_list: &mut [Node]) {
+        let mut auth = AuthorityManage::new();
+        auth.update(authority_list);
+        self.cache.insert(height, auth);
+        self.misses.remove(&height);
+    }
+
+    /// Record that the authority list of the given height failed to be got at the given time.
+    pub fn miss(&mut self, height: u64, at: Instant) {
+        self.misses.insert(height, at);
+    }
+
+    /// Get the time of the last failed lookup of the authority list of the given height.
+    pub fn last_miss(&self, height: u64) -> Option<Instant> {
+        self.misses.get(&height).copied()
+    }
+
+    /// Get the cached authority manage of exactly the given height.
+    pub fn get_cached(&self, height: u64) -> Option<&AuthorityManage> {
+        self.cache.get(&height)
+    }
+
+    /// Look up the authority manage of the given height when the current height is `current`.
+    /// The scheduled list is returned if it is determined, otherwise the cached one.
+    pub fn lookup(&self, height: u64, current: u64) -> Option<&AuthorityManage> {
+        if self.is_determined(height, current) {
+            self.get(height)
+        } else {
+            self.get_cached(height)
+        }
+    }
+
+    /// If the authority list of the given height is determined when the current height is
+    /// `current`. All the lists that take effect before `current + delay` have been returned by
+    /// the commits lower than the current height.
+    pub fn is_determined(&self, height: u64, current: u64) -> bool {
+        height < current + self.delay
+    }
+
+    /// Remove the lists that are replaced before the given height, and the cached lists and the
+    /// failed lookups lower than the given height. The list effective at `till` is kept.
+    pub fn flush(&mut self, till: u64) {
+        if let Some(&effective) = self.lists.range(..=till).next_back().map(|(h, _)| h) {
+            self.lists = self.lists.split_off(&effective);
+        }
+        self.cache = self.cache.split_off(&till);
+        self.misses = self.misses.split_off(&till);
+    }
+}
+
 /// Give the validators list and bitmap, returns the activated validators, the authority list MUST
 /// be sorted
 pub fn extract_voters(
@@ -186,6 +303,8 @@ fn rotation_leader_index(height: u64, round: u64, authority_len: usize) -> usize
 
 #[cfg(test)]
 mod test {
+    use std::time::Instant;
+
     use bit_vec::BitVec;
     use bytes::Bytes;
     use rand::random;
@@ -193,7 +312,7 @@ mod test {
     use crate::error::ConsensusError;
     use crate::extract_voters;
     use crate::types::{Address, Node};
-    use crate::utils::auth_manage::AuthorityManage;
+    use crate::utils::auth_manage::{AuthorityManage, AuthoritySchedule};
 
     fn gen_address() -> Address {
         Address::from((0..32).map(|_| random::<u8>()).collect::<Vec<_>>())
@@ -394,4 +513,89 @@ mod test {
             );
         }
     }
+
+    #[test]
+    fn test_authority_schedule() {
+        let mut origin = gen_auth_list(4);
+        let mut changed = gen_auth_list(4);
+        let mut schedule = AuthoritySchedule::new(3);
+        schedule.insert(1, &mut origin);
+
+        // The list returned by committing height 5 takes effect at height 8.
+        schedule.schedule(6, &mut changed);
+        for height in 1..8 {
+            assert!(schedule.get(height).unwrap().contains(&origin[0].address));
+            assert!(!schedule.get(height).unwrap().contains(&changed[0].address));
+        }
+        for height in 8..12 {
+            assert!(schedule.get(height).unwrap().contains(&changed[0].address));
+        }
+
+        // Peeking does not schedule the list.
+        let mut next = gen_auth_list(4);
+        let peeked = schedule.peek(9, 7, &mut next).unwrap();
+        assert!(peeked.contains(&next[0].address));
+        let peeked = schedule.peek(8, 7, &mut next).unwrap();
+        assert!(peeked.contains(&changed[0].address));
+        assert!(!schedule.get(9).unwrap().contains(&next[0].address));
+
+        assert!(schedule.is_determined(8, 6));
+        assert!(!schedule.is_determined(9, 6));
+
+        schedule.flush(7);
+        assert!(schedule.get(7).is_some());
+        schedule.flush(8);
+        assert!(schedule.get(7).is_none());
+        assert!(schedule.get(8).unwrap().contains(&changed[0].address));
+    }
+
+    #[test]
+    fn test_zero_delay_schedule() {
+        let mut authority_list = gen_auth_list(4);
+        let mut schedule = AuthoritySchedule::new(0);
+        assert_eq!(schedule.delay, 1);
+
+        schedule.schedule(2, &mut authority_list);
+        assert!(schedule.get(1).is_none());
+        assert!(schedule.get(2).is_some());
+    }
+
+    #[test]
+    fn test_authority_cache() {
+        let mut origin = gen_auth_list(4);
+        let mut fetched = gen_auth_list(4);
+        let mut schedule = AuthoritySchedule::new(2);
+        schedule.insert(1, &mut origin);
+
+        // Height 3 is not determined at height 1 until its list is cached.
+        assert!(schedule.lookup(2, 1).is_some());
+        assert!(schedule.lookup(3, 1).is_none());
+        schedule.cache(3, &mut fetched);
+        assert!(schedule.lookup(3, 1).unwrap().contains(&fetched[0].address));
+        assert!(schedule.lookup(4, 1).is_none());
+
+        // The scheduled list is preferred once the height is determined.
+        assert!(schedule.lookup(3, 2).unwrap().contains(&origin[0].address));
+        assert!(schedule.get_cached(3).is_some());
+
+        schedule.flush(4);
+        assert!(schedule.get_cached(3).is_none());
+    }
+
+    #[test]
+    fn test_authority_miss() {
+        let mut schedule = AuthoritySchedule::new(2);
+        let at = Instant::now();
+        schedule.miss(3, at);
+        schedule.miss(4, at);
+        assert_eq!(schedule.last_miss(3), Some(at));
+        assert!(schedule.last_miss(5).is_none());
+
+        // A cached list clears the miss.
+        schedule.cache(3, &mut gen_auth_list(4));
+        assert!(schedule.last_miss(3).is_none());
+
+        schedule.flush(5);
+        assert!(schedule.last_miss(4).is_none());
+    }
 }
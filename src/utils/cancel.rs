@@ -0,0 +1,88 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use creep::Context;
+use tokio::sync::Notify;
+
+/// The context key of the cancel token of a block check.
+const CANCEL_KEY: &str = "overlord_cancel_token";
+
+/// A token attached to the context of `check_block`. Overlord cancels the token when the check
+/// exceeds its deadline or the height it belongs to has been committed. The application can take
+/// the token from the context by `CancelToken::from_ctx` and stop the stale check.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancelToken {
+    /// Create a new token which is not cancelled.
+    pub fn new() -> Self {
+        CancelToken::default()
+    }
+
+    /// Get the token attached to the context.
+    pub fn from_ctx(ctx: &Context) -> Option<CancelToken> {
+        ctx.get::<CancelToken>(CANCEL_KEY).cloned()
+    }
+
+    /// Attach the token to the context.
+    pub fn attach(&self, ctx: Context) -> Context {
+        ctx.with_value(CANCEL_KEY, self.clone())
+    }
+
+    /// Cancel the token and wake up all the tasks waiting for it.
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        self.inner.notify.notify_waiters();
+    }
+
+    /// Return whether the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Wait until the token is cancelled.
+    pub async fn cancelled(&self) {
+        // Register the waiter before checking the flag, so a cancel in between is not missed.
+        let notified = self.inner.notify.notified();
+        if self.is_cancelled() {
+            return;
+        }
+        notified.await;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use creep::Context;
+
+    use crate::utils::cancel::CancelToken;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_cancel_token() {
+        assert!(CancelToken::from_ctx(&Context::new()).is_none());
+
+        let token = CancelToken::new();
+        let ctx = token.attach(Context::new());
+        let app_token = CancelToken::from_ctx(&ctx).unwrap();
+        assert!(!app_token.is_cancelled());
+
+        let waiter = tokio::spawn(async move { app_token.cancelled().await });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        token.cancel();
+        tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(CancelToken::from_ctx(&ctx).unwrap().is_cancelled());
+    }
+}
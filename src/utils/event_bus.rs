@@ -0,0 +1,65 @@
+use std::sync::Arc;
+
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use parking_lot::Mutex;
+
+use crate::types::OverlordEvent;
+
+/// An event bus that publishes overlord events to all the subscribers. The subscribers whose
+/// receiver has been dropped are removed when publishing.
+#[derive(Clone, Debug, Default)]
+pub struct EventBus {
+    subscribers: Arc<Mutex<Vec<UnboundedSender<OverlordEvent>>>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        EventBus::default()
+    }
+
+    /// Add a subscriber and return the receiver of the events published afterwards.
+    pub fn subscribe(&self) -> UnboundedReceiver<OverlordEvent> {
+        let (tx, rx) = unbounded();
+        self.subscribers.lock().push(tx);
+        rx
+    }
+
+    /// Publish an event to all the subscribers.
+    pub fn publish(&self, event: OverlordEvent) {
+        let mut subscribers = self.subscribers.lock();
+        if subscribers.is_empty() {
+            return;
+        }
+        subscribers.retain(|tx| tx.unbounded_send(event.clone()).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bytes::Bytes;
+
+    use crate::types::OverlordEvent;
+    use crate::utils::event_bus::EventBus;
+
+    #[test]
+    fn test_event_bus() {
+        let bus = EventBus::new();
+        let event = OverlordEvent::NewRound {
+            height: 1,
+            round: 0,
+            hash: Bytes::new(),
+        };
+        bus.publish(event.clone());
+
+        let mut rx_1 = bus.subscribe();
+        let rx_2 = bus.subscribe();
+        bus.publish(event.clone());
+        assert_eq!(rx_1.try_recv().ok(), Some(event.clone()));
+        assert!(rx_1.try_recv().is_err());
+
+        drop(rx_2);
+        bus.publish(event.clone());
+        assert_eq!(bus.subscribers.lock().len(), 1);
+        assert_eq!(rx_1.try_recv().ok(), Some(event));
+    }
+}
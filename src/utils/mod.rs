@@ -1,6 +1,15 @@
 ///
 pub mod auth_manage;
+/// Cancel the stale block checks of the application.
+pub mod cancel;
+/// Publish the consensus events to the subscribers.
+pub mod event_bus;
 ///
 mod rand_proposer;
+/// Retry the application callbacks.
+pub mod retry;
 ///
 pub mod timer_config;
+/// Carry the tracing spans across the message channels.
+#[cfg(feature = "tracing")]
+pub mod trace;
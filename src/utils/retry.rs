@@ -0,0 +1,92 @@
+use std::future::Future;
+
+use crate::config::RetryPolicy;
+use crate::error::{ErrorSource, TransientError};
+use crate::Clock;
+
+/// Call the given function until it succeeds, fails with an error that is not transient, or the
+/// attempts of the retry policy are exhausted. Return the error of the last call if it fails.
+pub async fn retry<R, F, Fut>(
+    policy: &RetryPolicy,
+    clock: &dyn Clock,
+    name: &str,
+    mut f: F,
+) -> Result<R, ErrorSource>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<R, ErrorSource>>,
+{
+    let mut attempt = 1;
+    loop {
+        match f().await {
+            Ok(res) => return Ok(res),
+            Err(e) if attempt < policy.max_attempts && TransientError::is_transient(&e) => {
+                let backoff = policy.backoff(attempt);
+                log::warn!(
+                    "Overlord: {} error {:?}, retry after {:?}",
+                    name,
+                    e,
+                    backoff
+                );
+                clock.sleep(backoff).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::error::Error;
+    use std::io;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use crate::config::RetryPolicy;
+    use crate::error::TransientError;
+    use crate::utils::retry::retry;
+    use crate::SystemClock;
+
+    #[tokio::test]
+    async fn test_retry() {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: 1,
+            max_backoff: 2,
+        };
+
+        let calls = AtomicU32::new(0);
+        let res = retry(&policy, &SystemClock, "test", || async {
+            if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                Err(TransientError::boxed(io::Error::from(
+                    io::ErrorKind::TimedOut,
+                )))
+            } else {
+                Ok(calls.load(Ordering::SeqCst))
+            }
+        })
+        .await;
+        assert_eq!(res.unwrap(), 3);
+
+        let calls = AtomicU32::new(0);
+        let res: Result<(), _> = retry(&policy, &SystemClock, "test", || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(TransientError::boxed(io::Error::from(
+                io::ErrorKind::TimedOut,
+            )))
+        })
+        .await;
+        assert!(res.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        // An error that is not transient is not retried.
+        let calls = AtomicU32::new(0);
+        let res: Result<(), _> = retry(&policy, &SystemClock, "test", || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(Box::new(io::Error::from(io::ErrorKind::InvalidData)) as Box<dyn Error + Send>)
+        })
+        .await;
+        assert!(res.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -1,11 +1,13 @@
 use std::cell::Cell;
+use std::collections::VecDeque;
 use std::time::Duration;
 
-use crate::smr::smr_types::SMREvent;
-use crate::DurationConfig;
+use crate::smr::smr_types::{SMREvent, Step};
 use crate::{error::ConsensusError, ConsensusResult};
+use crate::{AdaptiveTimerConfig, DurationConfig, OverlordConfig};
 
-/// Overlord timer config.
+/// Overlord timer config. If the adaptive timing mode is on, the propose, prevote and precommit
+/// timeouts are tuned by the observed step durations.
 #[derive(Debug, Clone)]
 pub struct TimerConfig {
     interval: Cell<u64>,
@@ -13,6 +15,51 @@ pub struct TimerConfig {
     prevote: (u64, u64),
     precommit: (u64, u64),
     brake: (u64, u64),
+    adaptive: Option<AdaptiveTimeout>,
+}
+
+/// The latest observed durations of the propose, prevote and precommit steps.
+#[derive(Debug, Clone)]
+struct AdaptiveTimeout {
+    config: AdaptiveTimerConfig,
+    propose: VecDeque<Duration>,
+    prevote: VecDeque<Duration>,
+    precommit: VecDeque<Duration>,
+}
+
+impl AdaptiveTimeout {
+    fn new(config: AdaptiveTimerConfig) -> Self {
+        AdaptiveTimeout {
+            config,
+            propose: VecDeque::new(),
+            prevote: VecDeque::new(),
+            precommit: VecDeque::new(),
+        }
+    }
+
+    fn observe(&mut self, step: Step, duration: Duration) {
+        let window = self.config.window;
+        let durations = match step {
+            Step::Propose => &mut self.propose,
+            Step::Prevote => &mut self.prevote,
+            Step::Precommit => &mut self.precommit,
+            _ => return,
+        };
+
+        if durations.len() >= window {
+            durations.pop_front();
+        }
+        durations.push_back(duration);
+    }
+
+    /// Return `None` if the step has not been observed.
+    fn timeout(&self, durations: &VecDeque<Duration>) -> Option<Duration> {
+        let longest = durations.iter().max()?;
+        Some((*longest * self.config.multiplier).clamp(
+            Duration::from_millis(self.config.min_timeout),
+            Duration::from_millis(self.config.max_timeout),
+        ))
+    }
 }
 
 impl TimerConfig {
@@ -23,6 +70,28 @@ impl TimerConfig {
             prevote: (10, 10),
             precommit: (5, 10),
             brake: (3, 10),
+            adaptive: None,
+        }
+    }
+
+    /// Create the timer config of the step timeouts set by the overlord config.
+    pub fn from_config(config: &OverlordConfig) -> Self {
+        let mut timer_config = TimerConfig::new(config.interval);
+        timer_config.update(config.timer_config.clone());
+        if let Some(adaptive) = config.adaptive_timer.clone() {
+            timer_config.set_adaptive(adaptive);
+        }
+        timer_config
+    }
+
+    pub fn set_adaptive(&mut self, config: AdaptiveTimerConfig) {
+        self.adaptive = Some(AdaptiveTimeout::new(config));
+    }
+
+    /// Observe the time spent in a step to tune the timeout of it in the adaptive timing mode.
+    pub fn observe(&mut self, step: Step, duration: Duration) {
+        if let Some(adaptive) = self.adaptive.as_mut() {
+            adaptive.observe(step, duration);
         }
     }
 
@@ -47,19 +116,94 @@ impl TimerConfig {
         }
     }
 
-    fn get_propose_timeout(&self) -> Duration {
-        Duration::from_millis(self.interval.get() * self.propose.0 / self.propose.1)
+    /// The deadline of checking a block in the first round, which is scaled by `round_timeout` in
+    /// the later rounds as the step timeouts. The check is expected to finish before the prevote
+    /// and the precommit steps time out, otherwise the block can not be precommitted in the round.
+    pub fn get_check_block_timeout(&self) -> Duration {
+        self.get_prevote_timeout() + self.get_precommit_timeout()
+    }
+
+    pub fn get_propose_timeout(&self) -> Duration {
+        self.adaptive
+            .as_ref()
+            .and_then(|adaptive| adaptive.timeout(&adaptive.propose))
+            .unwrap_or_else(|| {
+                Duration::from_millis(self.interval.get() * self.propose.0 / self.propose.1)
+            })
     }
 
     fn get_prevote_timeout(&self) -> Duration {
-        Duration::from_millis(self.interval.get() * self.prevote.0 / self.prevote.1)
+        self.adaptive
+            .as_ref()
+            .and_then(|adaptive| adaptive.timeout(&adaptive.prevote))
+            .unwrap_or_else(|| {
+                Duration::from_millis(self.interval.get() * self.prevote.0 / self.prevote.1)
+            })
     }
 
     fn get_precommit_timeout(&self) -> Duration {
-        Duration::from_millis(self.interval.get() * self.precommit.0 / self.precommit.1)
+        self.adaptive
+            .as_ref()
+            .and_then(|adaptive| adaptive.timeout(&adaptive.precommit))
+            .unwrap_or_else(|| {
+                Duration::from_millis(self.interval.get() * self.precommit.0 / self.precommit.1)
+            })
     }
 
     fn get_brake_timeout(&self) -> Duration {
         Duration::from_millis(self.interval.get() * self.brake.0 / self.brake.1)
     }
 }
+
+/// The timeout of a step in the given round, which doubles every round until the round reaches
+/// the max timeout coef.
+pub fn round_timeout(timeout: Duration, round: u64, max_coef: u32) -> Duration {
+    let coef = if round > u64::from(max_coef) {
+        max_coef
+    } else {
+        round as u32
+    };
+    timeout * 2u32.pow(coef)
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::smr::smr_types::Step;
+    use crate::utils::timer_config::{round_timeout, TimerConfig};
+    use crate::AdaptiveTimerConfig;
+
+    #[test]
+    fn test_adaptive_timeout() {
+        let mut config = TimerConfig::new(3000);
+        config.observe(Step::Propose, Duration::from_millis(100));
+        assert_eq!(config.get_propose_timeout(), Duration::from_millis(7200));
+
+        config.set_adaptive(AdaptiveTimerConfig {
+            window: 2,
+            multiplier: 2,
+            min_timeout: 100,
+            max_timeout: 5000,
+        });
+        assert_eq!(config.get_propose_timeout(), Duration::from_millis(7200));
+
+        config.observe(Step::Propose, Duration::from_millis(300));
+        config.observe(Step::Propose, Duration::from_millis(200));
+        assert_eq!(config.get_propose_timeout(), Duration::from_millis(600));
+        config.observe(Step::Propose, Duration::from_millis(20));
+        assert_eq!(config.get_propose_timeout(), Duration::from_millis(400));
+        config.observe(Step::Propose, Duration::from_millis(10));
+        assert_eq!(config.get_propose_timeout(), Duration::from_millis(100));
+
+        config.observe(Step::Prevote, Duration::from_millis(4000));
+        assert_eq!(config.get_prevote_timeout(), Duration::from_millis(5000));
+        assert_eq!(config.get_precommit_timeout(), Duration::from_millis(1500));
+
+        // The check deadline follows the tuned timeouts and grows with the round.
+        let timeout = config.get_check_block_timeout();
+        assert_eq!(timeout, Duration::from_millis(6500));
+        assert_eq!(round_timeout(timeout, 2, 5), Duration::from_millis(26000));
+        assert_eq!(round_timeout(timeout, 9, 5), Duration::from_millis(208_000));
+    }
+}
@@ -0,0 +1,15 @@
+use creep::Context;
+use tracing::{Id, Span};
+
+/// The context key of the span that a message is sent in.
+const SPAN_KEY: &str = "overlord_tracing_span";
+
+/// Attach the current span to the context, so that the spans of handling the message follow it.
+pub fn with_current_span(ctx: Context) -> Context {
+    ctx.with_value(SPAN_KEY, Span::current())
+}
+
+/// Get the id of the span attached to the context.
+pub fn parent_span(ctx: &Context) -> Option<Id> {
+    ctx.get::<Span>(SPAN_KEY).and_then(Span::id)
+}
@@ -1,3 +1,4 @@
+mod repair;
 mod wal_type;
 
 pub use self::wal_type::{SMRBase, WalInfo, WalLock};
@@ -0,0 +1,313 @@
+use bytes::Bytes;
+
+use crate::error::ConsensusError;
+use crate::smr::smr_types::Step;
+use crate::types::{AggregatedChoke, AggregatedVote, Node, UpdateFrom, VoteType};
+use crate::utils::auth_manage::AuthorityManage;
+use crate::wal::WalInfo;
+use crate::{Codec, ConsensusResult, Crypto, SignDomain};
+
+impl<T: Codec> WalInfo<T> {
+    /// Decode a wal information from the bytes saved by overlord.
+    pub fn from_bytes(data: &[u8]) -> ConsensusResult<Self> {
+        rlp::decode(data).map_err(|e| ConsensusError::LoadWalErr {
+            source: Box::new(e),
+        })
+    }
+
+    /// Encode the wal information into the bytes which overlord saves.
+    pub fn to_bytes(&self) -> Bytes {
+        Bytes::from(rlp::encode(self))
+    }
+
+    /// Verify the quorum certificates in the wal information against the authority list of its
+    /// height: the lock must be a prevote QC of the lock round and the height, the QC which the
+    /// state goes to the round from must not be from a higher round, and the voters of each QC
+    /// must be above the threshold with a valid aggregated signature in the sign domain. The
+    /// placeholder QC with which a height starts is not verified.
+    pub fn verify<C: Crypto>(
+        &self,
+        authority_list: &[Node],
+        crypto: &C,
+        domain: &SignDomain,
+    ) -> ConsensusResult<()> {
+        let mut authority = AuthorityManage::new();
+        authority.update(&mut authority_list.to_vec());
+
+        if let Some(lock) = self.lock.as_ref() {
+            let qc = &lock.lock_votes;
+            if qc.vote_type != VoteType::Prevote {
+                return Err(ConsensusError::CorrectnessErr(
+                    "the lock is not a prevote QC".to_string(),
+                ));
+            }
+            if qc.round != lock.lock_round || lock.lock_round > self.round {
+                return Err(ConsensusError::CorrectnessErr(format!(
+                    "the lock round {} mismatches the QC round {} or exceeds the round {}",
+                    lock.lock_round, qc.round, self.round
+                )));
+            }
+            if qc.block_hash.is_empty() {
+                return Err(ConsensusError::CorrectnessErr(
+                    "the lock is a nil QC".to_string(),
+                ));
+            }
+            self.verify_qc(qc, &authority, crypto, domain)?;
+        }
+
+        if self.step == Step::Commit && self.lock.is_none() {
+            return Err(ConsensusError::CorrectnessErr(
+                "no lock in commit step".to_string(),
+            ));
+        }
+
+        match &self.from {
+            UpdateFrom::PrevoteQC(qc) | UpdateFrom::PrecommitQC(qc) => {
+                if qc.signature.signature.is_empty() && qc.signature.address_bitmap.is_empty() {
+                    return Ok(());
+                }
+                if qc.round > self.round {
+                    return Err(ConsensusError::CorrectnessErr(format!(
+                        "the round {} is updated from a QC of the higher round {}",
+                        self.round, qc.round
+                    )));
+                }
+                self.verify_qc(qc, &authority, crypto, domain)
+            }
+            UpdateFrom::ChokeQC(qc) => {
+                if qc.round >= self.round {
+                    return Err(ConsensusError::CorrectnessErr(format!(
+                        "the round {} is updated from a choke QC of the round {}",
+                        self.round, qc.round
+                    )));
+                }
+                self.verify_choke_qc(qc, &authority, crypto, domain)
+            }
+        }
+    }
+
+    /// Reset the wal information to the propose step of the next round of the same height without
+    /// a lock, keeping the QC which the state goes to the round from. Self may have voted in the
+    /// round of the wal, so the reset moves on to the next round, where it has not voted yet, to
+    /// never vote twice in one round. Dropping a lock gives up the protection against voting for
+    /// a conflicting block, so a wal should only be reset when the lock is lost or broken.
+    pub fn reset(&self) -> Self {
+        WalInfo {
+            height: self.height,
+            round: self.round + 1,
+            step: Step::Propose,
+            lock: None,
+            from: self.from.clone(),
+        }
+    }
+
+    fn verify_qc<C: Crypto>(
+        &self,
+        qc: &AggregatedVote,
+        authority: &AuthorityManage,
+        crypto: &C,
+        domain: &SignDomain,
+    ) -> ConsensusResult<()> {
+        if qc.height != self.height {
+            return Err(ConsensusError::CorrectnessErr(format!(
+                "{} is not of the height {}",
+                qc, self.height
+            )));
+        }
+        if !authority.is_above_threshold(&qc.signature.address_bitmap)? {
+            return Err(ConsensusError::AggregatedSignatureErr(format!(
+                "the vote weight of the voters of {} is not above the threshold",
+                qc
+            )));
+        }
+
+        let voters = authority.get_voters(&qc.signature.address_bitmap)?;
+        let hash = crypto.hash(domain.vote_payload(&qc.to_vote()));
+        crypto
+            .verify_aggregated_signature(qc.signature.signature.clone(), hash, voters)
+            .map_err(|e| ConsensusError::AggregatedSignatureErr(format!("{}: {:?}", qc, e)))
+    }
+
+    fn verify_choke_qc<C: Crypto>(
+        &self,
+        qc: &AggregatedChoke,
+        authority: &AuthorityManage,
+        crypto: &C,
+        domain: &SignDomain,
+    ) -> ConsensusResult<()> {
+        if qc.height != self.height {
+            return Err(ConsensusError::CorrectnessErr(format!(
+                "the choke QC of the height {} is not of the height {}",
+                qc.height, self.height
+            )));
+        }
+        if qc.voters.iter().any(|voter| !authority.contains(voter)) {
+            return Err(ConsensusError::InvalidAddress);
+        }
+        if qc.len() * 3 <= authority.len() * 2 {
+            return Err(ConsensusError::AggregatedSignatureErr(
+                "the choke QC is not above the threshold".to_string(),
+            ));
+        }
+
+        let hash = crypto.hash(domain.choke_payload(qc.height, qc.round));
+        crypto
+            .verify_aggregated_signature(qc.signature.clone(), hash, qc.voters.clone())
+            .map_err(|e| {
+                ConsensusError::AggregatedSignatureErr(format!(
+                    "choke QC round {}: {:?}",
+                    qc.round, e
+                ))
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bit_vec::BitVec;
+    use bytes::Bytes;
+
+    use crate::sim::{sim_address, SimCrypto, SimWal};
+    use crate::smr::smr_types::Step;
+    use crate::state::harness::Harness;
+    use crate::types::{AggregatedSignature, AggregatedVote, Node, RawBlock, UpdateFrom, VoteType};
+    use crate::wal::{WalInfo, WalLock};
+    use crate::{get_leader, Crypto, OverlordConfig, SignDomain, Wal};
+
+    fn domain() -> SignDomain {
+        SignDomain::Chain(Bytes::from("chain"))
+    }
+
+    fn authority_list() -> Vec<Node> {
+        let mut list = (0..4)
+            .map(|i| Node {
+                address: sim_address(i),
+                propose_weight: 1,
+                vote_weight: 1,
+            })
+            .collect::<Vec<_>>();
+        list.sort();
+        list
+    }
+
+    fn gen_qc(voters: usize, vote_type: VoteType, round: u64) -> AggregatedVote {
+        let list = authority_list();
+        let mut qc = AggregatedVote {
+            signature: AggregatedSignature {
+                signature: Bytes::new(),
+                address_bitmap: Bytes::new(),
+            },
+            vote_type,
+            height: 5,
+            round,
+            block_hash: Bytes::from("block"),
+            leader: list[0].address.clone(),
+        };
+
+        let hash = SimCrypto::new(Bytes::new()).hash(domain().vote_payload(&qc.to_vote()));
+        let mut bitmap = BitVec::from_elem(list.len(), false);
+        let mut signatures = Vec::new();
+        let mut addresses = Vec::new();
+        for (i, node) in list.iter().take(voters).enumerate() {
+            bitmap.set(i, true);
+            let crypto = SimCrypto::new(node.address.clone());
+            signatures.push(crypto.sign(hash.clone()).unwrap());
+            addresses.push(node.address.clone());
+        }
+        qc.signature = AggregatedSignature {
+            signature: SimCrypto::new(Bytes::new())
+                .aggregate_signatures(signatures, addresses)
+                .unwrap(),
+            address_bitmap: Bytes::from(bitmap.to_bytes()),
+        };
+        qc
+    }
+
+    fn gen_wal(lock: AggregatedVote) -> WalInfo<RawBlock> {
+        WalInfo {
+            height: 5,
+            round: 2,
+            step: Step::Precommit,
+            lock: Some(WalLock {
+                lock_round: lock.round,
+                lock_votes: lock,
+                content: RawBlock(Bytes::from("content")),
+            }),
+            from: UpdateFrom::PrecommitQC(gen_qc(3, VoteType::Precommit, 1)),
+        }
+    }
+
+    #[test]
+    fn test_verify_wal() {
+        let list = authority_list();
+        let crypto = SimCrypto::new(Bytes::new());
+
+        let wal = gen_wal(gen_qc(3, VoteType::Prevote, 1));
+        let decoded = WalInfo::<RawBlock>::from_bytes(&wal.to_bytes()).unwrap();
+        assert_eq!(decoded, wal);
+        assert!(wal.verify(&list, &crypto, &domain()).is_ok());
+        // Signed in another domain.
+        assert!(wal.verify(&list, &crypto, &SignDomain::Legacy).is_err());
+
+        // Below the threshold.
+        assert!(gen_wal(gen_qc(2, VoteType::Prevote, 1))
+            .verify(&list, &crypto, &domain())
+            .is_err());
+        // A precommit QC as the lock.
+        assert!(gen_wal(gen_qc(3, VoteType::Precommit, 1))
+            .verify(&list, &crypto, &domain())
+            .is_err());
+        // A lock of a higher round.
+        assert!(gen_wal(gen_qc(3, VoteType::Prevote, 3))
+            .verify(&list, &crypto, &domain())
+            .is_err());
+        // A forged signature.
+        let mut qc = gen_qc(3, VoteType::Prevote, 1);
+        qc.block_hash = Bytes::from("forged");
+        assert!(gen_wal(qc).verify(&list, &crypto, &domain()).is_err());
+
+        assert!(WalInfo::<RawBlock>::from_bytes(b"broken").is_err());
+    }
+
+    #[test]
+    fn test_reset_wal() {
+        let wal = gen_wal(gen_qc(3, VoteType::Prevote, 1));
+        let reset = wal.reset();
+        assert_eq!(reset.height, 5);
+        assert_eq!(reset.round, 3);
+        assert_eq!(reset.step, Step::Propose);
+        assert!(reset.lock.is_none());
+        assert_eq!(reset.from, wal.from);
+        assert!(reset
+            .verify(&authority_list(), &SimCrypto::new(Bytes::new()), &domain())
+            .is_ok());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_restart_from_reset_wal() {
+        // Self has voted in round 2 before the wal is reset.
+        let wal = SimWal::default();
+        let info = gen_wal(gen_qc(3, VoteType::Prevote, 1)).reset();
+        wal.save(info.to_bytes()).await.unwrap();
+
+        let config = OverlordConfig {
+            sign_domain: domain(),
+            ..Default::default()
+        };
+        // A node which is not the leader of the rounds transmits its votes to the leader.
+        let index = (0..4)
+            .find(|i| {
+                let leader = |round| get_leader(5, round, authority_list());
+                sim_address(*i) != leader(2) && sim_address(*i) != leader(3)
+            })
+            .unwrap();
+        let node = Harness::start(index, 4, config, wal);
+        node.run_timers(10).await;
+
+        let votes = node.sent_votes();
+
+        assert!(!votes.is_empty());
+        assert!(votes.iter().all(|vote| vote.get_height() == 5));
+        assert!(votes.iter().all(|vote| vote.get_round() > 2), "{:?}", votes);
+    }
+}
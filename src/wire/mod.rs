@@ -0,0 +1,340 @@
+#[cfg(feature = "scale")]
+mod scale;
+
+use std::error::Error;
+
+use bytes::Bytes;
+use derive_more::Display;
+
+use crate::types::{OverlordMsg, Proposal, RawBlock, SignedProposal};
+use crate::Codec;
+
+/// The version of the wire format, which is the first byte of an encoded message.
+pub const WIRE_VERSION: u8 = 1;
+
+/// The encoding of the overlord messages between the nodes. An encoded message is the version byte
+/// followed by the message in the chosen encoding, which is a tagged list of the variant and its
+/// payload: 0 for a signed proposal, 1 for a signed vote, 2 for an aggregated vote and 4 for a
+/// signed choke. The block in a proposal is encoded by its `Codec`. A rich status and a stop
+/// message are only sent by the application to its own node, so they are never encoded or
+/// accepted on the wire, and the tags 3 and 5 are not used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WireFormat {
+    /// The RLP encoding, which the signed hashes and the wal also use. It encodes a `None`
+    /// interval or timer config of a `Status` as a default value, so a status does not decode to
+    /// the same status.
+    Rlp,
+    /// The SCALE encoding, where an `Option` is kept as it is instead of a default value.
+    #[cfg(feature = "scale")]
+    Scale,
+}
+
+impl Default for WireFormat {
+    fn default() -> Self {
+        WireFormat::Rlp
+    }
+}
+
+/// The error of encoding or decoding a message on the wire.
+#[derive(Debug, Display)]
+pub enum WireError {
+    /// The version of the message is not supported.
+    #[display(fmt = "unsupported wire version {}", _0)]
+    Version(u8),
+    /// The message is not sent between the nodes.
+    #[display(fmt = "{} is not a wire message", _0)]
+    NotOnWire(String),
+    /// The block of the message fails to encode by its `Codec`.
+    #[display(fmt = "wire encode error {}", _0)]
+    Encode(String),
+    /// The message is malformed.
+    #[display(fmt = "wire decode error {}", _0)]
+    Decode(String),
+}
+
+impl Error for WireError {}
+
+impl WireFormat {
+    /// Encode a message to send to other nodes. The block of a proposal is encoded first, so that
+    /// an error of its `Codec` is returned instead of a panic.
+    pub fn encode<T: Codec>(self, msg: &OverlordMsg<T>) -> Result<Bytes, WireError> {
+        check_on_wire(msg)?;
+        let msg = encode_block(msg)?;
+        let mut data = vec![WIRE_VERSION];
+        match self {
+            WireFormat::Rlp => data.extend_from_slice(&rlp::encode(&msg)),
+            #[cfg(feature = "scale")]
+            WireFormat::Scale => parity_scale_codec::Encode::encode_to(&msg, &mut data),
+        }
+        Ok(Bytes::from(data))
+    }
+
+    /// Decode a message received from another node.
+    pub fn decode<T: Codec>(self, data: &[u8]) -> Result<OverlordMsg<T>, WireError> {
+        let (version, body) = data
+            .split_first()
+            .ok_or_else(|| WireError::Decode("empty message".to_string()))?;
+        if *version != WIRE_VERSION {
+            return Err(WireError::Version(*version));
+        }
+
+        let msg = match self {
+            WireFormat::Rlp => {
+                rlp::decode(body).map_err(|e| WireError::Decode(format!("{:?}", e)))?
+            }
+            #[cfg(feature = "scale")]
+            WireFormat::Scale => {
+                let mut body = body;
+                parity_scale_codec::DecodeAll::decode_all(&mut body)
+                    .map_err(|e| WireError::Decode(e.to_string()))?
+            }
+        };
+        check_on_wire(&msg)?;
+        Ok(msg)
+    }
+}
+
+fn check_on_wire<T: Codec>(msg: &OverlordMsg<T>) -> Result<(), WireError> {
+    match msg {
+        OverlordMsg::RichStatus(_) | OverlordMsg::Stop => {
+            Err(WireError::NotOnWire(msg.to_string()))
+        }
+        #[cfg(test)]
+        OverlordMsg::Commit(_) => Err(WireError::NotOnWire(msg.to_string())),
+        _ => Ok(()),
+    }
+}
+
+/// Replace the block of a proposal by its encoded bytes, which encode the same on the wire.
+fn encode_block<T: Codec>(msg: &OverlordMsg<T>) -> Result<OverlordMsg<RawBlock>, WireError> {
+    let msg = match msg {
+        OverlordMsg::SignedProposal(sp) => {
+            let content = sp
+                .proposal
+                .content
+                .encode()
+                .map_err(|e| WireError::Encode(e.to_string()))?;
+            OverlordMsg::SignedProposal(SignedProposal {
+                signature: sp.signature.clone(),
+                proposal: Proposal {
+                    height: sp.proposal.height,
+                    round: sp.proposal.round,
+                    content: RawBlock(content),
+                    block_hash: sp.proposal.block_hash.clone(),
+                    lock: sp.proposal.lock.clone(),
+                    proposer: sp.proposal.proposer.clone(),
+                },
+            })
+        }
+        OverlordMsg::SignedVote(sv) => OverlordMsg::SignedVote(sv.clone()),
+        OverlordMsg::AggregatedVote(qc) => OverlordMsg::AggregatedVote(qc.clone()),
+        OverlordMsg::SignedChoke(sc) => OverlordMsg::SignedChoke(sc.clone()),
+        _ => return Err(WireError::NotOnWire(msg.to_string())),
+    };
+    Ok(msg)
+}
+
+#[cfg(test)]
+mod test {
+    use std::error::Error;
+
+    use bytes::Bytes;
+
+    use crate::types::{
+        AggregatedChoke, AggregatedSignature, AggregatedVote, Choke, Node, OverlordMsg, PoLC,
+        Proposal, RawBlock, SignedChoke, SignedProposal, SignedVote, Status, UpdateFrom, Vote,
+        VoteType,
+    };
+    use crate::wire::{WireError, WireFormat, WIRE_VERSION};
+    use crate::{Codec, DurationConfig};
+
+    fn gen_qc(vote_type: VoteType) -> AggregatedVote {
+        AggregatedVote {
+            signature: AggregatedSignature {
+                signature: Bytes::from("signature"),
+                address_bitmap: Bytes::from(vec![0b1110_0000]),
+            },
+            vote_type,
+            height: 10,
+            round: 2,
+            block_hash: Bytes::from("hash"),
+            leader: Bytes::from("leader"),
+        }
+    }
+
+    fn gen_msgs() -> Vec<OverlordMsg<RawBlock>> {
+        let proposal = Proposal {
+            height: 10,
+            round: 3,
+            content: RawBlock(Bytes::from("block")),
+            block_hash: Bytes::from("hash"),
+            lock: Some(PoLC {
+                lock_round: 2,
+                lock_votes: gen_qc(VoteType::Prevote),
+            }),
+            proposer: Bytes::from("proposer"),
+        };
+        let mut no_lock = proposal.clone();
+        no_lock.lock = None;
+        let choke = Choke {
+            height: 10,
+            round: 4,
+            from: UpdateFrom::ChokeQC(AggregatedChoke {
+                height: 10,
+                round: 3,
+                signature: Bytes::from("signature"),
+                voters: vec![Bytes::from("a"), Bytes::from("b"), Bytes::from("c")],
+            }),
+        };
+
+        vec![
+            OverlordMsg::SignedProposal(SignedProposal {
+                signature: Bytes::from("signature"),
+                proposal,
+            }),
+            OverlordMsg::SignedProposal(SignedProposal {
+                signature: Bytes::from("signature"),
+                proposal: no_lock,
+            }),
+            OverlordMsg::SignedVote(SignedVote {
+                signature: Bytes::from("signature"),
+                vote: Vote {
+                    height: 10,
+                    round: 2,
+                    vote_type: VoteType::Precommit,
+                    block_hash: Bytes::new(),
+                },
+                voter: Bytes::from("voter"),
+            }),
+            OverlordMsg::AggregatedVote(gen_qc(VoteType::Precommit)),
+            OverlordMsg::SignedChoke(SignedChoke {
+                signature: Bytes::from("signature"),
+                choke,
+                address: Bytes::from("address"),
+            }),
+        ]
+    }
+
+    fn gen_status() -> OverlordMsg<RawBlock> {
+        OverlordMsg::RichStatus(Status {
+            height: 11,
+            interval: Some(3000),
+            timer_config: Some(DurationConfig::new(24, 10, 10, 10)),
+            authority_list: vec![Node {
+                address: Bytes::from("node"),
+                propose_weight: 1,
+                vote_weight: 2,
+            }],
+        })
+    }
+
+    fn formats() -> Vec<WireFormat> {
+        vec![
+            WireFormat::Rlp,
+            #[cfg(feature = "scale")]
+            WireFormat::Scale,
+        ]
+    }
+
+    #[test]
+    fn test_wire_round_trip() {
+        for format in formats() {
+            for msg in gen_msgs() {
+                let data = format.encode(&msg).unwrap();
+                assert_eq!(data[0], WIRE_VERSION);
+                assert_eq!(format.decode::<RawBlock>(&data).unwrap(), msg);
+            }
+        }
+    }
+
+    #[test]
+    fn test_wire_rlp_layout() {
+        // The RLP body is the tag and the payload, which interoperating clients rely on.
+        let qc = gen_qc(VoteType::Prevote);
+        let data = WireFormat::Rlp
+            .encode::<RawBlock>(&OverlordMsg::AggregatedVote(qc.clone()))
+            .unwrap();
+        let mut s = rlp::RlpStream::new_list(2);
+        s.append(&2u8).append(&qc);
+        assert_eq!(&data[1..], s.out().as_ref());
+    }
+
+    #[test]
+    fn test_wire_reject() {
+        for format in formats() {
+            let data = format.encode(&gen_msgs()[0]).unwrap();
+
+            let mut other_version = data.to_vec();
+            other_version[0] = WIRE_VERSION + 1;
+            assert!(matches!(
+                format.decode::<RawBlock>(&other_version),
+                Err(WireError::Version(v)) if v == WIRE_VERSION + 1
+            ));
+            assert!(format.decode::<RawBlock>(&data[..data.len() - 1]).is_err());
+            assert!(format.decode::<RawBlock>(&[]).is_err());
+            assert!(format.decode::<RawBlock>(&[WIRE_VERSION, 0xff]).is_err());
+
+            assert!(matches!(
+                format.encode::<RawBlock>(&OverlordMsg::Stop),
+                Err(WireError::NotOnWire(_))
+            ));
+            assert!(matches!(
+                format.encode::<RawBlock>(&gen_status()),
+                Err(WireError::NotOnWire(_))
+            ));
+        }
+
+        // A rich status from a peer is rejected.
+        let mut status = vec![WIRE_VERSION];
+        status.extend_from_slice(&rlp::encode(&gen_status()));
+        assert!(matches!(
+            WireFormat::Rlp.decode::<RawBlock>(&status),
+            Err(WireError::NotOnWire(_))
+        ));
+
+        // A stop message from a peer is rejected.
+        let mut stop = vec![WIRE_VERSION];
+        stop.extend_from_slice(&rlp::encode(&OverlordMsg::<RawBlock>::Stop));
+        assert!(matches!(
+            WireFormat::Rlp.decode::<RawBlock>(&stop),
+            Err(WireError::NotOnWire(_))
+        ));
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct BadBlock;
+
+    impl Codec for BadBlock {
+        fn encode(&self) -> Result<Bytes, Box<dyn Error + Send>> {
+            Err(Box::new(WireError::Decode("bad block".to_string())))
+        }
+
+        fn decode(_data: Bytes) -> Result<Self, Box<dyn Error + Send>> {
+            Ok(BadBlock)
+        }
+    }
+
+    #[test]
+    fn test_wire_encode_block_error() {
+        let proposal = match gen_msgs().remove(0) {
+            OverlordMsg::SignedProposal(sp) => SignedProposal {
+                signature: sp.signature,
+                proposal: Proposal {
+                    height: sp.proposal.height,
+                    round: sp.proposal.round,
+                    content: BadBlock,
+                    block_hash: sp.proposal.block_hash,
+                    lock: sp.proposal.lock,
+                    proposer: sp.proposal.proposer,
+                },
+            },
+            _ => unreachable!(),
+        };
+        for format in formats() {
+            assert!(matches!(
+                format.encode(&OverlordMsg::SignedProposal(proposal.clone())),
+                Err(WireError::Encode(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,368 @@
+use std::convert::TryFrom;
+
+use bytes::Bytes;
+use parity_scale_codec::{Decode, Encode, Error, Input, Output};
+
+use crate::types::{
+    AggregatedChoke, AggregatedSignature, AggregatedVote, Choke, Node, OverlordMsg, PoLC, Proposal,
+    SignedChoke, SignedProposal, SignedVote, Status, UpdateFrom, Vote, VoteType,
+};
+use crate::{Codec, DurationConfig};
+
+// impl Encode and Decode trait for OverlordMsg
+impl<T: Codec> Encode for OverlordMsg<T> {
+    fn encode_to<W: Output + ?Sized>(&self, dest: &mut W) {
+        match self {
+            OverlordMsg::SignedProposal(sp) => {
+                dest.push_byte(0);
+                sp.encode_to(dest);
+            }
+            OverlordMsg::SignedVote(sv) => {
+                dest.push_byte(1);
+                sv.encode_to(dest);
+            }
+            OverlordMsg::AggregatedVote(qc) => {
+                dest.push_byte(2);
+                qc.encode_to(dest);
+            }
+            OverlordMsg::RichStatus(status) => {
+                dest.push_byte(3);
+                status.encode_to(dest);
+            }
+            OverlordMsg::SignedChoke(sc) => {
+                dest.push_byte(4);
+                sc.encode_to(dest);
+            }
+            OverlordMsg::Stop => dest.push_byte(5),
+            #[cfg(test)]
+            OverlordMsg::Commit(_) => unreachable!("a commit is not a wire message"),
+        }
+    }
+}
+
+impl<T: Codec> Decode for OverlordMsg<T> {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        match input.read_byte()? {
+            0 => Ok(OverlordMsg::SignedProposal(Decode::decode(input)?)),
+            1 => Ok(OverlordMsg::SignedVote(Decode::decode(input)?)),
+            2 => Ok(OverlordMsg::AggregatedVote(Decode::decode(input)?)),
+            3 => Ok(OverlordMsg::RichStatus(Decode::decode(input)?)),
+            4 => Ok(OverlordMsg::SignedChoke(Decode::decode(input)?)),
+            5 => Ok(OverlordMsg::Stop),
+            _ => Err("Invalid message tag.".into()),
+        }
+    }
+}
+
+// impl Encode and Decode trait for SignedProposal
+impl<T: Codec> Encode for SignedProposal<T> {
+    fn encode_to<W: Output + ?Sized>(&self, dest: &mut W) {
+        self.signature.encode_to(dest);
+        self.proposal.encode_to(dest);
+    }
+}
+
+impl<T: Codec> Decode for SignedProposal<T> {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        Ok(SignedProposal {
+            signature: Decode::decode(input)?,
+            proposal: Decode::decode(input)?,
+        })
+    }
+}
+
+// impl Encode and Decode trait for Proposal
+impl<T: Codec> Encode for Proposal<T> {
+    fn encode_to<W: Output + ?Sized>(&self, dest: &mut W) {
+        let content = self.content.encode().unwrap();
+        self.height.encode_to(dest);
+        self.round.encode_to(dest);
+        self.block_hash.encode_to(dest);
+        self.lock.encode_to(dest);
+        self.proposer.encode_to(dest);
+        content.encode_to(dest);
+    }
+}
+
+impl<T: Codec> Decode for Proposal<T> {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        let height = Decode::decode(input)?;
+        let round = Decode::decode(input)?;
+        let block_hash = Decode::decode(input)?;
+        let lock = Decode::decode(input)?;
+        let proposer = Decode::decode(input)?;
+        let content =
+            Codec::decode(Bytes::decode(input)?).map_err(|_| Error::from("Codec decode error."))?;
+        Ok(Proposal {
+            height,
+            round,
+            content,
+            block_hash,
+            lock,
+            proposer,
+        })
+    }
+}
+
+// impl Encode and Decode trait for PoLC
+impl Encode for PoLC {
+    fn encode_to<W: Output + ?Sized>(&self, dest: &mut W) {
+        self.lock_round.encode_to(dest);
+        self.lock_votes.encode_to(dest);
+    }
+}
+
+impl Decode for PoLC {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        Ok(PoLC {
+            lock_round: Decode::decode(input)?,
+            lock_votes: Decode::decode(input)?,
+        })
+    }
+}
+
+// impl Encode and Decode trait for VoteType
+impl Encode for VoteType {
+    fn encode_to<W: Output + ?Sized>(&self, dest: &mut W) {
+        dest.push_byte(self.clone().into());
+    }
+}
+
+impl Decode for VoteType {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        VoteType::try_from(input.read_byte()?).map_err(|_| "Invalid vote type".into())
+    }
+}
+
+// impl Encode and Decode trait for AggregatedSignature
+impl Encode for AggregatedSignature {
+    fn encode_to<W: Output + ?Sized>(&self, dest: &mut W) {
+        self.signature.encode_to(dest);
+        self.address_bitmap.encode_to(dest);
+    }
+}
+
+impl Decode for AggregatedSignature {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        Ok(AggregatedSignature {
+            signature: Decode::decode(input)?,
+            address_bitmap: Decode::decode(input)?,
+        })
+    }
+}
+
+// impl Encode and Decode trait for AggregatedVote
+impl Encode for AggregatedVote {
+    fn encode_to<W: Output + ?Sized>(&self, dest: &mut W) {
+        self.signature.encode_to(dest);
+        self.vote_type.encode_to(dest);
+        self.height.encode_to(dest);
+        self.round.encode_to(dest);
+        self.block_hash.encode_to(dest);
+        self.leader.encode_to(dest);
+    }
+}
+
+impl Decode for AggregatedVote {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        Ok(AggregatedVote {
+            signature: Decode::decode(input)?,
+            vote_type: Decode::decode(input)?,
+            height: Decode::decode(input)?,
+            round: Decode::decode(input)?,
+            block_hash: Decode::decode(input)?,
+            leader: Decode::decode(input)?,
+        })
+    }
+}
+
+// impl Encode and Decode trait for SignedVote
+impl Encode for SignedVote {
+    fn encode_to<W: Output + ?Sized>(&self, dest: &mut W) {
+        self.signature.encode_to(dest);
+        self.vote.encode_to(dest);
+        self.voter.encode_to(dest);
+    }
+}
+
+impl Decode for SignedVote {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        Ok(SignedVote {
+            signature: Decode::decode(input)?,
+            vote: Decode::decode(input)?,
+            voter: Decode::decode(input)?,
+        })
+    }
+}
+
+// impl Encode and Decode trait for Vote
+impl Encode for Vote {
+    fn encode_to<W: Output + ?Sized>(&self, dest: &mut W) {
+        self.height.encode_to(dest);
+        self.round.encode_to(dest);
+        self.vote_type.encode_to(dest);
+        self.block_hash.encode_to(dest);
+    }
+}
+
+impl Decode for Vote {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        Ok(Vote {
+            height: Decode::decode(input)?,
+            round: Decode::decode(input)?,
+            vote_type: Decode::decode(input)?,
+            block_hash: Decode::decode(input)?,
+        })
+    }
+}
+
+// impl Encode and Decode trait for DurationConfig
+impl Encode for DurationConfig {
+    fn encode_to<W: Output + ?Sized>(&self, dest: &mut W) {
+        self.propose_ratio.encode_to(dest);
+        self.prevote_ratio.encode_to(dest);
+        self.precommit_ratio.encode_to(dest);
+        self.brake_ratio.encode_to(dest);
+    }
+}
+
+impl Decode for DurationConfig {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        Ok(DurationConfig {
+            propose_ratio: Decode::decode(input)?,
+            prevote_ratio: Decode::decode(input)?,
+            precommit_ratio: Decode::decode(input)?,
+            brake_ratio: Decode::decode(input)?,
+        })
+    }
+}
+
+// impl Encode and Decode trait for Status
+impl Encode for Status {
+    fn encode_to<W: Output + ?Sized>(&self, dest: &mut W) {
+        self.height.encode_to(dest);
+        self.interval.encode_to(dest);
+        self.timer_config.encode_to(dest);
+        self.authority_list.encode_to(dest);
+    }
+}
+
+impl Decode for Status {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        Ok(Status {
+            height: Decode::decode(input)?,
+            interval: Decode::decode(input)?,
+            timer_config: Decode::decode(input)?,
+            authority_list: Decode::decode(input)?,
+        })
+    }
+}
+
+// impl Encode and Decode trait for Node
+impl Encode for Node {
+    fn encode_to<W: Output + ?Sized>(&self, dest: &mut W) {
+        self.address.encode_to(dest);
+        self.propose_weight.encode_to(dest);
+        self.vote_weight.encode_to(dest);
+    }
+}
+
+impl Decode for Node {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        Ok(Node {
+            address: Decode::decode(input)?,
+            propose_weight: Decode::decode(input)?,
+            vote_weight: Decode::decode(input)?,
+        })
+    }
+}
+
+// impl Encode and Decode trait for SignedChoke
+impl Encode for SignedChoke {
+    fn encode_to<W: Output + ?Sized>(&self, dest: &mut W) {
+        self.signature.encode_to(dest);
+        self.choke.encode_to(dest);
+        self.address.encode_to(dest);
+    }
+}
+
+impl Decode for SignedChoke {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        Ok(SignedChoke {
+            signature: Decode::decode(input)?,
+            choke: Decode::decode(input)?,
+            address: Decode::decode(input)?,
+        })
+    }
+}
+
+// impl Encode and Decode trait for Choke
+impl Encode for Choke {
+    fn encode_to<W: Output + ?Sized>(&self, dest: &mut W) {
+        self.height.encode_to(dest);
+        self.round.encode_to(dest);
+        self.from.encode_to(dest);
+    }
+}
+
+impl Decode for Choke {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        Ok(Choke {
+            height: Decode::decode(input)?,
+            round: Decode::decode(input)?,
+            from: Decode::decode(input)?,
+        })
+    }
+}
+
+// impl Encode and Decode trait for UpdateFrom
+impl Encode for UpdateFrom {
+    fn encode_to<W: Output + ?Sized>(&self, dest: &mut W) {
+        match self {
+            UpdateFrom::PrevoteQC(qc) => {
+                dest.push_byte(0);
+                qc.encode_to(dest);
+            }
+            UpdateFrom::PrecommitQC(qc) => {
+                dest.push_byte(1);
+                qc.encode_to(dest);
+            }
+            UpdateFrom::ChokeQC(qc) => {
+                dest.push_byte(2);
+                qc.encode_to(dest);
+            }
+        }
+    }
+}
+
+impl Decode for UpdateFrom {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        match input.read_byte()? {
+            0 => Ok(UpdateFrom::PrevoteQC(Decode::decode(input)?)),
+            1 => Ok(UpdateFrom::PrecommitQC(Decode::decode(input)?)),
+            2 => Ok(UpdateFrom::ChokeQC(Decode::decode(input)?)),
+            _ => Err("Invalid update from tag.".into()),
+        }
+    }
+}
+
+// impl Encode and Decode trait for AggregatedChoke
+impl Encode for AggregatedChoke {
+    fn encode_to<W: Output + ?Sized>(&self, dest: &mut W) {
+        self.height.encode_to(dest);
+        self.round.encode_to(dest);
+        self.signature.encode_to(dest);
+        self.voters.encode_to(dest);
+    }
+}
+
+impl Decode for AggregatedChoke {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        Ok(AggregatedChoke {
+            height: Decode::decode(input)?,
+            round: Decode::decode(input)?,
+            signature: Decode::decode(input)?,
+            voters: Decode::decode(input)?,
+        })
+    }
+}
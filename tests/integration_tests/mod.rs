@@ -4,12 +4,15 @@ mod run;
 mod utils;
 mod wal;
 
-// use std::fs;
+use std::fs;
+
+use overlord::sim::Scenario;
 
 use run::run_test;
 use wal::Record;
 
 const TEST_CASE_DIR: &str = "./tests/integration_tests/test_case/";
+const SCENARIO_DIR: &str = "./tests/integration_tests/scenario/";
 
 #[tokio::test(flavor = "multi_thread")]
 async fn test_1_wal() {
@@ -46,3 +49,13 @@ async fn test_4_wal() {
 //         run_test(Record::load(&path.display().to_string()), 10, 10).await
 //     }
 // }
+
+#[test]
+fn test_scenarios() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    for entry in fs::read_dir(SCENARIO_DIR).unwrap() {
+        let scenario = Scenario::load(entry.unwrap().path()).unwrap();
+        let report = scenario.run();
+        assert_eq!(report.check(), Ok(()), "scenario {}", scenario.name);
+    }
+}
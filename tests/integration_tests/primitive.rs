@@ -11,7 +11,7 @@ use serde::{Deserialize, Serialize};
 
 use overlord::error::ConsensusError;
 use overlord::types::{Commit, Hash, Node, OverlordMsg, Status, ViewChangeReason};
-use overlord::{Codec, Consensus, DurationConfig, Overlord, OverlordHandler};
+use overlord::{Codec, Consensus, Overlord, OverlordConfig, OverlordHandler};
 
 use super::crypto::MockCrypto;
 use super::utils::{gen_random_bytes, hash, timer_config, to_hex};
@@ -213,6 +213,11 @@ impl Participant {
             Arc::clone(&adapter),
             Arc::new(crypto),
             Arc::new(records.wal_record.get(address).unwrap().clone()),
+            OverlordConfig::builder()
+                .interval(records.interval)
+                .timer_config(timer_config().unwrap())
+                .build()
+                .unwrap(),
         );
         let overlord_handler = overlord.get_handler();
 
@@ -235,12 +240,7 @@ impl Participant {
         }
     }
 
-    pub async fn run(
-        &self,
-        interval: u64,
-        timer_config: Option<DurationConfig>,
-        node_list: Vec<Node>,
-    ) -> Result<(), Box<dyn Error + Send>> {
+    pub async fn run(&self, node_list: Vec<Node>) -> Result<(), Box<dyn Error + Send>> {
         let adapter = Arc::<Adapter>::clone(&self.adapter);
         let handler = self.handler.clone();
 
@@ -269,10 +269,7 @@ impl Participant {
             }
         });
 
-        self.overlord
-            .run(1, interval, node_list, timer_config)
-            .await
-            .unwrap();
+        self.overlord.run(1, node_list).await.unwrap();
 
         Ok(())
     }
@@ -79,7 +79,6 @@ fn run_alive_nodes(
     alive_nodes: Vec<Node>,
 ) -> (Vec<Arc<Participant>>, Vec<Sender<OverlordMsg<Block>>>) {
     let records = records.as_internal();
-    let interval = records.interval;
     let alive_num = alive_nodes.len();
 
     let channels: Vec<Channel> = (0..alive_num).map(|_| unbounded()).collect();
@@ -110,7 +109,7 @@ fn run_alive_nodes(
 
         let list = records.node_record.clone();
         tokio::spawn(async move {
-            node.run(interval, timer_config(), list).await.unwrap();
+            node.run(list).await.unwrap();
         });
     }
     (
@@ -88,6 +88,22 @@ impl Consensus<Pill> for ConsensusHelper<Pill> {
         Ok(status)
     }
 
+    async fn on_observed_commit(
+        &self,
+        _ctx: Context,
+        height: u64,
+        commit: Commit<Pill>,
+    ) -> Result<Option<Status>, Box<dyn Error + Send>> {
+        self.commit_tx.send(commit).unwrap();
+        let status = Status {
+            height: height + 1,
+            interval: None,
+            timer_config: None,
+            authority_list: self.auth_list.clone(),
+        };
+        Ok(Some(status))
+    }
+
     async fn get_authority_list(
         &self,
         _ctx: Context,